@@ -1,13 +1,92 @@
-use crate::types::{TradeInfo, WalletMetrics};
-use chrono::{DateTime, Utc};
+use crate::analysis::pricing::PriceSource;
+use crate::ingestion::balances::WRAPPED_SOL_MINT;
+use crate::types::{short_mint, CapitalFlow, TradeInfo, WalletMetrics};
+use chrono::Utc;
+use std::collections::HashMap;
 
+#[derive(Default)]
 pub struct MetricsCalculator;
 
+/// Open position in a single mint, tracked at average cost.
+#[derive(Default)]
+struct Position {
+    quantity: f64,
+    cost_basis: f64,
+}
+
+impl Position {
+    fn buy(&mut self, quantity: f64, cost: f64) {
+        self.quantity += quantity;
+        self.cost_basis += cost;
+    }
+
+    /// Sells `quantity` for `proceeds`, returning the profit/loss on the part
+    /// of it that was held.
+    fn sell(&mut self, quantity: f64, proceeds: f64) -> f64 {
+        if self.quantity <= 0.0 || quantity <= 0.0 {
+            return 0.0;
+        }
+        let sold = quantity.min(self.quantity);
+        let basis = self.cost_basis * sold / self.quantity;
+        self.quantity -= sold;
+        self.cost_basis -= basis;
+        proceeds * sold / quantity - basis
+    }
+}
+
 impl MetricsCalculator {
     pub fn new() -> Self {
         Self
     }
 
+    /// Values swaps with no SOL leg at the SOL price of the token sold, or
+    /// failing that the token bought, and records the SOL/USD price at the
+    /// time of every trade.
+    pub fn value_trades(&self, trades: &mut [TradeInfo], prices: &impl PriceSource) {
+        for trade in trades.iter_mut() {
+            trade.sol_usd_price = prices.sol_usd(trade.timestamp);
+            if trade.failed || trade.amount > 0.0 {
+                continue;
+            }
+            if let Some(value) = prices.price(&trade.token_in_mint, trade.timestamp)
+                .map(|price| price * trade.token_in_amount)
+                .or_else(|| prices.price(&trade.token_out_mint, trade.timestamp).map(|price| price * trade.token_out_amount))
+            {
+                trade.amount = value;
+            }
+        }
+    }
+
+    /// Fills in `profit_loss` for a single wallet's trades, ordered oldest first.
+    ///
+    /// Buys (SOL in, token out) open or grow a position at average cost; sells
+    /// (token in, SOL out) realize the proceeds against that cost. A valued
+    /// token-to-token swap is a sale of one token and a buy of the other at
+    /// its value. Tokens sold without a known buy, and token-to-token swaps
+    /// that could not be valued, realize nothing.
+    pub fn apply_realized_pnl(&self, trades: &mut [TradeInfo]) {
+        let mut positions: HashMap<String, Position> = HashMap::new();
+
+        for trade in trades.iter_mut() {
+            if trade.token_in_mint == WRAPPED_SOL_MINT {
+                positions.entry(trade.token_out_mint.clone()).or_default()
+                    .buy(trade.token_out_amount, trade.token_in_amount);
+                trade.profit_loss = 0.0;
+            } else if trade.token_out_mint == WRAPPED_SOL_MINT {
+                trade.profit_loss = positions.get_mut(&trade.token_in_mint)
+                    .map_or(0.0, |position| position.sell(trade.token_in_amount, trade.token_out_amount));
+            } else if trade.amount > 0.0 {
+                trade.profit_loss = positions.get_mut(&trade.token_in_mint)
+                    .map_or(0.0, |position| position.sell(trade.token_in_amount, trade.amount));
+                positions.entry(trade.token_out_mint.clone()).or_default()
+                    .buy(trade.token_out_amount, trade.amount);
+            }
+        }
+    }
+
+    /// Aggregates a wallet's history. Failed transactions count only
+    /// towards costs; net profit/loss is realized profit/loss less the fees
+    /// and tips of every transaction.
     pub fn calculate_metrics(
         &self,
         trades: &[TradeInfo],
@@ -17,8 +96,15 @@ impl MetricsCalculator {
         }
 
         let wallet_address = trades[0].wallet_address.clone();
+        let total_costs: f64 = trades.iter().map(TradeInfo::costs).sum();
+        let net_profit_loss_usd = trades.iter()
+            .map(|t| t.sol_usd_price.map(|price| (t.profit_loss - t.costs()) * price))
+            .sum::<Option<f64>>();
+        let failed_transaction_count = trades.iter().filter(|t| t.failed).count() as u64;
+
+        let trades: Vec<_> = trades.iter().filter(|t| !t.failed).collect();
         let total_trades = trades.len() as u64;
-        
+
         let total_profit_loss: f64 = trades.iter()
             .map(|t| t.profit_loss)
             .sum();
@@ -27,11 +113,14 @@ impl MetricsCalculator {
             .filter(|t| t.profit_loss > 0.0)
             .count();
 
-        let win_rate = (profitable_trades as f64) / (total_trades as f64) * 100.0;
-
-        let avg_trade_size = trades.iter()
-            .map(|t| t.amount)
-            .sum::<f64>() / (total_trades as f64);
+        let (win_rate, avg_trade_size) = if total_trades > 0 {
+            (
+                (profitable_trades as f64) / (total_trades as f64) * 100.0,
+                trades.iter().map(|t| t.amount).sum::<f64>() / (total_trades as f64),
+            )
+        } else {
+            (0.0, 0.0)
+        };
 
         Ok(WalletMetrics {
             address: wallet_address,
@@ -40,6 +129,54 @@ impl MetricsCalculator {
             avg_trade_size,
             trade_count: total_trades,
             last_updated: Utc::now().timestamp(),
+            total_costs,
+            net_profit_loss: total_profit_loss - total_costs,
+            net_profit_loss_usd,
+            failed_transaction_count,
+            capital_in: 0.0,
+            capital_out: 0.0,
+            roi: None,
+            most_traded_token: most_traded_token(&trades),
         })
     }
-}
\ No newline at end of file
+
+    /// Adds a wallet's deposits, withdrawals and transfers to its metrics.
+    /// They never affect profit/loss; SOL flows set the capital in and out,
+    /// and ROI is net profit/loss over the capital put in.
+    pub fn apply_capital_flows(&self, metrics: &mut WalletMetrics, flows: &[CapitalFlow]) {
+        let sol_flows = || flows.iter().filter(|flow| flow.mint == WRAPPED_SOL_MINT).map(|flow| flow.amount);
+        metrics.capital_in = sol_flows().filter(|amount| *amount > 0.0).sum();
+        metrics.capital_out = -sol_flows().filter(|amount| *amount < 0.0).sum::<f64>();
+        metrics.roi = (metrics.capital_in > 0.0).then(|| metrics.net_profit_loss / metrics.capital_in * 100.0);
+    }
+}
+
+/// Label of the non-SOL token appearing in the most trades, ties going to
+/// the lowest mint address.
+fn most_traded_token(trades: &[&TradeInfo]) -> String {
+    // mint -> (trade count, symbol)
+    let mut tokens: HashMap<&str, (usize, &str)> = HashMap::new();
+    for trade in trades {
+        for (mint, symbol) in [
+            (&trade.token_in_mint, &trade.token_in_symbol),
+            (&trade.token_out_mint, &trade.token_out_symbol),
+        ] {
+            if mint.is_empty() || mint == WRAPPED_SOL_MINT {
+                continue;
+            }
+            let entry = tokens.entry(mint).or_insert((0, ""));
+            entry.0 += 1;
+            if !symbol.is_empty() {
+                entry.1 = symbol;
+            }
+        }
+    }
+
+    tokens.into_iter()
+        .max_by(|(mint_a, (count_a, _)), (mint_b, (count_b, _))| count_a.cmp(count_b).then(mint_b.cmp(mint_a)))
+        .map(|(mint, (_, symbol))| match symbol {
+            "" => short_mint(mint),
+            symbol => symbol.to_string(),
+        })
+        .unwrap_or_default()
+}
@@ -1,3 +1,7 @@
+pub mod metrics;
+pub mod pricing;
+pub mod ranking;
+
 use crate::types::WalletMetrics;
 
 pub async fn calculate_wallet_metrics(wallet_address: &str) -> Result<WalletMetrics, Box<dyn std::error::Error>> {
@@ -9,6 +13,7 @@ pub async fn calculate_wallet_metrics(wallet_address: &str) -> Result<WalletMetr
         avg_trade_size: 0.0,
         trade_count: 0,
         last_updated: chrono::Utc::now().timestamp(),
+        ..Default::default()
     })
 }
 
@@ -0,0 +1,254 @@
+use crate::ingestion::balances::WRAPPED_SOL_MINT;
+use crate::types::{Candle, OraclePrice, TradeInfo};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+/// USDC, whose swaps against SOL give the SOL/USD price.
+pub const USDC_MINT: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+/// Candle length in seconds.
+pub const DEFAULT_CANDLE_INTERVAL: i64 = 60;
+/// How long after its last trade a candle's close still counts as the price.
+pub const DEFAULT_MAX_PRICE_AGE: i64 = 3600; // 1 hour
+/// Widest oracle confidence interval trusted, as a fraction of the price.
+pub const DEFAULT_MAX_ORACLE_CONFIDENCE: f64 = 0.01;
+const DEFAULT_MAX_DEVIATION: f64 = 0.2;
+const DEFAULT_MIN_VOLUME: f64 = 0.01; // SOL
+
+/// Price of a mint implied by a single swap against SOL.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PriceObservation {
+    pub mint: String,
+    /// Transaction of the swap, which keys the observation in storage.
+    #[serde(default)]
+    pub transaction_hash: String,
+    pub timestamp: i64,
+    /// SOL per UI unit.
+    pub price: f64,
+    /// SOL side of the swap, which the observation is weighted by.
+    pub volume: f64,
+}
+
+/// Implied prices of the other leg of every successful swap against SOL.
+/// Swaps between two tokens say nothing about either's SOL price.
+pub fn observations(trades: &[TradeInfo]) -> Vec<PriceObservation> {
+    trades.iter()
+        .filter(|trade| !trade.failed && trade.amount > 0.0)
+        .filter_map(|trade| {
+            let (mint, quantity) = if trade.token_in_mint == WRAPPED_SOL_MINT {
+                (&trade.token_out_mint, trade.token_out_amount)
+            } else if trade.token_out_mint == WRAPPED_SOL_MINT {
+                (&trade.token_in_mint, trade.token_in_amount)
+            } else {
+                return None;
+            };
+            (quantity > 0.0 && !mint.is_empty()).then(|| PriceObservation {
+                mint: mint.clone(),
+                transaction_hash: trade.transaction_hash.clone(),
+                timestamp: trade.timestamp,
+                price: trade.amount / quantity,
+                volume: trade.amount,
+            })
+        })
+        .collect()
+}
+
+/// Turns observed swaps into per-mint candles.
+#[derive(Debug, Clone)]
+pub struct PriceOracle {
+    /// Candle length in seconds.
+    pub interval: i64,
+    /// Furthest an observation's price may stray from the volume-weighted
+    /// median of its interval, as a fraction of that median.
+    pub max_deviation: f64,
+    /// Smallest swap, in SOL, that is priced at all; dust swaps are cheap
+    /// to print at any price.
+    pub min_volume: f64,
+}
+
+impl Default for PriceOracle {
+    fn default() -> Self {
+        Self {
+            interval: DEFAULT_CANDLE_INTERVAL,
+            max_deviation: DEFAULT_MAX_DEVIATION,
+            min_volume: DEFAULT_MIN_VOLUME,
+        }
+    }
+}
+
+impl PriceOracle {
+    /// Start of the candle containing `timestamp`.
+    pub fn bucket(&self, timestamp: i64) -> i64 {
+        timestamp - timestamp.rem_euclid(self.interval)
+    }
+
+    /// Drops dust swaps, then swaps priced too far from the volume-weighted
+    /// median of their mint and interval, so a thin trade against a drained
+    /// pool cannot move the price on its own. Returns the rest ordered by
+    /// mint and time.
+    pub fn filter_outliers(&self, observations: Vec<PriceObservation>) -> Vec<PriceObservation> {
+        let mut groups: HashMap<(String, i64), Vec<PriceObservation>> = HashMap::new();
+        for observation in observations.into_iter().filter(|o| o.volume >= self.min_volume && o.price > 0.0) {
+            groups.entry((observation.mint.clone(), self.bucket(observation.timestamp)))
+                .or_default()
+                .push(observation);
+        }
+
+        let mut kept: Vec<_> = groups.into_values()
+            .flat_map(|group| {
+                let median = weighted_median(&group);
+                group.into_iter().filter(move |o| (o.price - median).abs() <= self.max_deviation * median)
+            })
+            .collect();
+        kept.sort_by(|a, b| a.mint.cmp(&b.mint).then(a.timestamp.cmp(&b.timestamp)));
+        kept
+    }
+
+    /// Aggregates observations into candles, outliers filtered out, ordered
+    /// by mint and start.
+    pub fn candles(&self, observations: Vec<PriceObservation>) -> Vec<Candle> {
+        let mut candles: HashMap<(String, i64), Candle> = HashMap::new();
+        for observation in self.filter_outliers(observations) {
+            let start = self.bucket(observation.timestamp);
+            let candle = Candle {
+                mint: observation.mint.clone(),
+                start,
+                open: observation.price,
+                high: observation.price,
+                low: observation.price,
+                close: observation.price,
+                volume: observation.volume,
+                trade_count: 1,
+                first_trade: observation.timestamp,
+                last_trade: observation.timestamp,
+            };
+            match candles.entry((observation.mint, start)) {
+                Entry::Occupied(mut existing) => existing.get_mut().merge(&candle),
+                Entry::Vacant(slot) => {
+                    slot.insert(candle);
+                }
+            }
+        }
+
+        let mut candles: Vec<_> = candles.into_values().collect();
+        candles.sort_by(|a, b| a.mint.cmp(&b.mint).then(a.start.cmp(&b.start)));
+        candles
+    }
+}
+
+/// Price at which half the observed volume traded lower and half higher.
+fn weighted_median(observations: &[PriceObservation]) -> f64 {
+    let mut sorted: Vec<_> = observations.iter().collect();
+    sorted.sort_by(|a, b| a.price.total_cmp(&b.price));
+    let half = sorted.iter().map(|o| o.volume).sum::<f64>() / 2.0;
+
+    let mut cumulative = 0.0;
+    for observation in &sorted {
+        cumulative += observation.volume;
+        if cumulative >= half {
+            return observation.price;
+        }
+    }
+    sorted.last().map_or(0.0, |o| o.price)
+}
+
+/// Answers the price of a mint at a point in time.
+pub trait PriceSource {
+    /// SOL per UI unit of `mint` at `timestamp`, if known.
+    fn price(&self, mint: &str, timestamp: i64) -> Option<f64>;
+
+    /// USD per SOL at `timestamp`, the inverse of USDC's SOL price.
+    fn sol_usd(&self, timestamp: i64) -> Option<f64> {
+        self.price(USDC_MINT, timestamp)
+            .filter(|price| *price > 0.0)
+            .map(|price| 1.0 / price)
+    }
+}
+
+/// Candles of several mints, pricing each at the close of its latest candle
+/// starting at or before the requested time. Prices are only as fine as the
+/// candle interval, and go stale once no trade was seen for `max_age`.
+///
+/// Oracle prices take precedence: the latest one published at or before the
+/// requested time, within `max_age`, is used as long as its confidence
+/// interval is narrow enough. Oracle prices are in USD and are converted at the SOL/USD
+/// price, itself taken from the SOL oracle or else from USDC swaps.
+#[derive(Debug, Clone)]
+pub struct PriceHistory {
+    candles: HashMap<String, Vec<Candle>>,
+    oracle_prices: HashMap<String, Vec<OraclePrice>>,
+    max_age: i64,
+    max_confidence: f64,
+}
+
+impl PriceHistory {
+    pub fn new(max_age: i64) -> Self {
+        Self {
+            candles: HashMap::new(),
+            oracle_prices: HashMap::new(),
+            max_age,
+            max_confidence: DEFAULT_MAX_ORACLE_CONFIDENCE,
+        }
+    }
+
+    /// Sets the widest oracle confidence interval trusted, as a fraction of
+    /// the price.
+    pub fn with_max_confidence(mut self, max_confidence: f64) -> Self {
+        self.max_confidence = max_confidence;
+        self
+    }
+
+    /// Adds candles, merging any with the same mint and start.
+    pub fn insert(&mut self, candles: impl IntoIterator<Item = Candle>) {
+        for candle in candles {
+            let series = self.candles.entry(candle.mint.clone()).or_default();
+            match series.binary_search_by_key(&candle.start, |c| c.start) {
+                Ok(index) => series[index].merge(&candle),
+                Err(index) => series.insert(index, candle),
+            }
+        }
+    }
+
+    pub fn insert_oracle_prices(&mut self, prices: impl IntoIterator<Item = OraclePrice>) {
+        for price in prices {
+            self.oracle_prices.entry(price.mint.clone()).or_default().push(price);
+        }
+    }
+
+    /// USD per UI unit of `mint` from the latest trusted oracle price
+    /// published at or before `timestamp`.
+    pub fn oracle_usd(&self, mint: &str, timestamp: i64) -> Option<f64> {
+        self.oracle_prices.get(mint)?.iter()
+            .filter(|quote| quote.price > 0.0 && quote.confidence <= self.max_confidence * quote.price)
+            .filter(|quote| quote.publish_time <= timestamp && timestamp - quote.publish_time <= self.max_age)
+            .max_by_key(|quote| quote.publish_time)
+            .map(|quote| quote.price)
+    }
+
+    /// SOL per UI unit of `mint` implied by swaps.
+    pub fn implied_price(&self, mint: &str, timestamp: i64) -> Option<f64> {
+        let series = self.candles.get(mint)?;
+        let candle = series[..series.partition_point(|c| c.start <= timestamp)].last()?;
+        (timestamp - candle.last_trade <= self.max_age).then_some(candle.close)
+    }
+}
+
+impl PriceSource for PriceHistory {
+    fn price(&self, mint: &str, timestamp: i64) -> Option<f64> {
+        if mint == WRAPPED_SOL_MINT {
+            return Some(1.0);
+        }
+        self.oracle_usd(mint, timestamp)
+            .and_then(|usd| self.sol_usd(timestamp).map(|sol_usd| usd / sol_usd))
+            .or_else(|| self.implied_price(mint, timestamp))
+    }
+
+    fn sol_usd(&self, timestamp: i64) -> Option<f64> {
+        self.oracle_usd(WRAPPED_SOL_MINT, timestamp).or_else(|| {
+            self.implied_price(USDC_MINT, timestamp)
+                .filter(|price| *price > 0.0)
+                .map(|price| 1.0 / price)
+        })
+    }
+}
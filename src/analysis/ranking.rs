@@ -1,5 +1,6 @@
 use crate::types::WalletMetrics;
 
+#[derive(Default)]
 pub struct WalletRanker;
 
 impl WalletRanker {
@@ -1,4 +1,15 @@
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use solana_client::rpc_custom_error::{
+    JSON_RPC_SERVER_ERROR_BLOCK_NOT_AVAILABLE, JSON_RPC_SERVER_ERROR_MIN_CONTEXT_SLOT_NOT_REACHED,
+    JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY, JSON_RPC_SERVER_ERROR_TRANSACTION_HISTORY_NOT_AVAILABLE,
+};
+use solana_client::rpc_request::RpcError;
 use std::fmt;
+use std::time::Duration;
+use crate::ingestion::sender::HttpStatusError;
+
+/// JSON-RPC internal error, returned by overloaded nodes.
+const JSON_RPC_INTERNAL_ERROR: i64 = -32603;
 
 #[derive(Debug)]
 pub enum AnalyzerError {
@@ -7,6 +18,60 @@ pub enum AnalyzerError {
     ParseError(String),
     NetworkError(String),
     DataError(String),
+    /// A server-side failure that may succeed when retried: 5xx responses
+    /// or a node that is unhealthy or behind.
+    TransientError(String),
+    /// HTTP 429, with the delay the server asked for if it sent one.
+    RateLimitError(String, Option<Duration>),
+}
+
+impl AnalyzerError {
+    /// Whether retrying the operation may succeed. Network, transient and
+    /// rate limit errors are retryable; all other variants are permanent.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            AnalyzerError::NetworkError(_) | AnalyzerError::TransientError(_) | AnalyzerError::RateLimitError(..)
+        )
+    }
+
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            AnalyzerError::RateLimitError(_, retry_after) => *retry_after,
+            _ => None,
+        }
+    }
+
+    /// Classifies an RPC client error as transient, rate limited or permanent.
+    pub fn from_client_error(error: &ClientError) -> Self {
+        let message = error.to_string();
+        match error.kind() {
+            ClientErrorKind::Io(io_error) => {
+                match io_error.get_ref().and_then(|e| e.downcast_ref::<HttpStatusError>()) {
+                    Some(status) if status.status == 429 => AnalyzerError::RateLimitError(message, status.retry_after),
+                    Some(status) if status.status >= 500 || status.status == 408 => AnalyzerError::TransientError(message),
+                    Some(_) => AnalyzerError::SolanaClientError(message),
+                    None => AnalyzerError::NetworkError(message),
+                }
+            }
+            ClientErrorKind::Reqwest(reqwest_error) => match reqwest_error.status() {
+                Some(status) if status.as_u16() == 429 => AnalyzerError::RateLimitError(message, None),
+                Some(status) if status.is_server_error() => AnalyzerError::TransientError(message),
+                Some(_) => AnalyzerError::SolanaClientError(message),
+                None => AnalyzerError::NetworkError(message),
+            },
+            ClientErrorKind::RpcError(RpcError::RpcResponseError {
+                code:
+                    JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY
+                    | JSON_RPC_SERVER_ERROR_BLOCK_NOT_AVAILABLE
+                    | JSON_RPC_SERVER_ERROR_TRANSACTION_HISTORY_NOT_AVAILABLE
+                    | JSON_RPC_SERVER_ERROR_MIN_CONTEXT_SLOT_NOT_REACHED
+                    | JSON_RPC_INTERNAL_ERROR,
+                ..
+            }) => AnalyzerError::TransientError(message),
+            _ => AnalyzerError::SolanaClientError(message),
+        }
+    }
 }
 
 impl std::error::Error for AnalyzerError {}
@@ -19,13 +84,15 @@ impl fmt::Display for AnalyzerError {
             AnalyzerError::ParseError(msg) => write!(f, "Parse error: {}", msg),
             AnalyzerError::NetworkError(msg) => write!(f, "Network error: {}", msg),
             AnalyzerError::DataError(msg) => write!(f, "Data error: {}", msg),
+            AnalyzerError::TransientError(msg) => write!(f, "Transient error: {}", msg),
+            AnalyzerError::RateLimitError(msg, _) => write!(f, "Rate limited: {}", msg),
         }
     }
 }
 
-impl From<solana_client::client_error::ClientError> for AnalyzerError {
-    fn from(error: solana_client::client_error::ClientError) -> Self {
-        AnalyzerError::SolanaClientError(error.to_string())
+impl From<ClientError> for AnalyzerError {
+    fn from(error: ClientError) -> Self {
+        AnalyzerError::from_client_error(&error)
     }
 }
 
@@ -0,0 +1,121 @@
+use solana_sdk::{pubkey, pubkey::Pubkey};
+use std::collections::{BTreeSet, HashMap};
+use crate::ingestion::balances::{owner_balance_changes, BalanceChange};
+use crate::ingestion::decoders::has_discriminator;
+use crate::ingestion::flows::token_owners;
+use crate::ingestion::transaction::TransactionWithMeta;
+
+pub const SQUADS_V4_PROGRAM: Pubkey = pubkey!("SQDS4ep65T869zMMBKyuUq6aD6EgTu8psMjkvj52pCf");
+pub const SQUADS_V3_PROGRAM: Pubkey = pubkey!("SMPLecH534NA9acpos4G6x7uf3LWbCAwZQE9e8ZekMu");
+
+/// Vault a Squads v4 multisig uses unless told otherwise.
+const SQUADS_V4_DEFAULT_VAULT: u32 = 0;
+/// Authority a Squads v3 multisig uses unless told otherwise.
+const SQUADS_V3_DEFAULT_AUTHORITY: u32 = 1;
+
+/// Who paid for a transaction and whose balances it traded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attribution {
+    pub fee_payer: Pubkey,
+    /// Beneficial owner: the wallet whose token and SOL balances moved.
+    pub owner: Pubkey,
+    /// Net balance changes of `owner`.
+    pub changes: Vec<BalanceChange>,
+}
+
+/// Finds the trader of a transaction: the first wallet, in order of
+/// preference, that both lost one mint and gained another.
+///
+/// 1. The fee payer, the common case of a user signing their own swap.
+/// 2. Any other signer, for relayed transactions and bots that pay fees on
+///    behalf of users.
+/// 3. A Squads vault of a multisig the transaction executes through. Vaults
+///    never sign at the transaction level, and other non-signing owners are
+///    usually the pool itself, so only recognised vaults are considered.
+pub fn attribute(transaction: &TransactionWithMeta) -> Option<Attribution> {
+    let account_keys = transaction.account_keys();
+    let fee_payer = *account_keys.get(0)?;
+    let signers = transaction.transaction.message.header().num_required_signatures as usize;
+
+    let traded = |owner: &Pubkey| {
+        let changes = owner_balance_changes(transaction, owner);
+        let two_sided = changes.iter().any(|c| c.amount < 0.0) && changes.iter().any(|c| c.amount > 0.0);
+        two_sided.then_some(Attribution { fee_payer, owner: *owner, changes })
+    };
+
+    let signer_keys = transaction.transaction.message.static_account_keys().iter().take(signers);
+    if let Some(attribution) = signer_keys.filter_map(traded).next() {
+        return Some(attribution);
+    }
+
+    let owners = token_owners(transaction);
+    if owners.is_empty() {
+        return None;
+    }
+    let vaults = squads_vaults(transaction);
+    owners.iter()
+        .filter(|owner| vaults.contains(owner))
+        .find_map(traded)
+}
+
+/// Vault addresses of the multisigs named by top-level Squads instructions,
+/// whose first account is the multisig in both program versions.
+///
+/// Execute instructions carry no vault index; it lives in the transaction
+/// account they execute. The index is read from the create instructions
+/// that carry it, which multisigs with a threshold of one bundle into the
+/// same transaction, and is otherwise assumed to be the default vault.
+fn squads_vaults(transaction: &TransactionWithMeta) -> Vec<Pubkey> {
+    let account_keys = transaction.account_keys();
+
+    // Vault indices named for each multisig, by program
+    let mut multisigs: HashMap<(Pubkey, Pubkey), BTreeSet<u32>> = HashMap::new();
+    for instruction in transaction.transaction.message.instructions() {
+        let Some(program) = transaction.program_id(instruction) else { continue };
+        if *program != SQUADS_V4_PROGRAM && *program != SQUADS_V3_PROGRAM {
+            continue;
+        }
+        let Some(multisig) = instruction.accounts.first().and_then(|&i| account_keys.get(i as usize)) else {
+            continue;
+        };
+        let indices = multisigs.entry((*program, *multisig)).or_default();
+        indices.extend(squads_vault_index(program, &instruction.data));
+    }
+
+    multisigs.into_iter()
+        .flat_map(|((program, multisig), indices)| {
+            let v4 = program == SQUADS_V4_PROGRAM;
+            let default = if v4 { SQUADS_V4_DEFAULT_VAULT } else { SQUADS_V3_DEFAULT_AUTHORITY };
+            let indices = if indices.is_empty() { BTreeSet::from([default]) } else { indices };
+            indices.into_iter().filter_map(move |index| match v4 {
+                true => u8::try_from(index).ok().map(|index| squads_v4_vault(&multisig, index)),
+                false => Some(squads_v3_vault(&multisig, index)),
+            })
+        })
+        .collect()
+}
+
+/// Vault index leading the arguments of a Squads create instruction: v4's
+/// `vault_transaction_create` and `batch_create`, or v3's `create_transaction`.
+fn squads_vault_index(program: &Pubkey, data: &[u8]) -> Option<u32> {
+    if *program == SQUADS_V4_PROGRAM && has_discriminator(data, &[
+        [48, 250, 78, 168, 208, 226, 218, 211], // vault_transaction_create
+        [194, 142, 141, 17, 55, 185, 20, 248],  // batch_create
+    ]) {
+        data.get(8).map(|&index| index as u32)
+    } else if *program == SQUADS_V3_PROGRAM && has_discriminator(data, &[
+        [227, 193, 53, 239, 55, 126, 112, 105], // create_transaction
+    ]) {
+        data.get(8..12).map(|index| u32::from_le_bytes(index.try_into().unwrap()))
+    } else {
+        None
+    }
+}
+
+pub fn squads_v4_vault(multisig: &Pubkey, index: u8) -> Pubkey {
+    Pubkey::find_program_address(&[b"multisig", multisig.as_ref(), b"vault", &[index]], &SQUADS_V4_PROGRAM).0
+}
+
+pub fn squads_v3_vault(multisig: &Pubkey, index: u32) -> Pubkey {
+    Pubkey::find_program_address(&[b"squad", multisig.as_ref(), &index.to_le_bytes(), b"authority"], &SQUADS_V3_PROGRAM).0
+}
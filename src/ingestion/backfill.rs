@@ -0,0 +1,105 @@
+use solana_client::rpc_response::RpcConfirmedTransactionStatusWithSignature;
+use std::fmt;
+use crate::types::SignatureCursor;
+
+/// One end of a backfill range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackfillBound {
+    Slot(u64),
+    /// Unix timestamp in seconds, compared against the transaction's block time.
+    Timestamp(i64),
+}
+
+impl fmt::Display for BackfillBound {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BackfillBound::Slot(slot) => write!(f, "slot:{}", slot),
+            BackfillBound::Timestamp(ts) => write!(f, "time:{}", ts),
+        }
+    }
+}
+
+/// Historical range to analyze. Without an `end` the range extends to the
+/// current tip of the chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackfillRange {
+    pub start: BackfillBound,
+    pub end: Option<BackfillBound>,
+}
+
+/// Signatures of one `getSignaturesForAddress` page that fall inside the range.
+#[derive(Debug, Clone)]
+pub struct BackfillPage {
+    /// In-range signatures, oldest first.
+    pub signatures: Vec<RpcConfirmedTransactionStatusWithSignature>,
+    /// Oldest signature of the page, to pass as `before` for the next page.
+    pub cursor: Option<SignatureCursor>,
+    /// Whether the walk has gone past the start of the range or the history ran out.
+    pub done: bool,
+}
+
+impl BackfillRange {
+    /// Stable identifier used to key resume cursors, so different ranges
+    /// over the same program do not share progress.
+    pub fn id(&self) -> String {
+        match &self.end {
+            Some(end) => format!("{}-{}", self.start, end),
+            None => format!("{}-tip", self.start),
+        }
+    }
+
+    fn is_before_start(&self, status: &RpcConfirmedTransactionStatusWithSignature) -> bool {
+        match self.start {
+            BackfillBound::Slot(slot) => status.slot < slot,
+            BackfillBound::Timestamp(ts) => status.block_time.is_some_and(|t| t < ts),
+        }
+    }
+
+    fn is_after_end(&self, status: &RpcConfirmedTransactionStatusWithSignature) -> bool {
+        match self.end {
+            None => false,
+            Some(BackfillBound::Slot(slot)) => status.slot > slot,
+            Some(BackfillBound::Timestamp(ts)) => status.block_time.is_some_and(|t| t > ts),
+        }
+    }
+
+    /// Splits a newest-first page of signatures of `requested` size into the
+    /// part inside the range and the position to continue from.
+    pub fn select_page(
+        &self,
+        page: Vec<RpcConfirmedTransactionStatusWithSignature>,
+        requested: usize,
+    ) -> BackfillPage {
+        let cursor = page.last().map(SignatureCursor::from);
+        let done = page.len() < requested || page.iter().any(|s| self.is_before_start(s));
+
+        let mut signatures: Vec<_> = page
+            .into_iter()
+            .filter(|s| !self.is_after_end(s) && !self.is_before_start(s))
+            .collect();
+        signatures.reverse();
+
+        BackfillPage {
+            signatures,
+            cursor,
+            done,
+        }
+    }
+
+    /// Fraction of the range covered when walking back from `first` (the
+    /// newest signature seen) to `current`, if it can be determined.
+    pub fn progress(
+        &self,
+        first: &RpcConfirmedTransactionStatusWithSignature,
+        current: &RpcConfirmedTransactionStatusWithSignature,
+    ) -> Option<f64> {
+        let (top, now, bottom) = match self.start {
+            BackfillBound::Slot(slot) => (first.slot as f64, current.slot as f64, slot as f64),
+            BackfillBound::Timestamp(ts) => (first.block_time? as f64, current.block_time? as f64, ts as f64),
+        };
+        if top <= bottom {
+            return Some(1.0);
+        }
+        Some(((top - now) / (top - bottom)).clamp(0.0, 1.0))
+    }
+}
@@ -0,0 +1,169 @@
+use solana_sdk::{native_token::LAMPORTS_PER_SOL, pubkey::Pubkey, system_program};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::str::FromStr;
+use crate::ingestion::client::TOKEN_PROGRAM_IDS;
+use crate::ingestion::fees::jito_tips;
+use crate::ingestion::transaction::{TokenBalance, TransactionWithMeta};
+
+pub const WRAPPED_SOL_MINT: &str = "So11111111111111111111111111111111111111112";
+
+/// Rent-exempt minimum of a classic SPL token account (165 bytes). Token-2022
+/// accounts with extensions are larger and hold more.
+pub const TOKEN_ACCOUNT_RENT_LAMPORTS: i128 = 2_039_280;
+
+const SYSTEM_CREATE_ACCOUNT: u32 = 0;
+const TOKEN_CLOSE_ACCOUNT: u8 = 9;
+
+/// Net change of one mint's balance for a wallet over a transaction, in UI units.
+/// Native SOL and wrapped SOL are merged under `WRAPPED_SOL_MINT`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BalanceChange {
+    pub mint: String,
+    pub amount: f64,
+}
+
+/// Computes the per-mint balance changes of `owner` from the pre/post SOL and
+/// token balances in the transaction meta. Transaction fees, Jito tips and
+/// the rent the owner put into or got back from token accounts the
+/// transaction opened or closed are excluded.
+///
+/// Token amounts are the raw balances, so a Token-2022 transfer fee withheld
+/// from a received amount is never counted, and interest-bearing mints are
+/// measured in principal rather than interest-adjusted UI amounts.
+pub fn owner_balance_changes(tx: &TransactionWithMeta, owner: &Pubkey) -> Vec<BalanceChange> {
+    let meta = &tx.meta;
+
+    // mint -> (raw delta, decimals)
+    let mut token_deltas: BTreeMap<String, (i128, u8)> = BTreeMap::new();
+    let pre_accounts = owned_accounts(&meta.pre_token_balances, owner);
+    let post_accounts = owned_accounts(&meta.post_token_balances, owner);
+
+    for balance in meta.pre_token_balances.iter().filter(|b| pre_accounts.contains(&b.account_index)) {
+        let entry = token_deltas.entry(balance.mint.clone()).or_insert((0, balance.decimals));
+        entry.0 -= balance.amount as i128;
+    }
+    for balance in meta.post_token_balances.iter().filter(|b| post_accounts.contains(&b.account_index)) {
+        let entry = token_deltas.entry(balance.mint.clone()).or_insert((0, balance.decimals));
+        entry.0 += balance.amount as i128;
+    }
+
+    let mut lamport_delta: i128 = 0;
+    if let Some(index) = tx.account_keys().iter().position(|key| key == owner) {
+        let pre = meta.pre_balances.get(index).copied().unwrap_or_default() as i128;
+        let post = meta.post_balances.get(index).copied().unwrap_or_default() as i128;
+        lamport_delta = post - pre;
+        if index == 0 {
+            lamport_delta += meta.fee as i128;
+        }
+        lamport_delta += jito_tips(tx).iter()
+            .filter(|(payer, _)| payer == owner)
+            .map(|(_, lamports)| *lamports as i128)
+            .sum::<i128>();
+    }
+    lamport_delta += rent_paid(tx).get(owner).copied().unwrap_or(0);
+
+    // Wrapped SOL has the same 9 decimals as lamports
+    let wrapped_delta = token_deltas.remove(WRAPPED_SOL_MINT).map(|(delta, _)| delta).unwrap_or(0);
+    let mut changes: Vec<BalanceChange> = token_deltas
+        .into_iter()
+        .filter(|(_, (delta, _))| *delta != 0)
+        .map(|(mint, (delta, decimals))| BalanceChange {
+            mint,
+            amount: delta as f64 / 10f64.powi(decimals as i32),
+        })
+        .collect();
+
+    let sol_delta = lamport_delta + wrapped_delta;
+    if sol_delta != 0 {
+        changes.push(BalanceChange {
+            mint: WRAPPED_SOL_MINT.to_string(),
+            amount: sol_delta as f64 / LAMPORTS_PER_SOL as f64,
+        });
+    }
+
+    changes
+}
+
+/// Rent each wallet put into token accounts the transaction opened, less
+/// the rent it got back from token accounts the transaction closed, in
+/// lamports. Opened accounts are funded through the system program's
+/// `CreateAccount`; a token program `CloseAccount` refunds its destination.
+/// Accounts opened or closed with no such instruction in view are taken to
+/// be funded by the fee payer and refunded to their owner.
+pub(crate) fn rent_paid(tx: &TransactionWithMeta) -> HashMap<Pubkey, i128> {
+    let meta = &tx.meta;
+    let account_keys = tx.account_keys();
+    let indices = |balances: &[TokenBalance]| balances.iter().map(|b| b.account_index).collect::<HashSet<_>>();
+    let (pre, post) = (indices(&meta.pre_token_balances), indices(&meta.post_token_balances));
+    let mut opened: HashSet<usize> = post.difference(&pre).copied().collect();
+    let mut closed: HashSet<usize> = pre.difference(&post).copied().collect();
+
+    let mut paid = HashMap::new();
+    for instruction in tx.instructions() {
+        let Some(program) = tx.program_id(instruction) else {
+            continue;
+        };
+        let account = |position: usize| instruction.accounts.get(position).map(|&index| index as usize);
+        let (Some(first), Some(second)) = (account(0), account(1)) else {
+            continue;
+        };
+        if *program == system_program::id() {
+            let Some(lamports) = instruction.data.strip_prefix(&SYSTEM_CREATE_ACCOUNT.to_le_bytes()[..])
+                .and_then(|data| Some(u64::from_le_bytes(data.get(..8)?.try_into().ok()?)))
+            else {
+                continue;
+            };
+            if opened.remove(&second) {
+                let rent = account_rent(&meta.post_balances, &meta.post_token_balances, second).min(lamports as i128);
+                if let Some(funder) = account_keys.get(first) {
+                    *paid.entry(*funder).or_insert(0) += rent;
+                }
+            }
+        } else if TOKEN_PROGRAM_IDS.contains(program)
+            && instruction.data.first() == Some(&TOKEN_CLOSE_ACCOUNT)
+            && closed.remove(&first)
+        {
+            let rent = account_rent(&meta.pre_balances, &meta.pre_token_balances, first);
+            if let Some(destination) = account_keys.get(second) {
+                *paid.entry(*destination).or_insert(0) -= rent;
+            }
+        }
+    }
+
+    if let Some(fee_payer) = account_keys.get(0) {
+        for index in opened {
+            *paid.entry(*fee_payer).or_insert(0) += account_rent(&meta.post_balances, &meta.post_token_balances, index);
+        }
+    }
+    for index in closed {
+        let owner = meta.pre_token_balances.iter()
+            .find(|balance| balance.account_index == index)
+            .and_then(|balance| Pubkey::from_str(balance.owner.as_deref()?).ok());
+        if let Some(owner) = owner {
+            *paid.entry(owner).or_insert(0) -= account_rent(&meta.pre_balances, &meta.pre_token_balances, index);
+        }
+    }
+    paid
+}
+
+/// Indices of the token accounts in `balances` owned by `owner`.
+fn owned_accounts(balances: &[TokenBalance], owner: &Pubkey) -> HashSet<usize> {
+    let owner = owner.to_string();
+    balances.iter()
+        .filter(|b| b.owner.as_deref() == Some(owner.as_str()))
+        .map(|b| b.account_index)
+        .collect()
+}
+
+/// Rent held by the token account at `index`: its lamports, less the wrapped
+/// amount for wrapped SOL accounts. Sized per account, since Token-2022
+/// extensions make accounts larger than the classic 165 bytes.
+pub(crate) fn account_rent(lamports: &[u64], token_balances: &[TokenBalance], index: usize) -> i128 {
+    let Some(&lamports) = lamports.get(index) else {
+        return TOKEN_ACCOUNT_RENT_LAMPORTS;
+    };
+    let wrapped = token_balances.iter()
+        .find(|balance| balance.account_index == index && balance.mint == WRAPPED_SOL_MINT)
+        .map_or(0, |balance| balance.amount);
+    lamports as i128 - wrapped as i128
+}
@@ -0,0 +1,33 @@
+use solana_sdk::pubkey::Pubkey;
+use solana_transaction_status::{EncodedConfirmedTransactionWithStatusMeta, UiConfirmedBlock};
+use std::collections::HashSet;
+use crate::error::Result;
+use crate::ingestion::transaction::TransactionWithMeta;
+
+/// Converts the transactions of a block fetched with full transaction
+/// details, tagging each with the block's slot and time and its position.
+pub fn block_transactions(slot: u64, block: UiConfirmedBlock) -> Vec<Result<TransactionWithMeta>> {
+    let block_time = block.block_time;
+    block.transactions
+        .unwrap_or_default()
+        .into_iter()
+        .enumerate()
+        .map(|(index, transaction)| {
+            let mut transaction = TransactionWithMeta::from_encoded(EncodedConfirmedTransactionWithStatusMeta {
+                slot,
+                transaction,
+                block_time,
+            })?;
+            transaction.transaction_index = Some(index as u32);
+            Ok(transaction)
+        })
+        .collect()
+}
+
+/// Whether `transaction` references any of `programs`, among its static keys
+/// or the addresses it loaded from lookup tables. Programs invoked only via
+/// CPI are caught as long as the node reported the loaded addresses, which
+/// `getBlock` does for every v0 transaction.
+pub fn references_any(transaction: &TransactionWithMeta, programs: &HashSet<Pubkey>) -> bool {
+    transaction.account_keys().iter().any(|key| programs.contains(key))
+}
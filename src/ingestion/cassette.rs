@@ -0,0 +1,243 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use solana_client::client_error::{ClientError, ClientErrorKind, Result};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_client::RpcClientConfig;
+use solana_client::rpc_request::{RpcError, RpcRequest, RpcResponseErrorData};
+use solana_client::rpc_sender::{RpcSender, RpcTransportStats};
+use solana_sdk::commitment_config::CommitmentConfig;
+use std::collections::{HashMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::warn;
+use crate::error::AnalyzerError;
+use crate::ingestion::sender::{HttpRpcSender, HttpStatusError};
+
+/// Whether RPC traffic is recorded to or replayed from a cassette file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CassetteMode {
+    /// Forward requests to the endpoints and append every exchange to the file.
+    Record(PathBuf),
+    /// Answer requests from the file without touching the network.
+    Replay(PathBuf),
+}
+
+/// One request and the response it got, stored as a line of JSON.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Interaction {
+    pub method: String,
+    pub params: Value,
+    pub response: RecordedResponse,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordedResponse {
+    Result(Value),
+    Error(RecordedError),
+}
+
+/// A failed request as far as callers can tell failures apart: an RPC error,
+/// an HTTP error status, or a transport failure.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordedError {
+    Rpc { code: i64, message: String },
+    HttpStatus { status: u16, retry_after_ms: Option<u64> },
+    Network(String),
+    Other(String),
+}
+
+impl From<&Result<Value>> for RecordedResponse {
+    fn from(result: &Result<Value>) -> Self {
+        match result {
+            Ok(value) => RecordedResponse::Result(value.clone()),
+            Err(error) => RecordedResponse::Error(error.into()),
+        }
+    }
+}
+
+impl From<&ClientError> for RecordedError {
+    fn from(error: &ClientError) -> Self {
+        match error.kind() {
+            ClientErrorKind::Io(io_error) => match io_error.get_ref().and_then(|e| e.downcast_ref::<HttpStatusError>()) {
+                Some(status) => RecordedError::HttpStatus {
+                    status: status.status,
+                    retry_after_ms: status.retry_after.map(|d| d.as_millis() as u64),
+                },
+                None => RecordedError::Network(io_error.to_string()),
+            },
+            // Timeouts, resets and unreadable bodies from the HTTP sender
+            ClientErrorKind::Reqwest(reqwest_error) => match reqwest_error.status() {
+                Some(status) => RecordedError::HttpStatus { status: status.as_u16(), retry_after_ms: None },
+                None => RecordedError::Network(reqwest_error.to_string()),
+            },
+            ClientErrorKind::RpcError(RpcError::RpcResponseError { code, message, .. }) => RecordedError::Rpc {
+                code: *code,
+                message: message.clone(),
+            },
+            _ => RecordedError::Other(error.to_string()),
+        }
+    }
+}
+
+impl From<RecordedError> for ClientError {
+    fn from(error: RecordedError) -> Self {
+        let kind = match error {
+            RecordedError::Rpc { code, message } => ClientErrorKind::RpcError(RpcError::RpcResponseError {
+                code,
+                message,
+                data: RpcResponseErrorData::Empty,
+            }),
+            RecordedError::HttpStatus { status, retry_after_ms } => ClientErrorKind::Io(io::Error::other(HttpStatusError {
+                status,
+                retry_after: retry_after_ms.map(Duration::from_millis),
+            })),
+            RecordedError::Network(message) => ClientErrorKind::Io(io::Error::other(message)),
+            RecordedError::Other(message) => ClientErrorKind::Custom(message),
+        };
+        kind.into()
+    }
+}
+
+/// An open cassette, shared by the clients of every pooled endpoint.
+#[derive(Clone)]
+pub enum Cassette {
+    Recorder(Arc<Mutex<BufWriter<File>>>),
+    Player(Arc<Mutex<Tape>>),
+}
+
+impl Cassette {
+    /// Recording appends to an existing file; replaying loads it whole.
+    pub fn open(mode: &CassetteMode) -> crate::error::Result<Self> {
+        match mode {
+            CassetteMode::Record(path) => {
+                let file = OpenOptions::new().create(true).append(true).open(path)
+                    .map_err(|e| cassette_error(path, e))?;
+                Ok(Cassette::Recorder(Arc::new(Mutex::new(BufWriter::new(file)))))
+            }
+            CassetteMode::Replay(path) => {
+                let file = File::open(path).map_err(|e| cassette_error(path, e))?;
+                Ok(Cassette::Player(Arc::new(Mutex::new(Tape::read(BufReader::new(file))?))))
+            }
+        }
+    }
+
+    /// An RPC client for `url` that records through or replays from this cassette.
+    pub fn client(&self, url: &str) -> RpcClient {
+        let config = RpcClientConfig::with_commitment(CommitmentConfig::confirmed());
+        match self {
+            Cassette::Recorder(writer) => RpcClient::new_sender(
+                RecordingSender { inner: HttpRpcSender::new(url), writer: Arc::clone(writer) },
+                config,
+            ),
+            Cassette::Player(tape) => RpcClient::new_sender(
+                ReplaySender { url: url.to_string(), tape: Arc::clone(tape) },
+                config,
+            ),
+        }
+    }
+}
+
+fn cassette_error(path: &Path, error: io::Error) -> AnalyzerError {
+    AnalyzerError::DataError(format!("Failed to open cassette {}: {}", path.display(), error))
+}
+
+fn request_key(method: &str, params: &Value) -> String {
+    format!("{} {}", method, params)
+}
+
+/// Recorded responses by request. A request made several times is answered
+/// with its responses in the order they were recorded.
+#[derive(Debug, Default)]
+pub struct Tape {
+    responses: HashMap<String, VecDeque<RecordedResponse>>,
+}
+
+impl Tape {
+    pub fn read<R: BufRead>(reader: R) -> crate::error::Result<Self> {
+        let mut tape = Self::default();
+        for (index, line) in reader.lines().enumerate() {
+            let line = line.map_err(|e| AnalyzerError::DataError(format!("Failed to read cassette: {}", e)))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let interaction: Interaction = serde_json::from_str(&line)
+                .map_err(|e| AnalyzerError::ParseError(format!("cassette line {}: {}", index + 1, e)))?;
+            tape.responses
+                .entry(request_key(&interaction.method, &interaction.params))
+                .or_default()
+                .push_back(interaction.response);
+        }
+        Ok(tape)
+    }
+
+    fn next(&mut self, method: &str, params: &Value) -> Option<RecordedResponse> {
+        self.responses.get_mut(&request_key(method, params))?.pop_front()
+    }
+}
+
+struct RecordingSender {
+    inner: HttpRpcSender,
+    writer: Arc<Mutex<BufWriter<File>>>,
+}
+
+#[async_trait]
+impl RpcSender for RecordingSender {
+    async fn send(&self, request: RpcRequest, params: Value) -> Result<Value> {
+        let result = self.inner.send(request, params.clone()).await;
+
+        let interaction = Interaction {
+            method: request.to_string(),
+            params,
+            response: RecordedResponse::from(&result),
+        };
+        let mut writer = self.writer.lock().unwrap();
+        let written = serde_json::to_writer(&mut *writer, &interaction)
+            .map_err(io::Error::from)
+            .and_then(|_| writeln!(writer))
+            .and_then(|_| writer.flush());
+        if let Err(e) = written {
+            warn!("Failed to record {} to cassette: {}", interaction.method, e);
+        }
+        result
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.inner.get_transport_stats()
+    }
+
+    fn url(&self) -> String {
+        self.inner.url()
+    }
+}
+
+struct ReplaySender {
+    url: String,
+    tape: Arc<Mutex<Tape>>,
+}
+
+#[async_trait]
+impl RpcSender for ReplaySender {
+    async fn send(&self, request: RpcRequest, params: Value) -> Result<Value> {
+        let method = request.to_string();
+        let response = self.tape.lock().unwrap().next(&method, &params);
+        match response {
+            Some(RecordedResponse::Result(value)) => Ok(value),
+            Some(RecordedResponse::Error(error)) => Err(error.into()),
+            None => Err(ClientErrorKind::Custom(format!("No recorded response for {} {}", method, params)).into()),
+        }
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        RpcTransportStats::default()
+    }
+
+    fn url(&self) -> String {
+        self.url.clone()
+    }
+}
@@ -1,132 +1,694 @@
-use solana_client::rpc_client::RpcClient;
+use futures::future::BoxFuture;
+use futures::stream::{self, StreamExt};
+use futures::FutureExt;
+use solana_client::client_error::ClientError;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
 use solana_sdk::{
     commitment_config::CommitmentConfig,
-    transaction::Transaction,
     signature::Signature,
+    pubkey,
     pubkey::Pubkey,
 };
-use solana_client::rpc_config::{RpcTransactionConfig, RpcSignatureSubscribeConfig};
-use tracing::{info, warn, error};
+use solana_client::rpc_config::{RpcBlockConfig, RpcTransactionConfig};
+use solana_client::rpc_request::{MAX_GET_SIGNATURE_STATUSES_QUERY_ITEMS, MAX_MULTIPLE_ACCOUNTS};
+use solana_client::rpc_response::RpcConfirmedTransactionStatusWithSignature;
+use solana_transaction_status::{TransactionDetails, TransactionStatus, UiTransactionEncoding};
+use tracing::warn;
+use std::collections::HashSet;
 use std::str::FromStr;
-use crate::types::TradeInfo;
+use crate::error;
+use crate::ingestion::balances::{BalanceChange, WRAPPED_SOL_MINT};
+use crate::ingestion::blocks::{block_transactions, references_any};
+use crate::ingestion::cassette::{Cassette, CassetteMode};
+use crate::ingestion::cursor::{parse_signature, signatures_since, SignatureBatch};
+use crate::ingestion::decoders::{DecoderRegistry, SwapDecoder};
+use crate::ingestion::fees::TransactionCosts;
+use crate::ingestion::lookup_tables::{parse_lookup_table, LookupTableCache};
+use crate::ingestion::oracles::{parse_price_account, OracleFeed};
+use crate::ingestion::pool::{EndpointStats, RpcEndpoint, RpcPool};
+use crate::ingestion::retry::RetryPolicy;
+use crate::ingestion::transaction::TransactionWithMeta;
+use crate::ingestion::token_metadata::{metadata_address, parse_metaplex_metadata, parse_mint};
+use crate::ingestion::flows::capital_flows;
+use crate::types::{CapitalFlow, OraclePrice, SignatureCursor, TokenMetadata, TradeInfo, WatchCursor};
+
+pub const TOKEN_PROGRAM_ID: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+pub const TOKEN_2022_PROGRAM_ID: Pubkey = pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+/// Programs whose transactions are polled; swaps of Token-2022 mints never
+/// touch the classic Token program.
+pub const TOKEN_PROGRAM_IDS: [Pubkey; 2] = [TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID];
+
+/// Transactions fetched in one polling cycle, and the cursor to resume from
+/// once they have been processed.
+pub struct TransactionBatch {
+    pub transactions: Vec<TransactionWithMeta>,
+    pub cursor: Option<SignatureCursor>,
+}
+
+/// Transactions of a watched wallet fetched in one cycle, and the cursor to
+/// resume from once they have been processed.
+pub struct WalletHistoryBatch {
+    pub transactions: Vec<TransactionWithMeta>,
+    pub cursor: WatchCursor,
+    /// Signatures were left over on either end of the history.
+    pub more: bool,
+}
+
+/// Limits on the load a `SolanaClient` puts on its RPC providers.
+#[derive(Debug, Clone)]
+pub struct ClientConfig {
+    /// Transactions fetched in parallel within a batch.
+    pub max_concurrent_requests: usize,
+    /// Sustained request rate per endpoint across all RPC methods; zero
+    /// disables limiting.
+    pub requests_per_second: f64,
+    /// Requests that may be sent back to back before the rate applies.
+    pub burst: u32,
+    /// Backoff for requests that failed on every endpoint with a retryable error.
+    pub retry: RetryPolicy,
+    /// Record all RPC traffic to, or replay it from, a cassette file.
+    pub cassette: Option<CassetteMode>,
+    /// Commitment transactions are read at; `confirmed` or `finalized`, as
+    /// `getTransaction` and `getBlock` do not serve `processed`. Trades read
+    /// below `finalized` are marked provisional.
+    pub commitment: CommitmentConfig,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent_requests: 16,
+            requests_per_second: 40.0,
+            burst: 16,
+            retry: RetryPolicy::default(),
+            cassette: None,
+            commitment: CommitmentConfig::confirmed(),
+        }
+    }
+}
 
 pub struct SolanaClient {
-    client: RpcClient,
+    pool: RpcPool,
+    decoders: DecoderRegistry,
+    max_concurrent_requests: usize,
+    retry: RetryPolicy,
+    lookup_tables: LookupTableCache,
+    commitment: CommitmentConfig,
 }
 
 impl SolanaClient {
     pub fn new(rpc_url: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        let client = RpcClient::new_with_commitment(
-            rpc_url.to_string(),
-            CommitmentConfig::confirmed(),
-        );
-        Ok(Self { client })
-    }
-
-    pub async fn get_recent_transactions(&self) -> Result<Vec<Transaction>, Box<dyn std::error::Error>> {
-        let signatures = self.client.get_signatures_for_address(
-            &Pubkey::from_str("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA")?,  // Token program
-            Some(RpcSignatureSubscribeConfig {
-                commitment: Some(CommitmentConfig::confirmed()),
-                enable_received_notification: Some(false),
-            }),
-            Some(100),  // Limit to last 100 transactions
-        )?;
+        Self::with_decoders(rpc_url, DecoderRegistry::default())
+    }
 
-        let mut transactions = Vec::new();
-        for sig_info in signatures {
-            match self.client.get_transaction(
-                &sig_info.signature,
-                RpcTransactionConfig {
-                    encoding: None,
-                    commitment: Some(CommitmentConfig::confirmed()),
-                    max_supported_transaction_version: Some(0),
+    pub fn with_decoders(
+        rpc_url: &str,
+        decoders: DecoderRegistry,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::with_config(rpc_url, decoders, ClientConfig::default())
+    }
+
+    pub fn with_config(
+        rpc_url: &str,
+        decoders: DecoderRegistry,
+        config: ClientConfig,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::with_endpoints(vec![RpcEndpoint::new(rpc_url)], decoders, config)
+    }
+
+    /// Spreads requests over several weighted endpoints with failover; see `RpcPool`.
+    pub fn with_endpoints(
+        endpoints: Vec<RpcEndpoint>,
+        decoders: DecoderRegistry,
+        config: ClientConfig,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        if endpoints.is_empty() {
+            return Err("At least one RPC endpoint is required".into());
+        }
+        let pool = match &config.cassette {
+            Some(mode) => {
+                let cassette = Cassette::open(mode)?;
+                let endpoints = endpoints.into_iter()
+                    .map(|endpoint| {
+                        let client = cassette.client(&endpoint.url);
+                        (endpoint, client)
+                    })
+                    .collect();
+                RpcPool::with_clients(endpoints, config.requests_per_second, config.burst)
+            }
+            None => RpcPool::new(endpoints, config.requests_per_second, config.burst),
+        };
+        Ok(Self {
+            pool,
+            decoders,
+            max_concurrent_requests: config.max_concurrent_requests.max(1),
+            retry: config.retry,
+            lookup_tables: LookupTableCache::new(),
+            commitment: config.commitment,
+        })
+    }
+
+    /// A client without RPC endpoints, for decoding transactions read from
+    /// dumps; every RPC call fails. Its trades are not provisional, since
+    /// there is no node to reconcile them against.
+    pub fn offline(decoders: DecoderRegistry) -> Self {
+        let config = ClientConfig::default();
+        Self {
+            pool: RpcPool::new(Vec::new(), config.requests_per_second, config.burst),
+            decoders,
+            max_concurrent_requests: config.max_concurrent_requests,
+            retry: config.retry,
+            lookup_tables: LookupTableCache::new(),
+            commitment: CommitmentConfig::finalized(),
+        }
+    }
+
+    /// Whether this client has no RPC endpoints; see `offline`.
+    pub fn is_offline(&self) -> bool {
+        self.pool.stats().is_empty()
+    }
+
+    pub fn commitment(&self) -> CommitmentConfig {
+        self.commitment
+    }
+
+    /// Whether trades read at this client's commitment can still be rolled back.
+    pub fn is_provisional(&self) -> bool {
+        !self.commitment.is_finalized()
+    }
+
+    /// Request counts, failures, latency and health of each RPC endpoint.
+    pub fn endpoint_stats(&self) -> Vec<EndpointStats> {
+        self.pool.stats()
+    }
+
+    pub fn log_endpoint_stats(&self) {
+        self.pool.log_stats();
+    }
+
+    /// Adds or replaces the decoder for a venue.
+    pub fn register_decoder(&mut self, decoder: Box<dyn SwapDecoder>) {
+        self.decoders.register(decoder);
+    }
+
+    /// Fetches up to `limit` transactions of a token program following
+    /// `cursor`, or the latest `limit` when there is no cursor yet.
+    pub async fn get_recent_transactions(
+        &self,
+        program: &Pubkey,
+        cursor: Option<&SignatureCursor>,
+        limit: u64,
+    ) -> error::Result<TransactionBatch> {
+        let batch = self.get_signatures_since(program, cursor, limit as usize).await?;
+        let signature_strings: Vec<_> = batch.signatures.iter().map(|s| s.signature.clone()).collect();
+
+        Ok(TransactionBatch {
+            cursor: batch.cursor,
+            transactions: self.fetch_transactions(&signature_strings).await?,
+        })
+    }
+
+    /// Up to `limit` signatures for `address` following `cursor`, with the
+    /// cursor to store once they are processed; see `signatures_since`.
+    pub async fn get_signatures_since(
+        &self,
+        address: &Pubkey,
+        cursor: Option<&SignatureCursor>,
+        limit: usize,
+    ) -> error::Result<SignatureBatch> {
+        let until = cursor.map(|c| parse_signature(&c.signature)).transpose()?;
+        signatures_since(cursor, limit, |before, page_size| {
+            self.signatures_for_address(address, before, until, page_size)
+        }).await
+    }
+
+    /// Fetches up to `limit` of `wallet`'s transactions not yet covered by
+    /// `cursor`: first those after its newest signature, then, with what is
+    /// left of `limit`, those before its oldest, walking back until the
+    /// start of the history. The first call starts from the latest `limit`.
+    pub async fn get_wallet_transactions(
+        &self,
+        wallet: &Pubkey,
+        cursor: &WatchCursor,
+        limit: usize,
+    ) -> error::Result<WalletHistoryBatch> {
+        let mut cursor = cursor.clone();
+        let SignatureBatch { signatures: newer, cursor: newest } =
+            self.get_signatures_since(wallet, cursor.newest.as_ref(), limit).await?;
+        if cursor.newest.is_none() {
+            cursor.oldest = newer.first().map(SignatureCursor::from);
+            cursor.complete = newer.len() < limit;
+        }
+        if newest.is_some() {
+            cursor.newest = newest;
+        }
+        let catching_up = cursor.newest.as_ref().is_some_and(|newest| newest.backlog.is_some());
+
+        let remaining = limit - newer.len();
+        let mut older = Vec::new();
+        if !cursor.complete && remaining > 0 {
+            older = self.get_signatures_before(wallet, cursor.oldest.as_ref(), remaining).await?;
+            cursor.complete = older.len() < remaining;
+            if let Some(last) = older.last() {
+                cursor.oldest = Some(SignatureCursor::from(last));
+            }
+        }
+
+        // Pages arrive newest first
+        let signatures: Vec<_> = older.iter().rev().chain(&newer)
+            .map(|s| s.signature.clone())
+            .collect();
+        Ok(WalletHistoryBatch {
+            transactions: self.fetch_transactions(&signatures).await?,
+            more: catching_up || !cursor.complete,
+            cursor,
+        })
+    }
+
+    /// Fetches one page of signatures for `address`, newest first, older than `before`.
+    pub async fn get_signatures_before(
+        &self,
+        address: &Pubkey,
+        before: Option<&SignatureCursor>,
+        limit: usize,
+    ) -> error::Result<Vec<RpcConfirmedTransactionStatusWithSignature>> {
+        let before = before.map(|c| parse_signature(&c.signature)).transpose()?;
+        self.signatures_for_address(address, before, None, limit).await
+    }
+
+    async fn signatures_for_address(
+        &self,
+        address: &Pubkey,
+        before: Option<Signature>,
+        until: Option<Signature>,
+        limit: usize,
+    ) -> error::Result<Vec<RpcConfirmedTransactionStatusWithSignature>> {
+        let address = *address;
+        let commitment = self.commitment;
+        self.request("getSignaturesForAddress", move |client| async move {
+            client.get_signatures_for_address_with_config(
+                &address,
+                GetConfirmedSignaturesForAddress2Config {
+                    before,
+                    until,
+                    limit: Some(limit),
+                    commitment: Some(commitment),
                 },
-            ) {
-                Ok(confirmed_tx) => {
-                    if let Some(tx) = confirmed_tx.transaction {
-                        transactions.push(tx);
-                    }
+            ).await
+        }.boxed()).await
+    }
+
+    /// Latest slot at this client's commitment.
+    pub async fn get_slot(&self) -> error::Result<u64> {
+        self.get_slot_with_commitment(self.commitment).await
+    }
+
+    pub async fn get_slot_with_commitment(&self, commitment: CommitmentConfig) -> error::Result<u64> {
+        self.request("getSlot", move |client| async move {
+            client.get_slot_with_commitment(commitment).await
+        }.boxed()).await
+    }
+
+    /// Up to `limit` slots from `start` on that have a block at this client's
+    /// commitment; skipped slots are left out.
+    pub async fn get_block_slots(&self, start: u64, limit: usize) -> error::Result<Vec<u64>> {
+        let commitment = self.commitment;
+        self.request("getBlocksWithLimit", move |client| async move {
+            client.get_blocks_with_limit_and_commitment(start, limit, commitment).await
+        }.boxed()).await
+    }
+
+    /// Statuses of `signatures`, searching the full ledger history rather
+    /// than only the recent status cache. `None` means the node does not
+    /// know the transaction.
+    pub async fn get_signature_statuses(
+        &self,
+        signatures: &[String],
+    ) -> error::Result<Vec<Option<TransactionStatus>>> {
+        let signatures = signatures.iter()
+            .map(|sig_str| parse_signature(sig_str))
+            .collect::<error::Result<Vec<_>>>()?;
+
+        let mut statuses = Vec::with_capacity(signatures.len());
+        for chunk in signatures.chunks(MAX_GET_SIGNATURE_STATUSES_QUERY_ITEMS) {
+            let chunk = chunk.to_vec();
+            let response = self.request("getSignatureStatuses", move |client| {
+                let chunk = chunk.clone();
+                async move { client.get_signature_statuses_with_history(&chunk).await }.boxed()
+            }).await?;
+            statuses.extend(response.value);
+        }
+        Ok(statuses)
+    }
+
+    /// Fetches the full blocks at `slots` and returns their DEX transactions,
+    /// in block order, with one request per slot and up to
+    /// `max_concurrent_requests` in flight.
+    ///
+    /// Blocks that cannot be fetched or decoded for good are skipped, as are
+    /// their transactions that fail to convert; a block that still fails with
+    /// a retryable error fails the whole call.
+    pub async fn fetch_blocks(&self, slots: &[u64]) -> error::Result<Vec<TransactionWithMeta>> {
+        let programs: HashSet<Pubkey> = self.decoders.program_ids().copied().collect();
+        let blocks: Vec<_> = stream::iter(slots.iter().copied())
+            .map(|slot| self.fetch_block(slot, &programs))
+            .buffered(self.max_concurrent_requests)
+            .collect()
+            .await;
+
+        let mut transactions = Vec::new();
+        for block in blocks {
+            transactions.extend(block?);
+        }
+        self.resolve_lookup_tables(transactions).await
+    }
+
+    async fn fetch_block(&self, slot: u64, programs: &HashSet<Pubkey>) -> error::Result<Vec<TransactionWithMeta>> {
+        let config = RpcBlockConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            transaction_details: Some(TransactionDetails::Full),
+            rewards: Some(false),
+            commitment: Some(self.commitment),
+            max_supported_transaction_version: Some(0),
+        };
+        let block = match self.request("getBlock", move |client| async move {
+            client.get_block_with_config(slot, config).await
+        }.boxed()).await {
+            Ok(block) => block,
+            Err(e) if e.is_retryable() => return Err(e),
+            Err(e) => {
+                warn!("Failed to get block {}: {}", slot, e);
+                return Ok(Vec::new());
+            }
+        };
+
+        let transactions = block_transactions(slot, block).into_iter()
+            .filter_map(|transaction| match transaction {
+                Ok(tx) => references_any(&tx, programs).then_some(tx),
+                Err(e) => {
+                    warn!("Skipping transaction in block {}: {}", slot, e);
+                    None
                 }
+            })
+            .collect();
+        Ok(transactions)
+    }
+
+    /// Sends a request through the endpoint pool, retrying with backoff while
+    /// it keeps failing with a retryable error.
+    async fn request<T, F>(&self, description: &str, request: F) -> error::Result<T>
+    where
+        F: for<'a> Fn(&'a RpcClient) -> BoxFuture<'a, Result<T, ClientError>>,
+    {
+        self.retry.retry(description, || async {
+            Ok(self.pool.call(&request).await?)
+        }).await
+    }
+
+    /// Programs that have a registered swap decoder.
+    pub fn dex_program_ids(&self) -> Vec<Pubkey> {
+        self.decoders.program_ids().copied().collect()
+    }
+
+    /// Fetches each signature's transaction with status meta, preserving order.
+    /// Up to `max_concurrent_requests` fetches are in flight at once.
+    ///
+    /// Transactions that cannot be fetched or decoded for good are skipped;
+    /// if one still fails with a retryable error after all retries, the whole
+    /// call fails so that the caller can try the batch again. Lookup table
+    /// addresses missing from the meta are resolved; see `resolve_lookup_tables`.
+    pub async fn fetch_transactions(
+        &self,
+        signatures: &[String],
+    ) -> error::Result<Vec<TransactionWithMeta>> {
+        let signatures = signatures.iter()
+            .map(|sig_str| parse_signature(sig_str))
+            .collect::<error::Result<Vec<_>>>()?;
+
+        let results: Vec<_> = stream::iter(signatures)
+            .map(|signature| self.fetch_transaction(signature))
+            .buffered(self.max_concurrent_requests)
+            .collect()
+            .await;
+
+        let transactions = results.into_iter().filter_map(Result::transpose).collect::<error::Result<_>>()?;
+        self.resolve_lookup_tables(transactions).await
+    }
+
+    /// Fills in the lookup table addresses of v0 transactions whose meta
+    /// lacks them, from the cache or else from the chain. Transactions whose
+    /// tables cannot be found, e.g. because they were closed since, are
+    /// dropped rather than decoded against the wrong accounts.
+    pub async fn resolve_lookup_tables(
+        &self,
+        mut transactions: Vec<TransactionWithMeta>,
+    ) -> error::Result<Vec<TransactionWithMeta>> {
+        let missing: HashSet<Pubkey> = transactions.iter()
+            .filter(|tx| tx.has_unresolved_lookups())
+            .flat_map(|tx| self.lookup_tables.missing(tx))
+            .collect();
+        if !missing.is_empty() {
+            self.fetch_lookup_tables(missing.into_iter().collect()).await?;
+        }
+
+        transactions.retain_mut(|tx| {
+            let resolved = self.lookup_tables.resolve(tx);
+            if !resolved {
+                warn!("Skipping transaction {}: unresolved address lookup table", tx.signature);
+            }
+            resolved
+        });
+        Ok(transactions)
+    }
+
+    async fn fetch_lookup_tables(&self, tables: Vec<Pubkey>) -> error::Result<()> {
+        for chunk in tables.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let keys = chunk.to_vec();
+            let accounts = match self.request("getMultipleAccounts", move |client| {
+                let keys = keys.clone();
+                async move { client.get_multiple_accounts(&keys).await }.boxed()
+            }).await {
+                Ok(accounts) => accounts,
+                Err(e) if e.is_retryable() => return Err(e),
                 Err(e) => {
-                    warn!("Failed to get transaction {}: {}", sig_info.signature, e);
+                    warn!("Failed to fetch address lookup tables: {}", e);
                     continue;
                 }
+            };
+
+            for (table, account) in chunk.iter().zip(accounts) {
+                match account.map(|account| parse_lookup_table(&account.data)) {
+                    Some(Ok(addresses)) => self.lookup_tables.insert(*table, addresses),
+                    Some(Err(e)) => warn!("Skipping lookup table {}: {}", table, e),
+                    None => warn!("Address lookup table {} not found", table),
+                }
             }
         }
+        Ok(())
+    }
 
-        Ok(transactions)
+    /// Reads the mint accounts of `mints` and their Metaplex metadata. Mints
+    /// that do not exist or are not token mints are left out.
+    pub async fn fetch_token_metadata(&self, mints: &[Pubkey]) -> error::Result<Vec<TokenMetadata>> {
+        let mut resolved = Vec::with_capacity(mints.len());
+        // Each mint is fetched together with its metadata account
+        for chunk in mints.chunks(MAX_MULTIPLE_ACCOUNTS / 2) {
+            let keys: Vec<_> = chunk.iter().copied()
+                .chain(chunk.iter().map(metadata_address))
+                .collect();
+            let accounts = self.request("getMultipleAccounts", move |client| {
+                let keys = keys.clone();
+                async move { client.get_multiple_accounts(&keys).await }.boxed()
+            }).await?;
+            let (mint_accounts, metadata_accounts) = accounts.split_at(chunk.len());
+
+            for ((mint, account), metadata_account) in chunk.iter().zip(mint_accounts).zip(metadata_accounts) {
+                let Some(account) = account else {
+                    warn!("Mint {} not found", mint);
+                    continue;
+                };
+                let mut metadata = match parse_mint(mint, &account.owner, &account.data) {
+                    Ok(metadata) => metadata,
+                    Err(e) => {
+                        warn!("Skipping mint {}: {}", mint, e);
+                        continue;
+                    }
+                };
+                if let (true, Some(account)) = (metadata.symbol.is_empty(), metadata_account) {
+                    match parse_metaplex_metadata(&account.data) {
+                        Ok((name, symbol)) => {
+                            metadata.name = name;
+                            metadata.symbol = symbol;
+                        }
+                        Err(e) => warn!("Ignoring metadata of mint {}: {}", mint, e),
+                    }
+                }
+                resolved.push(metadata);
+            }
+        }
+        Ok(resolved)
     }
 
-    pub async fn get_wallet_transactions(
-        &self,
-        wallet_address: &str,
-        limit: u64,
-    ) -> Result<Vec<Transaction>, Box<dyn std::error::Error>> {
-        let pubkey = Pubkey::from_str(wallet_address)?;
-        let signatures = self.client.get_signatures_for_address(
-            &pubkey,
-            Some(RpcSignatureSubscribeConfig {
-                commitment: Some(CommitmentConfig::confirmed()),
-                enable_received_notification: Some(false),
-            }),
-            Some(limit as usize),
-        )?;
+    /// Reads the current price of each oracle feed. Feeds whose account is
+    /// missing or holds no usable price are logged and skipped.
+    pub async fn fetch_oracle_prices(&self, feeds: &[OracleFeed]) -> error::Result<Vec<OraclePrice>> {
+        let mut prices = Vec::with_capacity(feeds.len());
+        for chunk in feeds.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let keys: Vec<_> = chunk.iter().map(|feed| feed.account).collect();
+            let accounts = self.request("getMultipleAccounts", move |client| {
+                let keys = keys.clone();
+                async move { client.get_multiple_accounts(&keys).await }.boxed()
+            }).await?;
 
-        let mut transactions = Vec::new();
-        for sig_info in signatures {
-            if let Ok(confirmed_tx) = self.client.get_transaction(
-                &sig_info.signature,
-                RpcTransactionConfig {
-                    encoding: None,
-                    commitment: Some(CommitmentConfig::confirmed()),
-                    max_supported_transaction_version: Some(0),
-                },
-            ) {
-                if let Some(tx) = confirmed_tx.transaction {
-                    transactions.push(tx);
+            for (feed, account) in chunk.iter().zip(accounts) {
+                let Some(account) = account else {
+                    warn!("Oracle account {} not found", feed.account);
+                    continue;
+                };
+                match parse_price_account(&feed.mint, &account.owner, &account.data) {
+                    Ok(price) => prices.push(price),
+                    Err(e) => warn!("Skipping oracle account {}: {}", feed.account, e),
                 }
             }
         }
+        Ok(prices)
+    }
 
-        Ok(transactions)
+    async fn fetch_transaction(&self, signature: Signature) -> error::Result<Option<TransactionWithMeta>> {
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            commitment: Some(self.commitment),
+            max_supported_transaction_version: Some(0),
+        };
+        let encoded = match self.request("getTransaction", move |client| async move {
+            client.get_transaction_with_config(&signature, config).await
+        }.boxed()).await {
+            Ok(encoded) => encoded,
+            Err(e) if e.is_retryable() => return Err(e),
+            Err(e) => {
+                warn!("Failed to get transaction {}: {}", signature, e);
+                return Ok(None);
+            }
+        };
+
+        match TransactionWithMeta::from_encoded(encoded) {
+            Ok(tx) => Ok(Some(tx)),
+            Err(e) => {
+                warn!("Skipping transaction {}: {}", signature, e);
+                Ok(None)
+            }
+        }
     }
 
-    pub fn extract_trade_info(&self, transaction: &Transaction) -> Option<TradeInfo> {
-        // This is a simplified implementation that looks for token swaps
-        let timestamp = chrono::Utc::now().timestamp();
-        
-        for instruction in transaction.message.instructions.iter() {
-            // Check if this is a token swap instruction (e.g., Raydium, Orca, or other DEX)
-            if let Some(program_id) = transaction.message.account_keys.get(instruction.program_id_index as usize) {
-                // Common DEX program IDs
-                let dex_programs = [
-                    "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8", // Raydium
-                    "9W959DqEETiGZocYWCQPaJ6sBmUzgfxXfqGeTEdp3aQP", // Orca
-                ];
-
-                if dex_programs.contains(&program_id.to_string().as_str()) {
-                    // Extract wallet address (first account is usually the owner)
-                    if let Some(wallet_key) = transaction.message.account_keys.first() {
-                        // In a real implementation, we would:
-                        // 1. Decode the instruction data to get exact amounts
-                        // 2. Calculate price impact
-                        // 3. Track pre/post token balances
-                        // 4. Calculate actual profit/loss
-                        
-                        // For this PoC, we'll create a simplified trade info
-                        return Some(TradeInfo {
-                            wallet_address: wallet_key.to_string(),
-                            timestamp,
-                            amount: 1.0, // Placeholder
-                            profit_loss: 0.0, // Placeholder
-                            transaction_hash: transaction.signatures[0].to_string(),
-                        });
-                    }
+    /// Decodes the swap a successful transaction made; failed transactions
+    /// yield nothing here, see `extract_failed_swap`.
+    pub fn extract_trade_info(&self, transaction: &TransactionWithMeta) -> Option<TradeInfo> {
+        if transaction.meta.err.is_some() || transaction.has_unresolved_lookups() {
+            return None;
+        }
+        // The first instruction, top-level or CPI, that a registered decoder
+        // recognises as a swap determines the venue
+        transaction.instructions().find_map(|instruction| {
+            let decoder = self.decoders.get(transaction.program_id(instruction)?)?;
+            let mut swap = decoder.decode(transaction, instruction)?;
+            for hop in swap.route.iter_mut() {
+                if let Some(hop_decoder) = Pubkey::from_str(&hop.amm).ok()
+                    .and_then(|amm| self.decoders.get(&amm))
+                {
+                    hop.venue = hop_decoder.venue().to_string();
                 }
             }
+
+            let costs = TransactionCosts::paid_by(transaction, &swap.trader);
+            Some(TradeInfo {
+                wallet_address: swap.trader.to_string(),
+                fee_payer: swap.fee_payer.to_string(),
+                timestamp: transaction.timestamp(),
+                slot: transaction.slot,
+                transaction_index: transaction.transaction_index,
+                provisional: self.is_provisional(),
+                failed: false,
+                base_fee: costs.base_fee,
+                priority_fee: costs.priority_fee,
+                tip: costs.tip,
+                amount: sol_notional(&swap.token_in, &swap.token_out),
+                sol_usd_price: None,
+                profit_loss: 0.0,
+                transaction_hash: transaction.signature.clone(),
+                token_in_mint: swap.token_in.mint.clone(),
+                token_in_amount: -swap.token_in.amount,
+                token_out_mint: swap.token_out.mint.clone(),
+                token_out_amount: swap.token_out.amount,
+                token_in_symbol: String::new(),
+                token_out_symbol: String::new(),
+                venue: decoder.venue().to_string(),
+                decoder_version: decoder.version().to_string(),
+                route: swap.route,
+            })
+        })
+    }
+
+    /// Deposits, withdrawals and transfers made by a transaction that
+    /// involves no registered venue; see `capital_flows`. Transactions that
+    /// do are left to the decoders, even when no swap was recognised.
+    pub fn extract_capital_flows(&self, transaction: &TransactionWithMeta) -> Vec<CapitalFlow> {
+        if transaction.has_unresolved_lookups() {
+            return Vec::new();
+        }
+        let involves_venue = transaction.instructions()
+            .any(|instruction| transaction.program_id(instruction).is_some_and(|program| self.decoders.get(program).is_some()));
+        if involves_venue {
+            return Vec::new();
         }
+        let mut flows = capital_flows(transaction);
+        for flow in &mut flows {
+            flow.provisional = self.is_provisional();
+        }
+        flows
+    }
+
+    /// Records the costs of a failed transaction that attempted a swap on a
+    /// known venue, attributed to its fee payer. Such records carry no legs
+    /// and count only towards the payer's costs.
+    pub fn extract_failed_swap(&self, transaction: &TransactionWithMeta) -> Option<TradeInfo> {
+        if transaction.meta.err.is_none() || transaction.has_unresolved_lookups() {
+            return None;
+        }
+        let decoder = transaction.instructions().find_map(|instruction| {
+            let decoder = self.decoders.get(transaction.program_id(instruction)?)?;
+            decoder.is_swap_instruction(&instruction.data).then_some(decoder)
+        })?;
+        let fee_payer = *transaction.account_keys().get(0)?;
+        let costs = TransactionCosts::paid_by(transaction, &fee_payer);
+        let fee_payer = fee_payer.to_string();
 
-        None
+        Some(TradeInfo {
+            wallet_address: fee_payer.clone(),
+            fee_payer,
+            timestamp: transaction.timestamp(),
+            slot: transaction.slot,
+            transaction_index: transaction.transaction_index,
+            provisional: self.is_provisional(),
+            failed: true,
+            base_fee: costs.base_fee,
+            priority_fee: costs.priority_fee,
+            tip: costs.tip,
+            transaction_hash: transaction.signature.clone(),
+            venue: decoder.venue().to_string(),
+            decoder_version: decoder.version().to_string(),
+            ..Default::default()
+        })
     }
-}
\ No newline at end of file
+}
+
+/// SOL size of a swap, taken from whichever leg is SOL.
+fn sol_notional(token_in: &BalanceChange, token_out: &BalanceChange) -> f64 {
+    if token_in.mint == WRAPPED_SOL_MINT {
+        -token_in.amount
+    } else if token_out.mint == WRAPPED_SOL_MINT {
+        token_out.amount
+    } else {
+        0.0
+    }
+}
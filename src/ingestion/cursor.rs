@@ -0,0 +1,99 @@
+use solana_client::rpc_response::RpcConfirmedTransactionStatusWithSignature;
+use solana_sdk::signature::Signature;
+use std::future::Future;
+use std::str::FromStr;
+use crate::error::{AnalyzerError, Result};
+use crate::types::{Backlog, SignatureCursor};
+
+/// Largest page `getSignaturesForAddress` will return.
+pub const MAX_SIGNATURES_PER_PAGE: usize = 1000;
+
+/// Signatures to process next, oldest first, and the cursor to store once
+/// they are.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignatureBatch {
+    pub signatures: Vec<RpcConfirmedTransactionStatusWithSignature>,
+    pub cursor: Option<SignatureCursor>,
+}
+
+/// Collects up to `limit` signatures following `cursor`.
+///
+/// Without a cursor only the latest `limit` signatures are taken. With a
+/// cursor, pages are walked backwards from the tip (`fetch_page` is called
+/// with the `before` signature of each page and must stop at the cursor),
+/// stopping once `limit` signatures are collected. If the cursor was not
+/// reached, the returned cursor records where the walk stopped, and the next
+/// call continues from there rather than from the tip; once the walk reaches
+/// the cursor, the cursor moves to the newest signature of the walk. Every
+/// signature after the cursor is returned exactly once, a batch at a time,
+/// though batches of a long walk come newest first.
+pub async fn signatures_since<F, Fut>(
+    cursor: Option<&SignatureCursor>,
+    limit: usize,
+    mut fetch_page: F,
+) -> Result<SignatureBatch>
+where
+    F: FnMut(Option<Signature>, usize) -> Fut,
+    Fut: Future<Output = Result<Vec<RpcConfirmedTransactionStatusWithSignature>>>,
+{
+    let mut collected = Vec::new();
+    if limit == 0 {
+        return Ok(SignatureBatch { signatures: collected, cursor: cursor.cloned() });
+    }
+    let backlog = cursor.and_then(|cursor| cursor.backlog.as_ref());
+    let mut before = backlog.map(|backlog| parse_signature(&backlog.before)).transpose()?;
+
+    let mut exhausted = false;
+    while collected.len() < limit {
+        let page_size = (limit - collected.len()).min(MAX_SIGNATURES_PER_PAGE);
+        let page = fetch_page(before, page_size).await?;
+        exhausted = page.len() < page_size;
+        if let Some(last) = page.last() {
+            before = Some(parse_signature(&last.signature)?);
+        }
+        collected.extend(page);
+        if exhausted {
+            break;
+        }
+    }
+
+    // Pages arrive newest first
+    let newest = match backlog {
+        Some(backlog) => Some(SignatureCursor {
+            signature: backlog.newest.clone(),
+            slot: backlog.newest_slot,
+            backlog: None,
+        }),
+        None => collected.first().map(SignatureCursor::from),
+    };
+    let next = match (cursor, newest) {
+        (Some(cursor), Some(newest)) if !exhausted => Some(SignatureCursor {
+            backlog: Some(Backlog {
+                before: collected.last().map_or(String::new(), |last| last.signature.clone()),
+                newest: newest.signature,
+                newest_slot: newest.slot,
+            }),
+            ..cursor.clone()
+        }),
+        (cursor, None) => cursor.cloned(),
+        (_, newest) => newest,
+    };
+
+    collected.reverse();
+    Ok(SignatureBatch { signatures: collected, cursor: next })
+}
+
+pub(crate) fn parse_signature(signature: &str) -> Result<Signature> {
+    Signature::from_str(signature)
+        .map_err(|e| AnalyzerError::ParseError(format!("Invalid signature {}: {}", signature, e)))
+}
+
+impl From<&RpcConfirmedTransactionStatusWithSignature> for SignatureCursor {
+    fn from(status: &RpcConfirmedTransactionStatusWithSignature) -> Self {
+        Self {
+            signature: status.signature.clone(),
+            slot: status.slot,
+            backlog: None,
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use solana_sdk::{instruction::CompiledInstruction, pubkey, pubkey::Pubkey};
+use super::{decode_from_balances, has_discriminator, DecodedSwap, SwapDecoder};
+use crate::ingestion::transaction::TransactionWithMeta;
+use crate::types::RouteHop;
+
+pub const JUPITER_V6_PROGRAM_ID: Pubkey = pubkey!("JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4");
+
+/// Prefix of Anchor events emitted through a self-CPI.
+const EVENT_IX_TAG: [u8; 8] = [228, 69, 165, 46, 81, 203, 154, 29];
+/// sha256("event:SwapEvent")[..8]
+const SWAP_EVENT_DISCRIMINATOR: [u8; 8] = [64, 198, 205, 232, 38, 8, 113, 226];
+/// amm, input_mint, input_amount, output_mint, output_amount
+const SWAP_EVENT_LEN: usize = 32 + 32 + 8 + 32 + 8;
+
+/// Jupiter v6 aggregator. A route may hop through several venues via CPI; the
+/// user's net balance change is reported as one trade and each hop, taken from
+/// the `SwapEvent`s Jupiter emits, is attached as route metadata.
+pub struct JupiterV6Decoder;
+
+impl SwapDecoder for JupiterV6Decoder {
+    fn venue(&self) -> &'static str {
+        "jupiter-v6"
+    }
+
+    fn program_id(&self) -> Pubkey {
+        JUPITER_V6_PROGRAM_ID
+    }
+
+    fn version(&self) -> &'static str {
+        "1"
+    }
+
+    fn is_swap_instruction(&self, data: &[u8]) -> bool {
+        has_discriminator(data, &[
+            [229, 23, 203, 151, 122, 227, 173, 42],  // route
+            [193, 32, 155, 51, 65, 214, 156, 129],   // shared_accounts_route
+            [208, 51, 239, 151, 123, 43, 237, 92],   // exact_out_route
+            [176, 209, 105, 168, 154, 125, 69, 62],  // shared_accounts_exact_out_route
+            [150, 86, 71, 116, 167, 93, 14, 104],    // route_with_token_ledger
+            [230, 121, 143, 80, 119, 159, 106, 170], // shared_accounts_route_with_token_ledger
+        ])
+    }
+
+    fn decode(
+        &self,
+        transaction: &TransactionWithMeta,
+        instruction: &CompiledInstruction,
+    ) -> Option<DecodedSwap> {
+        if !self.is_swap_instruction(&instruction.data) {
+            return None;
+        }
+        let mut swap = decode_from_balances(transaction)?;
+        swap.route = transaction.instructions()
+            .filter(|ix| transaction.program_id(ix) == Some(&JUPITER_V6_PROGRAM_ID))
+            .filter_map(|ix| parse_swap_event(&ix.data))
+            .collect();
+        Some(swap)
+    }
+}
+
+fn parse_swap_event(data: &[u8]) -> Option<RouteHop> {
+    if data.len() < 16 + SWAP_EVENT_LEN
+        || data[..8] != EVENT_IX_TAG
+        || data[8..16] != SWAP_EVENT_DISCRIMINATOR
+    {
+        return None;
+    }
+    let event = &data[16..];
+    let pubkey_at = |offset: usize| Pubkey::try_from(&event[offset..offset + 32]).ok();
+    let u64_at = |offset: usize| event[offset..offset + 8].try_into().ok().map(u64::from_le_bytes);
+
+    Some(RouteHop {
+        venue: String::new(),
+        amm: pubkey_at(0)?.to_string(),
+        input_mint: pubkey_at(32)?.to_string(),
+        input_amount: u64_at(64)?,
+        output_mint: pubkey_at(72)?.to_string(),
+        output_amount: u64_at(104)?,
+    })
+}
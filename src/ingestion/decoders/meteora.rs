@@ -0,0 +1,29 @@
+use solana_sdk::{pubkey, pubkey::Pubkey};
+use super::{has_discriminator, SwapDecoder};
+
+pub const METEORA_DLMM_PROGRAM_ID: Pubkey = pubkey!("LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9YuVaPwxo");
+
+/// Meteora dynamic liquidity market maker.
+pub struct MeteoraDlmmDecoder;
+
+impl SwapDecoder for MeteoraDlmmDecoder {
+    fn venue(&self) -> &'static str {
+        "meteora-dlmm"
+    }
+
+    fn program_id(&self) -> Pubkey {
+        METEORA_DLMM_PROGRAM_ID
+    }
+
+    fn version(&self) -> &'static str {
+        "1"
+    }
+
+    fn is_swap_instruction(&self, data: &[u8]) -> bool {
+        has_discriminator(data, &[
+            [248, 198, 158, 145, 225, 117, 135, 200], // swap
+            [250, 73, 101, 33, 38, 207, 75, 184],     // swap_exact_out
+            [56, 173, 230, 208, 173, 228, 156, 205],  // swap_with_price_impact
+        ])
+    }
+}
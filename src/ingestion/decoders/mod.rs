@@ -0,0 +1,126 @@
+pub mod jupiter;
+pub mod meteora;
+pub mod openbook;
+pub mod orca;
+pub mod phoenix;
+pub mod pump_fun;
+pub mod raydium;
+
+use solana_sdk::{instruction::CompiledInstruction, pubkey::Pubkey};
+use std::collections::HashMap;
+use crate::ingestion::attribution::attribute;
+use crate::ingestion::balances::BalanceChange;
+use crate::ingestion::transaction::TransactionWithMeta;
+use crate::types::RouteHop;
+
+/// A swap recognised by a decoder, before it is turned into a `TradeInfo`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedSwap {
+    /// Beneficial owner of the traded balances.
+    pub trader: Pubkey,
+    pub fee_payer: Pubkey,
+    pub token_in: BalanceChange,
+    pub token_out: BalanceChange,
+    /// Individual hops when the swap was routed through an aggregator.
+    pub route: Vec<RouteHop>,
+}
+
+/// Recognises swaps on a single on-chain venue.
+///
+/// Implementors only need to say which program they handle and which
+/// instructions of that program are swaps; the default `decode` derives the
+/// traded amounts from the transaction's balance changes.
+pub trait SwapDecoder: Send + Sync {
+    /// Short venue name recorded on every trade this decoder produces.
+    fn venue(&self) -> &'static str;
+
+    fn program_id(&self) -> Pubkey;
+
+    /// Bumped whenever the decoding logic changes, so trades can be traced
+    /// back to the decoder that produced them.
+    fn version(&self) -> &'static str;
+
+    fn is_swap_instruction(&self, data: &[u8]) -> bool;
+
+    fn decode(
+        &self,
+        transaction: &TransactionWithMeta,
+        instruction: &CompiledInstruction,
+    ) -> Option<DecodedSwap> {
+        if !self.is_swap_instruction(&instruction.data) {
+            return None;
+        }
+        decode_from_balances(transaction)
+    }
+}
+
+/// Derives a swap from the trader's net balance changes: the mint it lost
+/// the most of went in, the mint it gained the most of came out. See
+/// `attribute` for how the trader is found.
+pub fn decode_from_balances(transaction: &TransactionWithMeta) -> Option<DecodedSwap> {
+    let attribution = attribute(transaction)?;
+    let changes = &attribution.changes;
+
+    let token_in = changes.iter()
+        .filter(|change| change.amount < 0.0)
+        .min_by(|a, b| a.amount.total_cmp(&b.amount))?;
+    let token_out = changes.iter()
+        .filter(|change| change.amount > 0.0)
+        .max_by(|a, b| a.amount.total_cmp(&b.amount))?;
+
+    Some(DecodedSwap {
+        trader: attribution.owner,
+        fee_payer: attribution.fee_payer,
+        token_in: token_in.clone(),
+        token_out: token_out.clone(),
+        route: Vec::new(),
+    })
+}
+
+/// Whether `data` starts with one of the given 8-byte Anchor instruction discriminators.
+pub(crate) fn has_discriminator(data: &[u8], discriminators: &[[u8; 8]]) -> bool {
+    data.len() >= 8 && discriminators.iter().any(|d| data[..8] == d[..])
+}
+
+/// Swap decoders keyed by the program they handle.
+pub struct DecoderRegistry {
+    decoders: HashMap<Pubkey, Box<dyn SwapDecoder>>,
+}
+
+impl DecoderRegistry {
+    /// An empty registry; see `Default` for one with every built-in venue.
+    pub fn new() -> Self {
+        Self {
+            decoders: HashMap::new(),
+        }
+    }
+
+    /// Registers a decoder, replacing any previous decoder for the same program.
+    pub fn register(&mut self, decoder: Box<dyn SwapDecoder>) {
+        self.decoders.insert(decoder.program_id(), decoder);
+    }
+
+    pub fn get(&self, program_id: &Pubkey) -> Option<&dyn SwapDecoder> {
+        self.decoders.get(program_id).map(|decoder| decoder.as_ref())
+    }
+
+    pub fn program_ids(&self) -> impl Iterator<Item = &Pubkey> {
+        self.decoders.keys()
+    }
+}
+
+impl Default for DecoderRegistry {
+    fn default() -> Self {
+        let mut registry = Self::new();
+        registry.register(Box::new(raydium::RaydiumAmmV4Decoder));
+        registry.register(Box::new(raydium::RaydiumClmmDecoder));
+        registry.register(Box::new(orca::OrcaWhirlpoolDecoder));
+        registry.register(Box::new(orca::OrcaTokenSwapDecoder));
+        registry.register(Box::new(meteora::MeteoraDlmmDecoder));
+        registry.register(Box::new(phoenix::PhoenixDecoder));
+        registry.register(Box::new(openbook::OpenBookV2Decoder));
+        registry.register(Box::new(pump_fun::PumpFunDecoder));
+        registry.register(Box::new(jupiter::JupiterV6Decoder));
+        registry
+    }
+}
@@ -0,0 +1,27 @@
+use solana_sdk::{pubkey, pubkey::Pubkey};
+use super::{has_discriminator, SwapDecoder};
+
+pub const OPENBOOK_V2_PROGRAM_ID: Pubkey = pubkey!("opnb2LAfJYbRMAHHvqjCwQxanZn7ReEHp1k81EohpZb");
+
+/// OpenBook v2 order book; only take orders settle within the transaction.
+pub struct OpenBookV2Decoder;
+
+impl SwapDecoder for OpenBookV2Decoder {
+    fn venue(&self) -> &'static str {
+        "openbook-v2"
+    }
+
+    fn program_id(&self) -> Pubkey {
+        OPENBOOK_V2_PROGRAM_ID
+    }
+
+    fn version(&self) -> &'static str {
+        "1"
+    }
+
+    fn is_swap_instruction(&self, data: &[u8]) -> bool {
+        has_discriminator(data, &[
+            [3, 44, 71, 3, 26, 199, 203, 85], // place_take_order
+        ])
+    }
+}
@@ -0,0 +1,53 @@
+use solana_sdk::{pubkey, pubkey::Pubkey};
+use super::{has_discriminator, SwapDecoder};
+
+pub const ORCA_WHIRLPOOL_PROGRAM_ID: Pubkey = pubkey!("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc");
+pub const ORCA_TOKEN_SWAP_PROGRAM_ID: Pubkey = pubkey!("9W959DqEETiGZocYWCQPaJ6sBmUzgfxXfqGeTEdp3aQP");
+
+/// Orca Whirlpools (concentrated liquidity).
+pub struct OrcaWhirlpoolDecoder;
+
+impl SwapDecoder for OrcaWhirlpoolDecoder {
+    fn venue(&self) -> &'static str {
+        "orca-whirlpool"
+    }
+
+    fn program_id(&self) -> Pubkey {
+        ORCA_WHIRLPOOL_PROGRAM_ID
+    }
+
+    fn version(&self) -> &'static str {
+        "1"
+    }
+
+    fn is_swap_instruction(&self, data: &[u8]) -> bool {
+        has_discriminator(data, &[
+            [248, 198, 158, 145, 225, 117, 135, 200], // swap
+            [43, 4, 237, 11, 26, 201, 30, 98],        // swap_v2
+            [195, 96, 237, 108, 68, 162, 219, 230],   // two_hop_swap
+            [186, 143, 209, 29, 254, 2, 194, 117],    // two_hop_swap_v2
+        ])
+    }
+}
+
+/// Orca's legacy token-swap (v2) pools.
+pub struct OrcaTokenSwapDecoder;
+
+impl SwapDecoder for OrcaTokenSwapDecoder {
+    fn venue(&self) -> &'static str {
+        "orca-token-swap"
+    }
+
+    fn program_id(&self) -> Pubkey {
+        ORCA_TOKEN_SWAP_PROGRAM_ID
+    }
+
+    fn version(&self) -> &'static str {
+        "1"
+    }
+
+    fn is_swap_instruction(&self, data: &[u8]) -> bool {
+        // Swap = 1
+        data.first() == Some(&1)
+    }
+}
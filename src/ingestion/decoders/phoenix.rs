@@ -0,0 +1,26 @@
+use solana_sdk::{pubkey, pubkey::Pubkey};
+use super::SwapDecoder;
+
+pub const PHOENIX_PROGRAM_ID: Pubkey = pubkey!("PhoeNiXZ8ByJGLkxNfZRnkUfjvmuYqLR89jjFHGqdXY");
+
+/// Phoenix central limit order book; only immediate-or-cancel swaps count as trades.
+pub struct PhoenixDecoder;
+
+impl SwapDecoder for PhoenixDecoder {
+    fn venue(&self) -> &'static str {
+        "phoenix"
+    }
+
+    fn program_id(&self) -> Pubkey {
+        PHOENIX_PROGRAM_ID
+    }
+
+    fn version(&self) -> &'static str {
+        "1"
+    }
+
+    fn is_swap_instruction(&self, data: &[u8]) -> bool {
+        // Swap = 0, SwapWithFreeFunds = 1
+        matches!(data.first(), Some(0) | Some(1))
+    }
+}
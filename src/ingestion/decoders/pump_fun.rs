@@ -0,0 +1,28 @@
+use solana_sdk::{pubkey, pubkey::Pubkey};
+use super::{has_discriminator, SwapDecoder};
+
+pub const PUMP_FUN_PROGRAM_ID: Pubkey = pubkey!("6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P");
+
+/// pump.fun bonding curve; buys and sells settle in native SOL.
+pub struct PumpFunDecoder;
+
+impl SwapDecoder for PumpFunDecoder {
+    fn venue(&self) -> &'static str {
+        "pump-fun"
+    }
+
+    fn program_id(&self) -> Pubkey {
+        PUMP_FUN_PROGRAM_ID
+    }
+
+    fn version(&self) -> &'static str {
+        "1"
+    }
+
+    fn is_swap_instruction(&self, data: &[u8]) -> bool {
+        has_discriminator(data, &[
+            [102, 6, 61, 18, 1, 218, 235, 234],   // buy
+            [51, 230, 133, 164, 1, 127, 131, 173], // sell
+        ])
+    }
+}
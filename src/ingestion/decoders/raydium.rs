@@ -0,0 +1,52 @@
+use solana_sdk::{pubkey, pubkey::Pubkey};
+use super::{has_discriminator, SwapDecoder};
+
+pub const RAYDIUM_AMM_V4_PROGRAM_ID: Pubkey = pubkey!("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8");
+pub const RAYDIUM_CLMM_PROGRAM_ID: Pubkey = pubkey!("CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK");
+
+/// Raydium constant-product AMM (v4).
+pub struct RaydiumAmmV4Decoder;
+
+impl SwapDecoder for RaydiumAmmV4Decoder {
+    fn venue(&self) -> &'static str {
+        "raydium-amm-v4"
+    }
+
+    fn program_id(&self) -> Pubkey {
+        RAYDIUM_AMM_V4_PROGRAM_ID
+    }
+
+    fn version(&self) -> &'static str {
+        "1"
+    }
+
+    fn is_swap_instruction(&self, data: &[u8]) -> bool {
+        // SwapBaseIn = 9, SwapBaseOut = 11
+        matches!(data.first(), Some(9) | Some(11))
+    }
+}
+
+/// Raydium concentrated-liquidity pools.
+pub struct RaydiumClmmDecoder;
+
+impl SwapDecoder for RaydiumClmmDecoder {
+    fn venue(&self) -> &'static str {
+        "raydium-clmm"
+    }
+
+    fn program_id(&self) -> Pubkey {
+        RAYDIUM_CLMM_PROGRAM_ID
+    }
+
+    fn version(&self) -> &'static str {
+        "1"
+    }
+
+    fn is_swap_instruction(&self, data: &[u8]) -> bool {
+        has_discriminator(data, &[
+            [248, 198, 158, 145, 225, 117, 135, 200], // swap
+            [43, 4, 237, 11, 26, 201, 30, 98],        // swap_v2
+            [69, 125, 115, 218, 245, 186, 242, 196],  // swap_router_base_in
+        ])
+    }
+}
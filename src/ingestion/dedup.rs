@@ -0,0 +1,17 @@
+use std::collections::HashSet;
+use crate::ingestion::transaction::TransactionWithMeta;
+
+/// Splits a batch into the transactions still to process and the number of
+/// duplicates, given which of their signatures the persistent index has
+/// already seen (`seen[i]` for `transactions[i]`). A signature repeated
+/// within the batch is only processed once.
+pub fn unseen<'a>(transactions: &'a [TransactionWithMeta], seen: &[bool]) -> (Vec<&'a TransactionWithMeta>, usize) {
+    let mut batch = HashSet::new();
+    let fresh: Vec<_> = transactions.iter()
+        .zip(seen.iter().copied().chain(std::iter::repeat(false)))
+        .filter(|(tx, seen)| !seen && batch.insert(tx.signature.as_str()))
+        .map(|(tx, _)| tx)
+        .collect();
+    let duplicates = transactions.len() - fresh.len();
+    (fresh, duplicates)
+}
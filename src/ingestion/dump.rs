@@ -0,0 +1,101 @@
+use serde_json::Value;
+use solana_transaction_status::{EncodedConfirmedTransactionWithStatusMeta, UiConfirmedBlock};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Lines};
+use std::path::Path;
+use crate::error::{AnalyzerError, Result};
+use crate::ingestion::blocks::block_transactions;
+use crate::ingestion::transaction::TransactionWithMeta;
+
+/// Reads transactions from a JSON-lines dump of RPC responses, so the pipeline
+/// can run without an RPC node.
+///
+/// Each line holds one `getTransaction` or `getBlock` response, either as the
+/// full JSON-RPC envelope or as its bare `result`. Transactions must use a
+/// binary encoding (`base64` or `base58`), and blocks must have been fetched
+/// with `transactionDetails: "full"`. Blank lines and `null` results are
+/// skipped. Lines or transactions that cannot be read are yielded as errors
+/// tagged with their line number, and reading continues with the next one.
+pub struct DumpReader<R> {
+    lines: Lines<R>,
+    line_number: usize,
+    pending: VecDeque<Result<TransactionWithMeta>>,
+}
+
+impl DumpReader<BufReader<File>> {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path)
+            .map_err(|e| AnalyzerError::DataError(format!("Failed to open {}: {}", path.display(), e)))?;
+        Ok(Self::new(BufReader::new(file)))
+    }
+}
+
+impl<R: BufRead> DumpReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            lines: reader.lines(),
+            line_number: 0,
+            pending: VecDeque::new(),
+        }
+    }
+
+    fn read_line(&mut self, line: &str) {
+        let line_number = self.line_number;
+        let tag = |e: AnalyzerError| AnalyzerError::DataError(format!("line {}: {}", line_number, e));
+        match parse_dump_line(line) {
+            Ok(transactions) => self.pending.extend(transactions.into_iter().map(|tx| tx.map_err(tag))),
+            Err(e) => self.pending.push_back(Err(tag(e))),
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for DumpReader<R> {
+    type Item = Result<TransactionWithMeta>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(next) = self.pending.pop_front() {
+                return Some(next);
+            }
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(e) => return Some(Err(AnalyzerError::DataError(format!("Failed to read dump: {}", e)))),
+            };
+            self.line_number += 1;
+            if !line.trim().is_empty() {
+                self.read_line(&line);
+            }
+        }
+    }
+}
+
+/// Parses one dump line into its transactions. The outer error means the line
+/// itself is unusable; inner errors are transactions that could not be converted.
+pub fn parse_dump_line(line: &str) -> Result<Vec<Result<TransactionWithMeta>>> {
+    let mut value: Value = serde_json::from_str(line)?;
+
+    // Unwrap a full JSON-RPC response
+    if value.get("jsonrpc").is_some() {
+        if let Some(error) = value.get("error") {
+            return Err(AnalyzerError::DataError(format!("RPC error response: {}", error)));
+        }
+        value = value["result"].take();
+    }
+
+    if value.is_null() {
+        return Ok(Vec::new());
+    }
+    if value.get("transactions").is_some() {
+        let block: UiConfirmedBlock = serde_json::from_value(value)?;
+        // getBlock responses do not carry their own slot; the parent's
+        // successor is exact unless the preceding slots were skipped
+        let slot = block.parent_slot + 1;
+        return Ok(block_transactions(slot, block));
+    }
+    if value.get("transaction").is_some() {
+        let encoded: EncodedConfirmedTransactionWithStatusMeta = serde_json::from_value(value)?;
+        return Ok(vec![TransactionWithMeta::from_encoded(encoded)]);
+    }
+    Err(AnalyzerError::DataError("Neither a getTransaction nor a getBlock response".into()))
+}
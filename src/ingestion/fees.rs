@@ -0,0 +1,102 @@
+use solana_sdk::{compute_budget, pubkey, pubkey::Pubkey};
+use crate::ingestion::flows::system_transfers;
+use crate::ingestion::transaction::TransactionWithMeta;
+
+/// Jito's tip payment accounts; a transfer to any of them is a bundle tip.
+pub const JITO_TIP_ACCOUNTS: [Pubkey; 8] = [
+    pubkey!("96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5"),
+    pubkey!("HFqU5x63VTqvQss8hp11i4wVV8bD44PvwucfZ2bU7gRe"),
+    pubkey!("Cw8CFyM9FkoMi7K7Crf6HNQqf4uEMzpKw6QNghXLvLkY"),
+    pubkey!("ADaUMid9yfUytqMBgopwjb2DTLSokTSzL1zt6iGPaS49"),
+    pubkey!("DfXygSm4jCyNCybVYYK6DwvWqjKee8pbDmJGcLWNDXjh"),
+    pubkey!("ADuUkR4vqLUMWXxW9gh6D6L8pMSawimctcNZ5pGwDcEt"),
+    pubkey!("DttWaMuVvTiduZRnguLF7jNxTgiMBZ1hyAumKUiL2KRL"),
+    pubkey!("3AVi9Tg9Uo68tJfuvoKvqKNWKkC5wPdSSdeBnizKZ6jT"),
+];
+
+/// Compute units granted per instruction without a `SetComputeUnitLimit`.
+const DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT: u64 = 200_000;
+const MAX_COMPUTE_UNIT_LIMIT: u64 = 1_400_000;
+const MICRO_LAMPORTS_PER_LAMPORT: u128 = 1_000_000;
+
+const SET_COMPUTE_UNIT_LIMIT: u8 = 2;
+const SET_COMPUTE_UNIT_PRICE: u8 = 3;
+
+/// What a transaction cost one wallet, in lamports.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TransactionCosts {
+    /// Signature fee: the charged fee less the priority fee.
+    pub base_fee: u64,
+    /// Compute unit price times the requested compute unit limit.
+    pub priority_fee: u64,
+    /// Lamports transferred to Jito tip accounts.
+    pub tip: u64,
+}
+
+impl TransactionCosts {
+    /// Costs borne by `wallet`: the fees only if it is the fee payer, and
+    /// only the tips it sent itself, so relayed and multisig transactions
+    /// charge the relayer or member rather than the trader.
+    pub fn paid_by(transaction: &TransactionWithMeta, wallet: &Pubkey) -> Self {
+        let tip = jito_tips(transaction).iter()
+            .filter(|(payer, _)| payer == wallet)
+            .map(|(_, lamports)| lamports)
+            .sum();
+        if transaction.account_keys().get(0) != Some(wallet) {
+            return Self { tip, ..Self::default() };
+        }
+        let priority_fee = priority_fee(transaction);
+        Self {
+            base_fee: transaction.meta.fee.saturating_sub(priority_fee),
+            priority_fee,
+            tip,
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.base_fee + self.priority_fee + self.tip
+    }
+}
+
+/// Priority fee requested by the transaction's ComputeBudget instructions,
+/// rounded up to whole lamports as the runtime charges it.
+pub fn priority_fee(transaction: &TransactionWithMeta) -> u64 {
+    let mut unit_limit = None;
+    let mut unit_price: u64 = 0;
+    let mut other_instructions: u64 = 0;
+
+    for instruction in transaction.transaction.message.instructions() {
+        if transaction.program_id(instruction) != Some(&compute_budget::id()) {
+            other_instructions += 1;
+            continue;
+        }
+        match instruction.data.split_first() {
+            Some((&SET_COMPUTE_UNIT_LIMIT, rest)) => {
+                if let Some(bytes) = rest.get(..4) {
+                    unit_limit = Some(u32::from_le_bytes(bytes.try_into().unwrap()) as u64);
+                }
+            }
+            Some((&SET_COMPUTE_UNIT_PRICE, rest)) => {
+                if let Some(bytes) = rest.get(..8) {
+                    unit_price = u64::from_le_bytes(bytes.try_into().unwrap());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let unit_limit = unit_limit
+        .unwrap_or(other_instructions * DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT)
+        .min(MAX_COMPUTE_UNIT_LIMIT);
+    let micro_lamports = unit_price as u128 * unit_limit as u128;
+    micro_lamports.div_ceil(MICRO_LAMPORTS_PER_LAMPORT) as u64
+}
+
+/// System transfers to Jito tip accounts, top-level or via CPI, as
+/// `(payer, lamports)`.
+pub fn jito_tips(transaction: &TransactionWithMeta) -> Vec<(Pubkey, u64)> {
+    system_transfers(transaction).into_iter()
+        .filter(|(_, to, _)| JITO_TIP_ACCOUNTS.contains(to))
+        .map(|(from, _, lamports)| (from, lamports))
+        .collect()
+}
@@ -0,0 +1,129 @@
+use solana_sdk::{pubkey, pubkey::Pubkey, system_program};
+use std::str::FromStr;
+use crate::ingestion::balances::{owner_balance_changes, BalanceChange, WRAPPED_SOL_MINT};
+use crate::ingestion::fees::JITO_TIP_ACCOUNTS;
+use crate::ingestion::transaction::{TokenBalance, TransactionWithMeta};
+use crate::types::{CapitalFlow, FlowKind};
+
+/// Hot wallets of centralized exchanges; transfers to and from them are
+/// deposits and withdrawals.
+pub const EXCHANGE_WALLETS: [(Pubkey, &str); 8] = [
+    (pubkey!("5tzFkiKscXHK5ZXCGbXZxdw7gTjjD1mBwuoFbhJwvH8n"), "Binance"),
+    (pubkey!("9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM"), "Binance"),
+    (pubkey!("2ojv9BAiHUrvsm9gxDe7fJSzbNZSJcxZvf8dqmWGHG8S"), "Binance"),
+    (pubkey!("H8sMJSCQxfKiFTCfDR3DUMLPwcRbM61LGFJ8N4dK3WjS"), "Coinbase"),
+    (pubkey!("GJRs4FwHtemZ5ZE9x3FNvJ8TMwitKTh21yxdRPqn7npE"), "Coinbase"),
+    (pubkey!("5VCwKtCXgCJ6kit5FybXjvriW3xELsFDhYrPSqtJNmcD"), "OKX"),
+    (pubkey!("AC5RDfQFmDS1deWZos921JfqscXdByf8BKHs5ACWjtW2"), "Bybit"),
+    (pubkey!("FWznbcNXWQuHTawe9RxvQ2LdCENssh12dsznf4RiouN5"), "Kraken"),
+];
+
+const SYSTEM_TRANSFER: u32 = 2;
+
+/// Name of the exchange owning `wallet`, if it is a known exchange wallet.
+pub fn exchange_name(wallet: &Pubkey) -> Option<&'static str> {
+    EXCHANGE_WALLETS.iter().find(|(address, _)| address == wallet).map(|(_, name)| *name)
+}
+
+/// System program transfers, top-level or via CPI, as `(from, to, lamports)`.
+pub fn system_transfers(transaction: &TransactionWithMeta) -> Vec<(Pubkey, Pubkey, u64)> {
+    let account_keys = transaction.account_keys();
+    transaction.instructions()
+        .filter(|instruction| transaction.program_id(instruction) == Some(&system_program::id()))
+        .filter_map(|instruction| {
+            let (tag, lamports) = instruction.data.split_at_checked(4)?;
+            if u32::from_le_bytes(tag.try_into().ok()?) != SYSTEM_TRANSFER {
+                return None;
+            }
+            let lamports = u64::from_le_bytes(lamports.get(..8)?.try_into().ok()?);
+            let from = account_keys.get(*instruction.accounts.first()? as usize)?;
+            let to = account_keys.get(*instruction.accounts.get(1)? as usize)?;
+            Some((*from, *to, lamports))
+        })
+        .collect()
+}
+
+/// Classifies the balance movements of a successful transaction that made
+/// no swap. The wallets considered are the ends of SOL transfers and the
+/// owners of token accounts whose balances changed; each of their per-mint
+/// changes becomes one flow. The counterparty is the first other wallet
+/// that moved the same mint the opposite way. Exchange wallets themselves
+/// get no flows, and neither Jito tips nor the rent of token accounts
+/// opened or closed, whoever paid or received it, are flows.
+pub fn capital_flows(transaction: &TransactionWithMeta) -> Vec<CapitalFlow> {
+    if transaction.meta.err.is_some() {
+        return Vec::new();
+    }
+
+    let mut wallets: Vec<Pubkey> = Vec::new();
+    let transfer_ends = system_transfers(transaction).into_iter()
+        .filter(|(_, to, _)| !JITO_TIP_ACCOUNTS.contains(to))
+        .flat_map(|(from, to, _)| [from, to]);
+    for wallet in transfer_ends.chain(token_owners(transaction)) {
+        if !wallets.contains(&wallet) {
+            wallets.push(wallet);
+        }
+    }
+
+    let changes: Vec<(Pubkey, Vec<BalanceChange>)> = wallets.into_iter()
+        .map(|wallet| (wallet, owner_balance_changes(transaction, &wallet)))
+        .collect();
+    let counterparty = |wallet: &Pubkey, change: &BalanceChange| {
+        changes.iter()
+            .filter(|(other, _)| other != wallet)
+            .find(|(_, others)| {
+                others.iter().any(|other| other.mint == change.mint && other.amount.signum() == -change.amount.signum())
+            })
+            .map(|(other, _)| *other)
+    };
+
+    let mut flows = Vec::new();
+    for (wallet, wallet_changes) in &changes {
+        if exchange_name(wallet).is_some() {
+            continue;
+        }
+        for change in wallet_changes {
+            let counterparty = counterparty(wallet, change);
+            let kind = match (counterparty.as_ref().and_then(exchange_name), change.amount > 0.0) {
+                (Some(_), true) => FlowKind::ExchangeWithdrawal,
+                (Some(_), false) => FlowKind::ExchangeDeposit,
+                (None, _) if change.mint == WRAPPED_SOL_MINT => FlowKind::SolTransfer,
+                (None, _) => FlowKind::TokenTransfer,
+            };
+            flows.push(CapitalFlow {
+                wallet_address: wallet.to_string(),
+                counterparty: counterparty.map(|c| c.to_string()).unwrap_or_default(),
+                kind,
+                mint: change.mint.clone(),
+                amount: change.amount,
+                timestamp: transaction.timestamp(),
+                slot: transaction.slot,
+                transaction_index: transaction.transaction_index,
+                transaction_hash: transaction.signature.clone(),
+                provisional: false,
+            });
+        }
+    }
+    flows
+}
+
+/// Distinct owners of the token accounts whose balances changed.
+pub(crate) fn token_owners(transaction: &TransactionWithMeta) -> Vec<Pubkey> {
+    let meta = &transaction.meta;
+    let amount = |balances: &[TokenBalance], index: usize| {
+        balances.iter().find(|b| b.account_index == index).map(|b| b.amount)
+    };
+
+    let mut owners = Vec::new();
+    for balance in meta.pre_token_balances.iter().chain(&meta.post_token_balances) {
+        let changed = amount(&meta.pre_token_balances, balance.account_index)
+            != amount(&meta.post_token_balances, balance.account_index);
+        let owner = balance.owner.as_deref().and_then(|owner| Pubkey::from_str(owner).ok());
+        if let (true, Some(owner)) = (changed, owner) {
+            if !owners.contains(&owner) {
+                owners.push(owner);
+            }
+        }
+    }
+    owners
+}
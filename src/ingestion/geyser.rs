@@ -0,0 +1,393 @@
+use futures::channel::mpsc as request_channel;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    hash::Hash,
+    instruction::CompiledInstruction,
+    message::{
+        v0::{self, LoadedAddresses, MessageAddressTableLookup},
+        Message, MessageHeader, VersionedMessage,
+    },
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::{TransactionError, VersionedTransaction},
+};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, Duration};
+use tonic::transport::{ClientTlsConfig, Endpoint};
+use tracing::{info, warn};
+use crate::error::{AnalyzerError, Result};
+use crate::ingestion::transaction::{InnerInstructions, TokenBalance, TransactionMeta, TransactionWithMeta};
+use proto::geyser::{
+    geyser_client::GeyserClient, subscribe_update::UpdateOneof, CommitmentLevel, SubscribeRequest,
+    SubscribeRequestFilterBlocksMeta, SubscribeRequestFilterTransactions, SubscribeRequestPing,
+    SubscribeUpdateBlockMeta, SubscribeUpdateTransactionInfo,
+};
+use proto::solana::storage::confirmed_block;
+
+/// Message types generated from the vendored Yellowstone protos in `proto/`.
+#[allow(clippy::all)]
+pub mod proto {
+    pub mod geyser {
+        tonic::include_proto!("geyser");
+    }
+
+    pub mod solana {
+        pub mod storage {
+            pub mod confirmed_block {
+                tonic::include_proto!("solana.storage.confirmed_block");
+            }
+        }
+    }
+}
+
+const INITIAL_RECONNECT_DELAY: Duration = Duration::from_millis(500);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+const TRANSACTION_FILTER: &str = "dex";
+const BLOCKS_META_FILTER: &str = "blocks";
+/// Slots whose transactions are held for a block time before they are
+/// forwarded without one.
+const MAX_HELD_SLOTS: usize = 64;
+
+/// Streams transactions touching any of the given programs from a Yellowstone
+/// gRPC (Geyser) endpoint into a channel, failed ones included.
+///
+/// Updates already carry the full transaction and status meta, so unlike the
+/// websocket source nothing has to be fetched over JSON-RPC. Transaction
+/// updates carry no block time, so block meta is subscribed to as well and
+/// each transaction is held until the block time of its slot is known. On
+/// reconnect the subscription resumes from the last slot seen.
+pub struct GeyserStream {
+    endpoint: String,
+    x_token: Option<String>,
+    programs: Vec<Pubkey>,
+    last_slot: Option<u64>,
+    /// Signatures already forwarded from `last_slot`, which is replayed on resume.
+    last_slot_signatures: HashSet<String>,
+    /// Block times of recent slots, from their block meta.
+    block_times: BTreeMap<u64, i64>,
+    /// Transactions waiting for the block meta of their slot.
+    held: BTreeMap<u64, Vec<TransactionWithMeta>>,
+    reconnect_delay: Duration,
+    commitment: CommitmentLevel,
+}
+
+impl GeyserStream {
+    pub fn new(endpoint: &str, x_token: Option<String>, programs: Vec<Pubkey>) -> Self {
+        Self {
+            endpoint: endpoint.to_string(),
+            x_token,
+            programs,
+            last_slot: None,
+            last_slot_signatures: HashSet::new(),
+            block_times: BTreeMap::new(),
+            held: BTreeMap::new(),
+            reconnect_delay: INITIAL_RECONNECT_DELAY,
+            commitment: CommitmentLevel::Confirmed,
+        }
+    }
+
+    /// Commitment updates are sent at; `confirmed` by default.
+    pub fn with_commitment(mut self, commitment: CommitmentConfig) -> Self {
+        self.commitment = if commitment.is_finalized() {
+            CommitmentLevel::Finalized
+        } else {
+            CommitmentLevel::Confirmed
+        };
+        self
+    }
+
+    /// Runs the stream on a background task until the receiving end is dropped.
+    pub fn spawn(self, sender: mpsc::UnboundedSender<TransactionWithMeta>) -> JoinHandle<()> {
+        tokio::spawn(self.run(sender))
+    }
+
+    pub async fn run(mut self, sender: mpsc::UnboundedSender<TransactionWithMeta>) {
+        loop {
+            match self.stream_once(&sender).await {
+                Ok(()) => warn!("Geyser stream from {} closed", self.endpoint),
+                Err(e) => warn!("Geyser stream from {} failed: {}", self.endpoint, e),
+            }
+            if sender.is_closed() {
+                return;
+            }
+
+            sleep(self.reconnect_delay).await;
+            self.reconnect_delay = (self.reconnect_delay * 2).min(MAX_RECONNECT_DELAY);
+        }
+    }
+
+    async fn stream_once(
+        &mut self,
+        sender: &mpsc::UnboundedSender<TransactionWithMeta>,
+    ) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut endpoint = Endpoint::from_shared(self.endpoint.clone())?;
+        if self.endpoint.starts_with("https://") {
+            endpoint = endpoint.tls_config(ClientTlsConfig::new())?;
+        }
+        let mut client = GeyserClient::new(endpoint.connect().await?);
+
+        // The request stream stays open for keepalive pings
+        let (requests, request_stream) = request_channel::unbounded();
+        requests.unbounded_send(self.subscribe_request())?;
+        let mut request = tonic::Request::new(request_stream);
+        if let Some(token) = &self.x_token {
+            request.metadata_mut().insert("x-token", token.parse()?);
+        }
+
+        let mut updates = client.subscribe(request).await?.into_inner();
+        info!("Subscribed to transactions of {} programs on {}", self.programs.len(), self.endpoint);
+        self.reconnect_delay = INITIAL_RECONNECT_DELAY;
+
+        while let Some(update) = updates.message().await? {
+            match update.update_oneof {
+                Some(UpdateOneof::Transaction(update)) => {
+                    let Some(info) = update.transaction else { continue };
+                    let mut transaction = match TransactionWithMeta::try_from(info) {
+                        Ok(transaction) => transaction,
+                        Err(e) => {
+                            warn!("Skipping Geyser transaction in slot {}: {}", update.slot, e);
+                            continue;
+                        }
+                    };
+                    if !self.mark_seen(update.slot, &transaction.signature) {
+                        continue;
+                    }
+                    transaction.slot = update.slot;
+                    if !self.forward(transaction, sender) {
+                        break;
+                    }
+                }
+                Some(UpdateOneof::BlockMeta(meta)) => {
+                    if !self.release(meta, sender) {
+                        break;
+                    }
+                }
+                Some(UpdateOneof::Ping(_)) => {
+                    requests.unbounded_send(SubscribeRequest {
+                        ping: Some(SubscribeRequestPing { id: 1 }),
+                        ..Default::default()
+                    })?;
+                }
+                Some(UpdateOneof::Pong(_)) | None => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn subscribe_request(&self) -> SubscribeRequest {
+        let filter = SubscribeRequestFilterTransactions {
+            vote: Some(false),
+            failed: None,
+            account_include: self.programs.iter().map(|p| p.to_string()).collect(),
+            ..Default::default()
+        };
+
+        SubscribeRequest {
+            transactions: HashMap::from([(TRANSACTION_FILTER.to_string(), filter)]),
+            blocks_meta: HashMap::from([(BLOCKS_META_FILTER.to_string(), SubscribeRequestFilterBlocksMeta {})]),
+            commitment: Some(self.commitment as i32),
+            from_slot: self.last_slot,
+            ..Default::default()
+        }
+    }
+
+    /// Forwards a transaction stamped with the block time of its slot, or
+    /// holds it until the slot's block meta arrives. Returns false once the
+    /// receiving end is dropped.
+    fn forward(&mut self, mut transaction: TransactionWithMeta, sender: &mpsc::UnboundedSender<TransactionWithMeta>) -> bool {
+        if let Some(block_time) = self.block_times.get(&transaction.slot) {
+            transaction.block_time = Some(*block_time);
+            return sender.send(transaction).is_ok();
+        }
+        self.held.entry(transaction.slot).or_default().push(transaction);
+        if self.held.len() <= MAX_HELD_SLOTS {
+            return true;
+        }
+
+        let (slot, transactions) = self.held.pop_first().unwrap();
+        warn!("No block meta for slot {}; forwarding {} transactions without a block time", slot, transactions.len());
+        transactions.into_iter().all(|transaction| sender.send(transaction).is_ok())
+    }
+
+    /// Records the block time of a slot and forwards the transactions held
+    /// for it.
+    fn release(&mut self, meta: SubscribeUpdateBlockMeta, sender: &mpsc::UnboundedSender<TransactionWithMeta>) -> bool {
+        let block_time = meta.block_time.map(|time| time.timestamp);
+        match block_time {
+            Some(block_time) => {
+                self.block_times.insert(meta.slot, block_time);
+                while self.block_times.len() > MAX_HELD_SLOTS {
+                    self.block_times.pop_first();
+                }
+            }
+            None => warn!("Block meta for slot {} has no block time", meta.slot),
+        }
+        self.held.remove(&meta.slot).unwrap_or_default().into_iter().all(|mut transaction| {
+            transaction.block_time = block_time;
+            sender.send(transaction).is_ok()
+        })
+    }
+
+    /// Records a forwarded signature; returns false for replays of the resumed slot.
+    fn mark_seen(&mut self, slot: u64, signature: &str) -> bool {
+        match self.last_slot {
+            Some(last) if slot < last => return true,
+            Some(last) if slot == last => {}
+            _ => {
+                self.last_slot = Some(slot);
+                self.last_slot_signatures.clear();
+            }
+        }
+        self.last_slot_signatures.insert(signature.to_string())
+    }
+}
+
+impl TryFrom<SubscribeUpdateTransactionInfo> for TransactionWithMeta {
+    type Error = AnalyzerError;
+
+    /// Converts a Geyser transaction update, legacy or v0. Fails on missing
+    /// fields and malformed keys or signatures. The slot is carried by the
+    /// enclosing update and left at zero, and the block time by the block
+    /// meta of the slot.
+    fn try_from(info: SubscribeUpdateTransactionInfo) -> Result<Self> {
+        let transaction = info.transaction
+            .ok_or_else(|| AnalyzerError::DataError("Update has no transaction".into()))?;
+        let meta = info.meta
+            .ok_or_else(|| AnalyzerError::DataError("Transaction has no status meta".into()))?;
+        let message = transaction.message
+            .ok_or_else(|| AnalyzerError::DataError("Transaction has no message".into()))?;
+        let signatures = transaction.signatures.iter()
+            .map(|bytes| signature(bytes))
+            .collect::<Result<Vec<_>>>()?;
+        let header = message.header.unwrap_or_default();
+        let account_keys = pubkeys(&message.account_keys)?;
+        let recent_blockhash = <[u8; 32]>::try_from(message.recent_blockhash.as_slice())
+            .map(Hash::new_from_array)
+            .map_err(|_| AnalyzerError::ParseError("Invalid recent blockhash".into()))?;
+        let instructions = message.instructions.into_iter()
+            .map(|ix| CompiledInstruction {
+                program_id_index: ix.program_id_index as u8,
+                accounts: ix.accounts,
+                data: ix.data,
+            })
+            .collect();
+
+        let header = MessageHeader {
+            num_required_signatures: header.num_required_signatures as u8,
+            num_readonly_signed_accounts: header.num_readonly_signed_accounts as u8,
+            num_readonly_unsigned_accounts: header.num_readonly_unsigned_accounts as u8,
+        };
+        let message = if message.versioned {
+            let address_table_lookups = message.address_table_lookups.into_iter()
+                .map(|lookup| {
+                    Ok(MessageAddressTableLookup {
+                        account_key: pubkey(&lookup.account_key)?,
+                        writable_indexes: lookup.writable_indexes,
+                        readonly_indexes: lookup.readonly_indexes,
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            VersionedMessage::V0(v0::Message {
+                header,
+                account_keys,
+                recent_blockhash,
+                instructions,
+                address_table_lookups,
+            })
+        } else {
+            VersionedMessage::Legacy(Message {
+                header,
+                account_keys,
+                recent_blockhash,
+                instructions,
+            })
+        };
+
+        Ok(Self {
+            signature: signature(&info.signature)?.to_string(),
+            slot: 0,
+            block_time: None,
+            transaction_index: Some(info.index as u32),
+            transaction: VersionedTransaction { signatures, message },
+            meta: TransactionMeta::try_from(meta)?,
+        })
+    }
+}
+
+impl TryFrom<confirmed_block::TransactionStatusMeta> for TransactionMeta {
+    type Error = AnalyzerError;
+
+    fn try_from(meta: confirmed_block::TransactionStatusMeta) -> Result<Self> {
+        let err = meta.err
+            .map(|err| {
+                bincode::deserialize::<TransactionError>(&err.err)
+                    .map(|e| e.to_string())
+                    .map_err(|e| AnalyzerError::ParseError(format!("Invalid transaction error: {}", e)))
+            })
+            .transpose()?;
+
+        let inner_instructions = meta.inner_instructions.into_iter()
+            .map(|inner| InnerInstructions {
+                index: inner.index as usize,
+                instructions: inner.instructions.into_iter()
+                    .map(|ix| CompiledInstruction {
+                        program_id_index: ix.program_id_index as u8,
+                        accounts: ix.accounts,
+                        data: ix.data,
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        Ok(Self {
+            err,
+            fee: meta.fee,
+            pre_balances: meta.pre_balances,
+            post_balances: meta.post_balances,
+            pre_token_balances: token_balances(meta.pre_token_balances)?,
+            post_token_balances: token_balances(meta.post_token_balances)?,
+            inner_instructions,
+            log_messages: meta.log_messages,
+            loaded_addresses: LoadedAddresses {
+                writable: pubkeys(&meta.loaded_writable_addresses)?,
+                readonly: pubkeys(&meta.loaded_readonly_addresses)?,
+            },
+        })
+    }
+}
+
+fn signature(bytes: &[u8]) -> Result<Signature> {
+    Signature::try_from(bytes)
+        .map_err(|_| AnalyzerError::ParseError("Invalid signature".into()))
+}
+
+fn pubkey(bytes: &[u8]) -> Result<Pubkey> {
+    Pubkey::try_from(bytes)
+        .map_err(|_| AnalyzerError::ParseError("Invalid account key".into()))
+}
+
+fn pubkeys(keys: &[Vec<u8>]) -> Result<Vec<Pubkey>> {
+    keys.iter().map(|bytes| pubkey(bytes)).collect()
+}
+
+fn token_balances(balances: Vec<confirmed_block::TokenBalance>) -> Result<Vec<TokenBalance>> {
+    balances
+        .into_iter()
+        .map(|balance| {
+            let ui_amount = balance.ui_token_amount.unwrap_or_default();
+            let amount = ui_amount.amount.parse::<u64>().map_err(|e| {
+                AnalyzerError::ParseError(format!("Invalid token amount: {}", e))
+            })?;
+            Ok(TokenBalance {
+                account_index: balance.account_index as usize,
+                mint: balance.mint,
+                owner: Some(balance.owner).filter(|owner| !owner.is_empty()),
+                amount,
+                decimals: ui_amount.decimals as u8,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,94 @@
+use solana_sdk::{
+    address_lookup_table::state::AddressLookupTable,
+    message::v0::{LoadedAddresses, MessageAddressTableLookup},
+    pubkey::Pubkey,
+};
+use std::collections::HashMap;
+use std::sync::RwLock;
+use crate::error::{AnalyzerError, Result};
+use crate::ingestion::transaction::TransactionWithMeta;
+
+/// Addresses of the lookup tables seen so far.
+///
+/// Tables only ever grow while active, so a cached table stays valid for
+/// every index it covers and is refetched only when a lookup reaches past
+/// its end.
+#[derive(Default)]
+pub struct LookupTableCache {
+    tables: RwLock<HashMap<Pubkey, Vec<Pubkey>>>,
+}
+
+impl LookupTableCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&self, table: Pubkey, addresses: Vec<Pubkey>) {
+        self.tables.write().unwrap().insert(table, addresses);
+    }
+
+    pub fn len(&self) -> usize {
+        self.tables.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Tables `transaction` looks up that are not cached or are too short.
+    pub fn missing(&self, transaction: &TransactionWithMeta) -> Vec<Pubkey> {
+        let tables = self.tables.read().unwrap();
+        transaction.address_table_lookups().iter()
+            .filter(|lookup| {
+                tables.get(&lookup.account_key)
+                    .is_none_or(|addresses| lookup_index_bound(lookup) > addresses.len())
+            })
+            .map(|lookup| lookup.account_key)
+            .collect()
+    }
+
+    /// Fills in the loaded addresses of a transaction whose meta lacks them.
+    /// Returns false if a table it needs is missing from the cache.
+    pub fn resolve(&self, transaction: &mut TransactionWithMeta) -> bool {
+        if !transaction.has_unresolved_lookups() {
+            return true;
+        }
+        let tables = self.tables.read().unwrap();
+        let lookups = transaction.address_table_lookups();
+
+        let select = |indexes: fn(&MessageAddressTableLookup) -> &[u8]| {
+            lookups.iter()
+                .flat_map(|lookup| {
+                    let addresses = tables.get(&lookup.account_key);
+                    indexes(lookup).iter().map(move |&index| addresses?.get(index as usize).copied())
+                })
+                .collect::<Option<Vec<_>>>()
+        };
+        let (Some(writable), Some(readonly)) = (
+            select(|lookup| &lookup.writable_indexes),
+            select(|lookup| &lookup.readonly_indexes),
+        ) else {
+            return false;
+        };
+        drop(tables);
+
+        transaction.meta.loaded_addresses = LoadedAddresses { writable, readonly };
+        true
+    }
+}
+
+/// One past the highest index `lookup` reads from its table.
+fn lookup_index_bound(lookup: &MessageAddressTableLookup) -> usize {
+    lookup.writable_indexes.iter()
+        .chain(&lookup.readonly_indexes)
+        .map(|&index| index as usize + 1)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Addresses stored in a lookup table account.
+pub fn parse_lookup_table(data: &[u8]) -> Result<Vec<Pubkey>> {
+    AddressLookupTable::deserialize(data)
+        .map(|table| table.addresses.to_vec())
+        .map_err(|e| AnalyzerError::ParseError(format!("Invalid address lookup table: {}", e)))
+}
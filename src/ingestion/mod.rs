@@ -1,3 +1,28 @@
+pub mod attribution;
+pub mod backfill;
+pub mod balances;
+pub mod blocks;
+pub mod cassette;
+pub mod client;
+pub mod cursor;
+pub mod decoders;
+pub mod dedup;
+pub mod dump;
+pub mod fees;
+pub mod flows;
+pub mod geyser;
+pub mod lookup_tables;
+pub mod oracles;
+pub mod pool;
+pub mod rate_limit;
+pub mod reconcile;
+pub mod retry;
+pub mod sender;
+pub mod stream;
+pub mod token_metadata;
+pub mod transaction;
+pub mod watchlist;
+
 use solana_client::rpc_client::RpcClient;
 use solana_sdk::commitment_config::CommitmentConfig;
 
@@ -6,11 +31,11 @@ pub fn init_solana_client(rpc_url: &str) -> Result<RpcClient, Box<dyn std::error
         rpc_url.to_string(),
         CommitmentConfig::confirmed(),
     );
-    
+
     Ok(client)
 }
 
 pub async fn process_transactions() -> Result<(), Box<dyn std::error::Error>> {
     // TODO: Implement transaction processing logic
     Ok(())
-}
\ No newline at end of file
+}
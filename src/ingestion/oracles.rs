@@ -0,0 +1,167 @@
+use solana_client::rpc_response::RpcKeyedAccount;
+use solana_sdk::{account::Account, hash::hash, pubkey, pubkey::Pubkey};
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::str::FromStr;
+use crate::error::{AnalyzerError, Result};
+use crate::types::{OraclePrice, OracleSource};
+
+/// Pyth's original push oracle, whose price accounts hold the aggregate price.
+pub const PYTH_PROGRAM_ID: Pubkey = pubkey!("FsJ3A3u2vn5cTVofAjvy6y5kwABJAqYWpe4975bi2epH");
+/// Pyth's pull oracle receiver, which owns posted `PriceUpdateV2` accounts.
+pub const PYTH_RECEIVER_PROGRAM_ID: Pubkey = pubkey!("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
+pub const SWITCHBOARD_V2_PROGRAM_ID: Pubkey = pubkey!("SW1TCH7qEPTdLsDHRgPuMQjbQxKdH2aBStViMFnt64f");
+
+const PYTH_MAGIC: u32 = 0xa1b2c3d4;
+const PYTH_PRICE_ACCOUNT_TYPE: u32 = 3;
+const PYTH_STATUS_TRADING: u32 = 1;
+const PYTH_VERIFICATION_FULL: u8 = 1;
+
+/// A mint and the oracle account pricing it in USD, given as `<MINT>=<ACCOUNT>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OracleFeed {
+    pub mint: Pubkey,
+    pub account: Pubkey,
+}
+
+impl FromStr for OracleFeed {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        let (mint, account) = value.split_once('=')
+            .ok_or_else(|| format!("expected <MINT>=<PRICE_ACCOUNT>, got '{}'", value))?;
+        let parse = |key: &str| Pubkey::from_str(key.trim()).map_err(|e| format!("invalid address '{}': {}", key, e));
+        Ok(Self { mint: parse(mint)?, account: parse(account)? })
+    }
+}
+
+/// Decodes an oracle account by its owner: a Pyth price account, a Pyth
+/// price update or a Switchboard v2 aggregator. Prices that are not
+/// currently trading, or were not fully verified, are rejected.
+pub fn parse_price_account(mint: &Pubkey, owner: &Pubkey, data: &[u8]) -> Result<OraclePrice> {
+    let (source, (price, confidence, publish_time)) = if *owner == PYTH_PROGRAM_ID {
+        (OracleSource::Pyth, parse_pyth_price(data)?)
+    } else if *owner == PYTH_RECEIVER_PROGRAM_ID {
+        (OracleSource::Pyth, parse_pyth_price_update(data)?)
+    } else if *owner == SWITCHBOARD_V2_PROGRAM_ID {
+        (OracleSource::Switchboard, parse_switchboard_aggregator(data)?)
+    } else {
+        return Err(AnalyzerError::ParseError(format!("Account owned by {} is not a known price oracle", owner)));
+    };
+    Ok(OraclePrice {
+        mint: mint.to_string(),
+        source,
+        price,
+        confidence,
+        publish_time,
+    })
+}
+
+/// Price, confidence and publish time.
+type Quote = (f64, f64, i64);
+
+fn parse_pyth_price(data: &[u8]) -> Result<Quote> {
+    if u32::from_le_bytes(read(data, 0)?) != PYTH_MAGIC
+        || u32::from_le_bytes(read(data, 8)?) != PYTH_PRICE_ACCOUNT_TYPE
+    {
+        return Err(AnalyzerError::ParseError("Not a Pyth price account".into()));
+    }
+    if u32::from_le_bytes(read(data, 224)?) != PYTH_STATUS_TRADING {
+        return Err(AnalyzerError::ParseError("Pyth price is not trading".into()));
+    }
+    let scale = 10f64.powi(i32::from_le_bytes(read(data, 20)?));
+    Ok((
+        i64::from_le_bytes(read(data, 208)?) as f64 * scale,
+        u64::from_le_bytes(read(data, 216)?) as f64 * scale,
+        i64::from_le_bytes(read(data, 96)?),
+    ))
+}
+
+fn parse_pyth_price_update(data: &[u8]) -> Result<Quote> {
+    if data.get(..8) != Some(&anchor_discriminator("PriceUpdateV2")) {
+        return Err(AnalyzerError::ParseError("Not a Pyth price update".into()));
+    }
+    // Discriminator, write authority, then the verification level
+    if read::<1>(data, 40)?[0] != PYTH_VERIFICATION_FULL {
+        return Err(AnalyzerError::ParseError("Pyth price update is not fully verified".into()));
+    }
+    // The price message follows, after the feed id
+    let message = 41 + 32;
+    let scale = 10f64.powi(i32::from_le_bytes(read(data, message + 16)?));
+    Ok((
+        i64::from_le_bytes(read(data, message)?) as f64 * scale,
+        u64::from_le_bytes(read(data, message + 8)?) as f64 * scale,
+        i64::from_le_bytes(read(data, message + 20)?),
+    ))
+}
+
+fn parse_switchboard_aggregator(data: &[u8]) -> Result<Quote> {
+    if data.get(..8) != Some(&anchor_discriminator("AggregatorAccountData")) {
+        return Err(AnalyzerError::ParseError("Not a Switchboard aggregator".into()));
+    }
+    // Offsets into the packed `latest_confirmed_round`
+    let round = 341;
+    if u32::from_le_bytes(read(data, round)?) == 0 {
+        return Err(AnalyzerError::ParseError("Switchboard aggregator has no confirmed result".into()));
+    }
+    Ok((
+        switchboard_decimal(data, round + 25)?,
+        switchboard_decimal(data, round + 45)?,
+        i64::from_le_bytes(read(data, round + 17)?),
+    ))
+}
+
+/// A Switchboard decimal: an `i128` mantissa and a `u32` scale.
+fn switchboard_decimal(data: &[u8], offset: usize) -> Result<f64> {
+    let mantissa = i128::from_le_bytes(read(data, offset)?);
+    let scale = u32::from_le_bytes(read(data, offset + 16)?);
+    Ok(mantissa as f64 / 10f64.powi(scale as i32))
+}
+
+/// First eight bytes of an Anchor account, identifying its type.
+pub fn anchor_discriminator(account: &str) -> [u8; 8] {
+    hash(format!("account:{}", account).as_bytes()).to_bytes()[..8].try_into().unwrap()
+}
+
+fn read<const N: usize>(data: &[u8], offset: usize) -> Result<[u8; N]> {
+    data.get(offset..offset + N)
+        .map(|bytes| bytes.try_into().unwrap())
+        .ok_or_else(|| AnalyzerError::ParseError("Price account too short".into()))
+}
+
+/// Reads the prices of `feeds` from a JSON-lines dump of accounts, one per
+/// line as printed by `solana account --output json`. Accounts that are not
+/// feeds are ignored; unreadable lines are errors tagged with their number.
+pub fn read_price_dump<R: BufRead>(reader: R, feeds: &[OracleFeed]) -> Vec<Result<OraclePrice>> {
+    let mints: HashMap<String, Pubkey> = feeds.iter()
+        .map(|feed| (feed.account.to_string(), feed.mint))
+        .collect();
+
+    let mut prices = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let tag = |e: AnalyzerError| AnalyzerError::DataError(format!("line {}: {}", index + 1, e));
+        let line = match line {
+            Ok(line) if line.trim().is_empty() => continue,
+            Ok(line) => line,
+            Err(e) => {
+                prices.push(Err(AnalyzerError::DataError(format!("Failed to read dump: {}", e))));
+                break;
+            }
+        };
+        let keyed: RpcKeyedAccount = match serde_json::from_str(&line) {
+            Ok(keyed) => keyed,
+            Err(e) => {
+                prices.push(Err(tag(e.into())));
+                continue;
+            }
+        };
+        let Some(mint) = mints.get(&keyed.pubkey) else {
+            continue;
+        };
+        prices.push(match keyed.account.decode::<Account>() {
+            Some(account) => parse_price_account(mint, &account.owner, &account.data).map_err(tag),
+            None => Err(tag(AnalyzerError::DataError("account data must be base64 or base58".into()))),
+        });
+    }
+    prices
+}
@@ -0,0 +1,263 @@
+use futures::future::BoxFuture;
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_client::RpcClientConfig;
+use solana_sdk::commitment_config::CommitmentConfig;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Mutex;
+use tokio::time::{Duration, Instant};
+use tracing::{info, warn};
+use crate::error::AnalyzerError;
+use crate::ingestion::rate_limit::RateLimiter;
+use crate::ingestion::sender::HttpRpcSender;
+
+/// Consecutive failures after which an endpoint is taken out of rotation.
+const EJECT_AFTER_FAILURES: u32 = 3;
+const EJECTION_PERIOD: Duration = Duration::from_secs(30);
+/// Weight of the newest sample in the latency moving average.
+const LATENCY_SMOOTHING: f64 = 0.2;
+
+/// An RPC node and its share of traffic, parsed from `<url>` or `<url>#<weight>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RpcEndpoint {
+    pub url: String,
+    pub weight: u32,
+}
+
+impl RpcEndpoint {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            weight: 1,
+        }
+    }
+
+    /// The URL without its query string, which often carries an API key.
+    pub fn label(&self) -> &str {
+        self.url.split('?').next().unwrap_or(&self.url)
+    }
+}
+
+impl FromStr for RpcEndpoint {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.rsplit_once('#') {
+            Some((url, weight)) => {
+                let weight = weight.parse::<u32>()
+                    .map_err(|e| format!("invalid weight '{}': {}", weight, e))?;
+                if weight == 0 {
+                    return Err("weight must be at least 1".to_string());
+                }
+                Ok(Self { url: url.to_string(), weight })
+            }
+            None => Ok(Self::new(value)),
+        }
+    }
+}
+
+/// Health of one endpoint as tracked by the pool.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EndpointStats {
+    pub label: String,
+    pub weight: u32,
+    pub requests: u64,
+    pub failures: u64,
+    pub average_latency_ms: f64,
+    pub healthy: bool,
+}
+
+impl fmt::Display for EndpointStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} (weight {}): {} requests, {} failures, {:.0}ms avg latency, {}",
+            self.label,
+            self.weight,
+            self.requests,
+            self.failures,
+            self.average_latency_ms,
+            if self.healthy { "healthy" } else { "ejected" },
+        )
+    }
+}
+
+#[derive(Default)]
+struct Health {
+    requests: u64,
+    failures: u64,
+    consecutive_failures: u32,
+    latency_ms: f64,
+    ejected_until: Option<Instant>,
+    /// Running weight for smooth weighted round-robin.
+    current_weight: i64,
+}
+
+impl Health {
+    fn is_ejected(&self, now: Instant) -> bool {
+        self.ejected_until.is_some_and(|until| until > now)
+    }
+
+    fn error_rate(&self) -> f64 {
+        if self.requests == 0 {
+            0.0
+        } else {
+            self.failures as f64 / self.requests as f64
+        }
+    }
+}
+
+struct PooledEndpoint {
+    endpoint: RpcEndpoint,
+    client: RpcClient,
+    rate_limiter: RateLimiter,
+    health: Mutex<Health>,
+}
+
+/// Spreads requests over several RPC endpoints by weight, failing over to
+/// the next best endpoint when one errors and ejecting endpoints that keep
+/// failing for a cool-down period.
+pub struct RpcPool {
+    endpoints: Vec<PooledEndpoint>,
+}
+
+impl RpcPool {
+    /// Each endpoint gets its own rate limiter, since providers limit per key.
+    pub fn new(endpoints: Vec<RpcEndpoint>, requests_per_second: f64, burst: u32) -> Self {
+        let endpoints = endpoints
+            .into_iter()
+            .map(|endpoint| {
+                let client = RpcClient::new_sender(
+                    HttpRpcSender::new(&endpoint.url),
+                    RpcClientConfig::with_commitment(CommitmentConfig::confirmed()),
+                );
+                (endpoint, client)
+            })
+            .collect();
+        Self::with_clients(endpoints, requests_per_second, burst)
+    }
+
+    /// Like `new`, with the client that talks to each endpoint supplied by the caller.
+    pub fn with_clients(endpoints: Vec<(RpcEndpoint, RpcClient)>, requests_per_second: f64, burst: u32) -> Self {
+        let endpoints = endpoints
+            .into_iter()
+            .map(|(endpoint, client)| PooledEndpoint {
+                client,
+                rate_limiter: RateLimiter::new(requests_per_second, burst),
+                health: Mutex::new(Health::default()),
+                endpoint,
+            })
+            .collect();
+        Self { endpoints }
+    }
+
+    /// Runs `request` against the endpoint whose turn it is, then against the
+    /// remaining endpoints, healthiest first, until one does not fail.
+    pub async fn call<T, F>(&self, request: F) -> Result<T, ClientError>
+    where
+        F: for<'a> Fn(&'a RpcClient) -> BoxFuture<'a, Result<T, ClientError>>,
+    {
+        let mut last_error = None;
+        for index in self.attempt_order() {
+            let pooled = &self.endpoints[index];
+            pooled.rate_limiter.acquire().await;
+
+            let started = Instant::now();
+            let result = request(&pooled.client).await;
+            let failed = result.as_ref().err().is_some_and(is_endpoint_failure);
+            self.record(index, started.elapsed(), failed);
+
+            match result {
+                Err(e) if failed => {
+                    warn!("RPC endpoint {} failed: {}", pooled.endpoint.label(), e);
+                    last_error = Some(e);
+                }
+                result => return result,
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| ClientErrorKind::Custom("No RPC endpoints configured".into()).into()))
+    }
+
+    pub fn stats(&self) -> Vec<EndpointStats> {
+        let now = Instant::now();
+        self.endpoints
+            .iter()
+            .map(|pooled| {
+                let health = pooled.health.lock().unwrap();
+                EndpointStats {
+                    label: pooled.endpoint.label().to_string(),
+                    weight: pooled.endpoint.weight,
+                    requests: health.requests,
+                    failures: health.failures,
+                    average_latency_ms: health.latency_ms,
+                    healthy: !health.is_ejected(now),
+                }
+            })
+            .collect()
+    }
+
+    pub fn log_stats(&self) {
+        for stats in self.stats() {
+            info!("RPC endpoint {}", stats);
+        }
+    }
+
+    /// Endpoint indices to try: the weighted round-robin pick among healthy
+    /// endpoints first, then the other healthy ones by error rate and latency,
+    /// then ejected ones as a last resort.
+    fn attempt_order(&self) -> Vec<usize> {
+        let now = Instant::now();
+        let mut healths: Vec<_> = self.endpoints.iter().map(|e| e.health.lock().unwrap()).collect();
+
+        let healthy: Vec<usize> = (0..healths.len()).filter(|&i| !healths[i].is_ejected(now)).collect();
+        let total_weight: i64 = healthy.iter().map(|&i| self.endpoints[i].endpoint.weight as i64).sum();
+        for &i in &healthy {
+            healths[i].current_weight += self.endpoints[i].endpoint.weight as i64;
+        }
+        let picked = healthy.iter().copied().max_by_key(|&i| (healths[i].current_weight, std::cmp::Reverse(i)));
+        if let Some(picked) = picked {
+            healths[picked].current_weight -= total_weight;
+        }
+
+        let score = |i: usize| (healths[i].is_ejected(now), healths[i].error_rate(), healths[i].latency_ms);
+        let mut order: Vec<usize> = (0..healths.len()).filter(|&i| Some(i) != picked).collect();
+        order.sort_by(|&a, &b| score(a).partial_cmp(&score(b)).unwrap_or(std::cmp::Ordering::Equal));
+        picked.into_iter().chain(order).collect()
+    }
+
+    fn record(&self, index: usize, latency: Duration, failed: bool) {
+        let pooled = &self.endpoints[index];
+        let mut health = pooled.health.lock().unwrap();
+        let latency_ms = latency.as_secs_f64() * 1000.0;
+        health.latency_ms = if health.requests == 0 {
+            latency_ms
+        } else {
+            LATENCY_SMOOTHING * latency_ms + (1.0 - LATENCY_SMOOTHING) * health.latency_ms
+        };
+        health.requests += 1;
+
+        if !failed {
+            health.consecutive_failures = 0;
+            health.ejected_until = None;
+            return;
+        }
+        health.failures += 1;
+        health.consecutive_failures += 1;
+        if health.consecutive_failures >= EJECT_AFTER_FAILURES && self.endpoints.len() > 1 {
+            warn!(
+                "Ejecting RPC endpoint {} for {:?} after {} consecutive failures",
+                pooled.endpoint.label(), EJECTION_PERIOD, health.consecutive_failures
+            );
+            health.ejected_until = Some(Instant::now() + EJECTION_PERIOD);
+            health.consecutive_failures = 0;
+        }
+    }
+}
+
+/// Whether an error reflects on the endpoint rather than the request, so that
+/// another endpoint may succeed.
+fn is_endpoint_failure(error: &ClientError) -> bool {
+    AnalyzerError::from_client_error(error).is_retryable()
+}
@@ -0,0 +1,55 @@
+use tokio::sync::Mutex;
+use tokio::time::{sleep, Duration, Instant};
+
+/// Token bucket of a single RPC endpoint; each endpoint in the pool has its own.
+///
+/// Holds up to `burst` tokens and refills `requests_per_second` of them per
+/// second; each request takes one token, waiting for a refill when empty.
+/// A rate of zero disables limiting.
+pub struct RateLimiter {
+    burst: f64,
+    requests_per_second: f64,
+    bucket: Mutex<Bucket>,
+}
+
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_second: f64, burst: u32) -> Self {
+        let burst = burst.max(1) as f64;
+        Self {
+            burst,
+            requests_per_second,
+            bucket: Mutex::new(Bucket {
+                tokens: burst,
+                refilled_at: Instant::now(),
+            }),
+        }
+    }
+
+    /// Waits until a request may be sent.
+    pub async fn acquire(&self) {
+        if self.requests_per_second <= 0.0 {
+            return;
+        }
+        loop {
+            let wait = {
+                let mut bucket = self.bucket.lock().await;
+                let now = Instant::now();
+                let elapsed = now.duration_since(bucket.refilled_at).as_secs_f64();
+                bucket.tokens = (bucket.tokens + elapsed * self.requests_per_second).min(self.burst);
+                bucket.refilled_at = now;
+
+                if bucket.tokens >= 1.0 {
+                    bucket.tokens -= 1.0;
+                    return;
+                }
+                Duration::from_secs_f64((1.0 - bucket.tokens) / self.requests_per_second)
+            };
+            sleep(wait).await;
+        }
+    }
+}
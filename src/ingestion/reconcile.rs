@@ -0,0 +1,52 @@
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_transaction_status::TransactionStatus;
+use crate::types::{CapitalFlow, TradeInfo};
+
+/// What to do with a provisional trade once the chain has moved on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// Its slot is not finalized yet; check again later.
+    Pending,
+    /// The transaction is finalized in the slot the trade was read from.
+    Finalized,
+    /// The transaction is gone, or its outcome on the finalized fork differs
+    /// from the one recorded; the record never happened.
+    Reverted,
+    /// The transaction landed in a different slot than the one it was read
+    /// from, whose block was abandoned, so it may have executed against
+    /// different state. The trade is reverted and the transaction refetched.
+    Moved(u64),
+}
+
+/// Decides the fate of a provisional trade from its transaction's status,
+/// given the latest finalized slot.
+pub fn verdict(trade: &TradeInfo, finalized_slot: u64, status: Option<&TransactionStatus>) -> Verdict {
+    slot_verdict(trade.slot, trade.failed, finalized_slot, status)
+}
+
+/// Decides the fate of a provisional capital flow, which only successful
+/// transactions have; see `verdict`.
+pub fn flow_verdict(flow: &CapitalFlow, finalized_slot: u64, status: Option<&TransactionStatus>) -> Verdict {
+    slot_verdict(flow.slot, false, finalized_slot, status)
+}
+
+fn slot_verdict(slot: u64, failed: bool, finalized_slot: u64, status: Option<&TransactionStatus>) -> Verdict {
+    if slot > finalized_slot {
+        return Verdict::Pending;
+    }
+    let Some(status) = status else {
+        return Verdict::Reverted;
+    };
+    if status.slot != slot {
+        return Verdict::Moved(status.slot);
+    }
+    // A failed-transaction record is only valid if it failed on the finalized fork too
+    if status.err.is_some() != failed {
+        return Verdict::Reverted;
+    }
+    if status.satisfies_commitment(CommitmentConfig::finalized()) {
+        Verdict::Finalized
+    } else {
+        Verdict::Pending
+    }
+}
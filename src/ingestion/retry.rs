@@ -0,0 +1,71 @@
+use rand::Rng;
+use std::future::Future;
+use tokio::time::{sleep, Duration};
+use tracing::warn;
+use crate::error::{AnalyzerError, Result};
+
+/// Exponential backoff with full jitter for retryable `AnalyzerError`s.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Attempts in total, including the first one.
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(250),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Upper bound of the delay after the given number of failed attempts,
+    /// doubling from `base_delay` up to `max_delay`.
+    pub fn backoff_ceiling(&self, failures: u32) -> Duration {
+        let exponent = failures.saturating_sub(1).min(31);
+        self.base_delay.saturating_mul(1 << exponent).min(self.max_delay)
+    }
+
+    /// How long to wait before the next attempt. A server's `Retry-After` is
+    /// honored up to `max_delay`; otherwise a random delay up to the backoff
+    /// ceiling.
+    pub fn delay(&self, failures: u32, error: &AnalyzerError) -> Duration {
+        if let Some(retry_after) = error.retry_after() {
+            return retry_after.min(self.max_delay);
+        }
+        let ceiling = self.backoff_ceiling(failures);
+        ceiling.mul_f64(rand::thread_rng().gen_range(0.0..=1.0))
+    }
+
+    /// Runs `operation` until it succeeds, fails permanently, or runs out of
+    /// attempts; the last error is returned.
+    pub async fn retry<T, F, Fut>(&self, description: &str, mut operation: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut failures = 0;
+        loop {
+            let error = match operation().await {
+                Ok(value) => return Ok(value),
+                Err(error) => error,
+            };
+            failures += 1;
+            if !error.is_retryable() || failures >= self.max_attempts {
+                return Err(error);
+            }
+
+            let delay = self.delay(failures, &error);
+            warn!(
+                "{} failed (attempt {}/{}), retrying in {:?}: {}",
+                description, failures, self.max_attempts, delay, error
+            );
+            sleep(delay).await;
+        }
+    }
+}
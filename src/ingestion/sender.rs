@@ -0,0 +1,136 @@
+use async_trait::async_trait;
+use reqwest::header::{CONTENT_TYPE, RETRY_AFTER};
+use reqwest::StatusCode;
+use serde_json::Value;
+use solana_client::client_error::{ClientError, ClientErrorKind, Result};
+use solana_client::rpc_request::{RpcError, RpcRequest, RpcResponseErrorData};
+use solana_client::rpc_sender::{RpcSender, RpcTransportStats};
+use std::fmt;
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// An HTTP error status returned by an RPC node, with its `Retry-After` hint.
+///
+/// Carried inside `ClientErrorKind::Io` so it survives the `RpcClient` API,
+/// and recovered by `AnalyzerError::from_client_error`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpStatusError {
+    pub status: u16,
+    pub retry_after: Option<Duration>,
+}
+
+impl fmt::Display for HttpStatusError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "HTTP status {}", self.status)?;
+        if let Some(retry_after) = self.retry_after {
+            write!(f, " (retry after {:?})", retry_after)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for HttpStatusError {}
+
+/// JSON-RPC over HTTP that makes exactly one attempt per request.
+///
+/// Unlike the stock sender it does not retry HTTP 429 internally; the status
+/// and `Retry-After` are surfaced so `RetryPolicy` decides when to try again.
+pub struct HttpRpcSender {
+    client: reqwest::Client,
+    url: String,
+    request_id: AtomicU64,
+    stats: RwLock<RpcTransportStats>,
+}
+
+impl HttpRpcSender {
+    pub fn new(url: &str) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .expect("HTTP client configuration is valid");
+        Self {
+            client,
+            url: url.to_string(),
+            request_id: AtomicU64::new(0),
+            stats: RwLock::new(RpcTransportStats::default()),
+        }
+    }
+
+    async fn post(&self, body: String) -> Result<Value> {
+        let response = self.client
+            .post(&self.url)
+            .header(CONTENT_TYPE, "application/json")
+            .body(body)
+            .send()
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let retry_after = response.headers().get(RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(parse_retry_after);
+            return Err(http_status_error(status, retry_after));
+        }
+
+        let mut json = response.json::<Value>().await?;
+        if json["error"].is_object() {
+            let code = json["error"]["code"].as_i64();
+            let message = json["error"]["message"].as_str().unwrap_or_default().to_string();
+            return Err(match code {
+                Some(code) => RpcError::RpcResponseError {
+                    code,
+                    message,
+                    data: RpcResponseErrorData::Empty,
+                },
+                None => RpcError::RpcRequestError(format!("Malformed RPC error response: {}", json["error"])),
+            }.into());
+        }
+        Ok(json["result"].take())
+    }
+}
+
+#[async_trait]
+impl RpcSender for HttpRpcSender {
+    async fn send(&self, request: RpcRequest, params: Value) -> Result<Value> {
+        let request_id = self.request_id.fetch_add(1, Ordering::Relaxed);
+        let body = request.build_request_json(request_id, params).to_string();
+
+        let started = Instant::now();
+        let result = self.post(body).await;
+
+        let mut stats = self.stats.write().unwrap();
+        stats.request_count += 1;
+        stats.elapsed_time += started.elapsed();
+        result
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.stats.read().unwrap().clone()
+    }
+
+    fn url(&self) -> String {
+        self.url.clone()
+    }
+}
+
+fn http_status_error(status: StatusCode, retry_after: Option<Duration>) -> ClientError {
+    let error = HttpStatusError {
+        status: status.as_u16(),
+        retry_after,
+    };
+    ClientErrorKind::Io(io::Error::other(error)).into()
+}
+
+/// Parses `Retry-After` given either as delay seconds or as an HTTP date.
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    if let Ok(seconds) = value.trim().parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value.trim()).ok()?;
+    let delay = date.timestamp() - chrono::Utc::now().timestamp();
+    Some(Duration::from_secs(delay.max(0) as u64))
+}
@@ -0,0 +1,216 @@
+use futures::stream::{select_all, StreamExt};
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_client::rpc_config::{RpcTransactionLogsConfig, RpcTransactionLogsFilter};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, timeout_at, Duration, Instant};
+use tracing::{info, warn};
+use crate::ingestion::client::SolanaClient;
+use crate::ingestion::cursor::SignatureBatch;
+use crate::ingestion::transaction::TransactionWithMeta;
+use crate::types::{Backlog, SignatureCursor};
+
+const INITIAL_RECONNECT_DELAY: Duration = Duration::from_millis(500);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+/// Upper bound on signatures recovered per program after a reconnect.
+const MAX_GAP_FILL_SIGNATURES: usize = 10_000;
+
+/// Pushes the signatures of transactions mentioning any of the given
+/// programs, as reported by `logsSubscribe`, into a channel. Failed ones are
+/// included, since their fees count towards a wallet's costs.
+///
+/// The websocket is reconnected with exponential backoff whenever it drops.
+/// Signatures that landed while disconnected are recovered through
+/// `getSignaturesForAddress` once the new subscription delivers its first
+/// signature for a program, retried with each later one until it succeeds,
+/// so none fall between the two.
+pub struct LogStream {
+    ws_url: String,
+    programs: Vec<Pubkey>,
+    client: Arc<SolanaClient>,
+    last_seen: HashMap<Pubkey, SignatureCursor>,
+    reconnect_delay: Duration,
+}
+
+impl LogStream {
+    pub fn new(ws_url: &str, programs: Vec<Pubkey>, client: Arc<SolanaClient>) -> Self {
+        Self {
+            ws_url: ws_url.to_string(),
+            programs,
+            client,
+            last_seen: HashMap::new(),
+            reconnect_delay: INITIAL_RECONNECT_DELAY,
+        }
+    }
+
+    /// Runs the stream on a background task until the receiving end is dropped.
+    pub fn spawn(self, sender: mpsc::UnboundedSender<String>) -> JoinHandle<()> {
+        tokio::spawn(self.run(sender))
+    }
+
+    pub async fn run(mut self, sender: mpsc::UnboundedSender<String>) {
+        loop {
+            match self.stream_once(&sender).await {
+                Ok(()) => warn!("Websocket stream to {} closed", self.ws_url),
+                Err(e) => warn!("Websocket stream to {} failed: {}", self.ws_url, e),
+            }
+            if sender.is_closed() {
+                return;
+            }
+
+            sleep(self.reconnect_delay).await;
+            self.reconnect_delay = (self.reconnect_delay * 2).min(MAX_RECONNECT_DELAY);
+        }
+    }
+
+    async fn stream_once(
+        &mut self,
+        sender: &mpsc::UnboundedSender<String>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let pubsub = PubsubClient::new(&self.ws_url).await?;
+
+        let mut streams = Vec::with_capacity(self.programs.len());
+        for program in &self.programs {
+            let (stream, _unsubscribe) = pubsub.logs_subscribe(
+                RpcTransactionLogsFilter::Mentions(vec![program.to_string()]),
+                RpcTransactionLogsConfig {
+                    commitment: Some(self.client.commitment()),
+                },
+            ).await?;
+            let program = *program;
+            streams.push(stream.map(move |notification| (program, notification)));
+        }
+        info!("Subscribed to logs of {} programs on {}", streams.len(), self.ws_url);
+        self.reconnect_delay = INITIAL_RECONNECT_DELAY;
+
+        // Programs streamed before, whose gap is filled up to the first
+        // signature the new subscription delivers
+        let mut gaps: HashSet<Pubkey> = self.last_seen.keys().copied().collect();
+        let mut notifications = select_all(streams);
+        while let Some((program, notification)) = notifications.next().await {
+            let streamed = SignatureCursor {
+                signature: notification.value.signature.clone(),
+                slot: notification.context.slot,
+                backlog: None,
+            };
+            if gaps.contains(&program) {
+                match self.fill_gap(program, &streamed, sender).await {
+                    Ok(true) => {
+                        gaps.remove(&program);
+                    }
+                    Ok(false) => break,
+                    // Keep the last signature seen before the reconnect, so
+                    // the gap is filled up to the next one streamed instead
+                    Err(e) => warn!("Failed to recover missed signatures for {}, retrying: {}", program, e),
+                }
+            }
+            if !gaps.contains(&program) {
+                self.last_seen.insert(program, streamed);
+            }
+            if sender.send(notification.value.signature).is_err() {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Forwards the signatures of `program` that landed between the last one
+    /// streamed before a reconnect and `streamed`, one streamed after it.
+    /// Returns false once the receiving end is dropped.
+    async fn fill_gap(
+        &self,
+        program: Pubkey,
+        streamed: &SignatureCursor,
+        sender: &mpsc::UnboundedSender<String>,
+    ) -> crate::error::Result<bool> {
+        let Some(last_seen) = self.last_seen.get(&program) else {
+            return Ok(true);
+        };
+        // Walk back from the streamed signature rather than the tip
+        let bounded = SignatureCursor {
+            backlog: Some(Backlog {
+                before: streamed.signature.clone(),
+                newest: streamed.signature.clone(),
+                newest_slot: streamed.slot,
+            }),
+            ..last_seen.clone()
+        };
+        let SignatureBatch { signatures, cursor } = self.client
+            .get_signatures_since(&program, Some(&bounded), MAX_GAP_FILL_SIGNATURES)
+            .await?;
+
+        if cursor.is_some_and(|cursor| cursor.backlog.is_some()) {
+            warn!(
+                "More than {} signatures for {} landed while disconnected; those between slot {} and slot {} were skipped",
+                MAX_GAP_FILL_SIGNATURES,
+                program,
+                last_seen.slot,
+                signatures.first().map_or(streamed.slot, |status| status.slot),
+            );
+        }
+        if !signatures.is_empty() {
+            info!("Recovered {} signatures for {} missed while disconnected", signatures.len(), program);
+        }
+        Ok(signatures.into_iter().all(|status| sender.send(status.signature).is_ok()))
+    }
+}
+
+/// Streamed item that `recv_batch` deduplicates by transaction signature.
+pub trait Signed {
+    fn signature(&self) -> &str;
+}
+
+impl Signed for String {
+    fn signature(&self) -> &str {
+        self
+    }
+}
+
+impl Signed for TransactionWithMeta {
+    fn signature(&self) -> &str {
+        &self.signature
+    }
+}
+
+/// Waits for the next item, then keeps collecting until `max` items with
+/// distinct signatures are buffered or `window` has elapsed. Returns `None`
+/// once the stream has shut down and nothing is left.
+pub async fn recv_batch<T: Signed>(
+    receiver: &mut mpsc::UnboundedReceiver<T>,
+    max: usize,
+    window: Duration,
+) -> Option<Vec<T>> {
+    let first = receiver.recv().await?;
+    let deadline = Instant::now() + window;
+
+    let mut seen = HashSet::new();
+    seen.insert(first.signature().to_string());
+    let mut batch = vec![first];
+    while batch.len() < max {
+        match timeout_at(deadline, receiver.recv()).await {
+            Ok(Some(item)) => {
+                if seen.insert(item.signature().to_string()) {
+                    batch.push(item);
+                }
+            }
+            Ok(None) | Err(_) => break,
+        }
+    }
+
+    Some(batch)
+}
+
+/// Derives the PubSub endpoint from an HTTP RPC URL.
+pub fn ws_url_from_rpc(rpc_url: &str) -> String {
+    if let Some(rest) = rpc_url.strip_prefix("https://") {
+        format!("wss://{}", rest)
+    } else if let Some(rest) = rpc_url.strip_prefix("http://") {
+        format!("ws://{}", rest)
+    } else {
+        rpc_url.to_string()
+    }
+}
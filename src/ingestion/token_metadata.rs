@@ -0,0 +1,74 @@
+use solana_sdk::{pubkey, pubkey::Pubkey};
+use spl_token_2022::extension::{BaseStateWithExtensions, StateWithExtensions};
+use spl_token_2022::state::Mint;
+use spl_token_metadata_interface::state::TokenMetadata as TokenMetadataExtension;
+use crate::error::{AnalyzerError, Result};
+use crate::ingestion::client::{TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID};
+use crate::types::TokenMetadata;
+
+pub const METAPLEX_METADATA_PROGRAM_ID: Pubkey = pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+/// Key byte, update authority and mint precede the name in a Metaplex
+/// metadata account.
+const METAPLEX_NAME_OFFSET: usize = 1 + 32 + 32;
+
+/// Metaplex metadata account of `mint`.
+pub fn metadata_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"metadata", METAPLEX_METADATA_PROGRAM_ID.as_ref(), mint.as_ref()],
+        &METAPLEX_METADATA_PROGRAM_ID,
+    ).0
+}
+
+/// Reads a mint account owned by `owner`, which must be the Token or
+/// Token-2022 program. A Token-2022 metadata extension supplies the symbol
+/// and name; otherwise they are left empty for Metaplex metadata to fill.
+pub fn parse_mint(mint: &Pubkey, owner: &Pubkey, data: &[u8]) -> Result<TokenMetadata> {
+    let token_2022 = *owner == TOKEN_2022_PROGRAM_ID;
+    if !token_2022 && *owner != TOKEN_PROGRAM_ID {
+        return Err(AnalyzerError::ParseError(format!("{} is not owned by a token program", mint)));
+    }
+    let state = StateWithExtensions::<Mint>::unpack(data)
+        .map_err(|e| AnalyzerError::ParseError(format!("Invalid mint {}: {}", mint, e)))?;
+
+    let mut metadata = TokenMetadata {
+        mint: mint.to_string(),
+        decimals: state.base.decimals,
+        supply: state.base.supply,
+        token_2022,
+        ..Default::default()
+    };
+    if token_2022 {
+        metadata.extensions = state.get_extension_types()
+            .map_err(|e| AnalyzerError::ParseError(format!("Invalid extensions of mint {}: {}", mint, e)))?
+            .iter()
+            .map(|extension| format!("{:?}", extension))
+            .collect();
+        if let Ok(extension) = state.get_variable_len_extension::<TokenMetadataExtension>() {
+            metadata.name = clean(&extension.name);
+            metadata.symbol = clean(&extension.symbol);
+        }
+    }
+    Ok(metadata)
+}
+
+/// Name and symbol stored in a Metaplex metadata account.
+pub fn parse_metaplex_metadata(data: &[u8]) -> Result<(String, String)> {
+    let mut offset = METAPLEX_NAME_OFFSET;
+    let mut read_string = || {
+        let len = data.get(offset..offset + 4)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()) as usize)?;
+        let value = data.get(offset + 4..offset + 4 + len)?;
+        offset += 4 + len;
+        Some(clean(&String::from_utf8_lossy(value)))
+    };
+    let invalid = || AnalyzerError::ParseError("Truncated Metaplex metadata account".into());
+    let name = read_string().ok_or_else(invalid)?;
+    let symbol = read_string().ok_or_else(invalid)?;
+    Ok((name, symbol))
+}
+
+/// Metaplex pads names and symbols with NUL bytes to a fixed length.
+fn clean(value: &str) -> String {
+    value.trim_matches(char::from(0)).trim().to_string()
+}
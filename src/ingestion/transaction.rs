@@ -0,0 +1,220 @@
+use solana_sdk::{
+    bs58,
+    instruction::CompiledInstruction,
+    message::{v0::{LoadedAddresses, MessageAddressTableLookup}, AccountKeys},
+    pubkey::Pubkey,
+    transaction::VersionedTransaction,
+};
+use solana_transaction_status::{
+    option_serializer::OptionSerializer, EncodedConfirmedTransactionWithStatusMeta, UiInstruction,
+    UiLoadedAddresses, UiTransactionStatusMeta, UiTransactionTokenBalance,
+};
+use std::str::FromStr;
+use tracing::warn;
+use crate::error::{AnalyzerError, Result};
+
+/// SPL token balance of a single account, as reported in the transaction status meta.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenBalance {
+    pub account_index: usize,
+    pub mint: String,
+    pub owner: Option<String>,
+    pub amount: u64,
+    pub decimals: u8,
+}
+
+/// Instructions invoked via CPI by the outer instruction at `index`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InnerInstructions {
+    pub index: usize,
+    pub instructions: Vec<CompiledInstruction>,
+}
+
+/// Execution result and balance changes recorded by the validator.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TransactionMeta {
+    pub err: Option<String>,
+    pub fee: u64,
+    pub pre_balances: Vec<u64>,
+    pub post_balances: Vec<u64>,
+    pub pre_token_balances: Vec<TokenBalance>,
+    pub post_token_balances: Vec<TokenBalance>,
+    pub inner_instructions: Vec<InnerInstructions>,
+    pub log_messages: Vec<String>,
+    /// Accounts a v0 transaction loaded from address lookup tables, writable
+    /// ones first; they follow the static keys in the account list.
+    pub loaded_addresses: LoadedAddresses,
+}
+
+/// A transaction together with its status meta, the unit every ingestion
+/// source hands to `SolanaClient::extract_trade_info`.
+#[derive(Debug, Clone)]
+pub struct TransactionWithMeta {
+    pub signature: String,
+    pub slot: u64,
+    /// Unix timestamp of the block, if the node reported one.
+    pub block_time: Option<i64>,
+    /// Position of the transaction within its block, if the source knows it.
+    pub transaction_index: Option<u32>,
+    pub transaction: VersionedTransaction,
+    pub meta: TransactionMeta,
+}
+
+impl TransactionWithMeta {
+    /// Converts a `getTransaction` response. Fails if the response carries no
+    /// status meta or uses a JSON encoding.
+    pub fn from_encoded(encoded: EncodedConfirmedTransactionWithStatusMeta) -> Result<Self> {
+        let transaction = encoded
+            .transaction
+            .transaction
+            .decode()
+            .ok_or_else(|| AnalyzerError::ParseError("Undecodable transaction encoding".into()))?;
+
+        let meta = encoded
+            .transaction
+            .meta
+            .ok_or_else(|| AnalyzerError::DataError("Transaction has no status meta".into()))?;
+
+        let signature = transaction
+            .signatures
+            .first()
+            .map(|sig| sig.to_string())
+            .ok_or_else(|| AnalyzerError::DataError("Transaction has no signatures".into()))?;
+
+        Ok(Self {
+            signature,
+            slot: encoded.slot,
+            block_time: encoded.block_time,
+            transaction_index: None,
+            transaction,
+            meta: TransactionMeta::from_ui(meta)?,
+        })
+    }
+
+    /// Block time of the transaction, or the current time, with a warning,
+    /// when the source did not report one.
+    pub fn timestamp(&self) -> i64 {
+        self.block_time.unwrap_or_else(|| {
+            warn!("No block time for transaction {} in slot {}; using the current time", self.signature, self.slot);
+            chrono::Utc::now().timestamp()
+        })
+    }
+
+    /// The full account list instructions index into: the static keys followed
+    /// by the addresses loaded from lookup tables.
+    pub fn account_keys(&self) -> AccountKeys<'_> {
+        AccountKeys::new(self.transaction.message.static_account_keys(), Some(&self.meta.loaded_addresses))
+    }
+
+    pub fn address_table_lookups(&self) -> &[MessageAddressTableLookup] {
+        self.transaction.message.address_table_lookups().unwrap_or_default()
+    }
+
+    /// Whether the transaction uses lookup tables whose addresses are not
+    /// known yet, in which case account indices past the static keys are
+    /// meaningless.
+    pub fn has_unresolved_lookups(&self) -> bool {
+        let expected: usize = self.address_table_lookups().iter()
+            .map(|lookup| lookup.writable_indexes.len() + lookup.readonly_indexes.len())
+            .sum();
+        self.meta.loaded_addresses.len() != expected
+    }
+
+    /// All instructions in execution order: each top-level instruction followed
+    /// by the instructions it invoked via CPI.
+    pub fn instructions(&self) -> impl Iterator<Item = &CompiledInstruction> {
+        self.transaction.message.instructions().iter().enumerate().flat_map(move |(index, ix)| {
+            std::iter::once(ix).chain(
+                self.meta.inner_instructions.iter()
+                    .filter(move |inner| inner.index == index)
+                    .flat_map(|inner| inner.instructions.iter()),
+            )
+        })
+    }
+
+    pub fn program_id(&self, instruction: &CompiledInstruction) -> Option<&Pubkey> {
+        self.account_keys().get(instruction.program_id_index as usize)
+    }
+}
+
+impl TransactionMeta {
+    pub fn from_ui(meta: UiTransactionStatusMeta) -> Result<Self> {
+        let inner_instructions = Option::<Vec<_>>::from(meta.inner_instructions)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|inner| {
+                let instructions = inner
+                    .instructions
+                    .into_iter()
+                    .map(|ix| match ix {
+                        UiInstruction::Compiled(compiled) => Ok(CompiledInstruction {
+                            program_id_index: compiled.program_id_index,
+                            accounts: compiled.accounts,
+                            data: bs58::decode(&compiled.data).into_vec().map_err(|e| {
+                                AnalyzerError::ParseError(format!("Invalid instruction data: {}", e))
+                            })?,
+                        }),
+                        UiInstruction::Parsed(_) => Err(AnalyzerError::ParseError(
+                            "Parsed inner instructions are not supported".into(),
+                        )),
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(InnerInstructions {
+                    index: inner.index as usize,
+                    instructions,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            err: meta.err.map(|e| e.to_string()),
+            fee: meta.fee,
+            pre_balances: meta.pre_balances,
+            post_balances: meta.post_balances,
+            pre_token_balances: token_balances(meta.pre_token_balances)?,
+            post_token_balances: token_balances(meta.post_token_balances)?,
+            inner_instructions,
+            log_messages: Option::<Vec<_>>::from(meta.log_messages).unwrap_or_default(),
+            loaded_addresses: Option::<UiLoadedAddresses>::from(meta.loaded_addresses)
+                .map(|loaded| loaded_addresses(&loaded))
+                .transpose()?
+                .unwrap_or_default(),
+        })
+    }
+}
+
+fn loaded_addresses(loaded: &UiLoadedAddresses) -> Result<LoadedAddresses> {
+    let parse = |keys: &[String]| {
+        keys.iter()
+            .map(|key| {
+                Pubkey::from_str(key)
+                    .map_err(|e| AnalyzerError::ParseError(format!("Invalid loaded address {}: {}", key, e)))
+            })
+            .collect::<Result<Vec<_>>>()
+    };
+    Ok(LoadedAddresses {
+        writable: parse(&loaded.writable)?,
+        readonly: parse(&loaded.readonly)?,
+    })
+}
+
+fn token_balances(
+    balances: OptionSerializer<Vec<UiTransactionTokenBalance>>,
+) -> Result<Vec<TokenBalance>> {
+    Option::<Vec<_>>::from(balances)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|balance: UiTransactionTokenBalance| {
+            let amount = balance.ui_token_amount.amount.parse::<u64>().map_err(|e| {
+                AnalyzerError::ParseError(format!("Invalid token amount: {}", e))
+            })?;
+            Ok(TokenBalance {
+                account_index: balance.account_index as usize,
+                mint: balance.mint,
+                owner: Option::from(balance.owner),
+                amount,
+                decimals: balance.ui_token_amount.decimals,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,43 @@
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashSet;
+use std::path::Path;
+use std::str::FromStr;
+use crate::error::{AnalyzerError, Result};
+
+/// Parses a watchlist: one wallet address per line. Blank lines and
+/// anything after a `#` are ignored.
+pub fn parse_watchlist(contents: &str) -> Result<Vec<Pubkey>> {
+    contents
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let address = line.split('#').next().unwrap_or_default().trim();
+            (!address.is_empty()).then_some((index + 1, address))
+        })
+        .map(|(line_number, address)| {
+            Pubkey::from_str(address).map_err(|e| {
+                AnalyzerError::ParseError(format!("Line {}: invalid wallet address {}: {}", line_number, address, e))
+            })
+        })
+        .collect()
+}
+
+/// Combines addresses given directly with those listed in `file`, keeping
+/// the first occurrence of each.
+pub fn load_watchlist(addresses: &[Pubkey], file: Option<&Path>) -> Result<Vec<Pubkey>> {
+    let mut watchlist = addresses.to_vec();
+    if let Some(path) = file {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| AnalyzerError::DataError(format!("Failed to read {}: {}", path.display(), e)))?;
+        let listed = parse_watchlist(&contents)
+            .map_err(|e| match e {
+                AnalyzerError::ParseError(msg) => AnalyzerError::ParseError(format!("{}: {}", path.display(), msg)),
+                e => e,
+            })?;
+        watchlist.extend(listed);
+    }
+
+    let mut seen = HashSet::new();
+    watchlist.retain(|address| seen.insert(*address));
+    Ok(watchlist)
+}
@@ -0,0 +1,6 @@
+pub mod analysis;
+pub mod error;
+pub mod ingestion;
+pub mod storage;
+pub mod types;
+pub mod visualization;
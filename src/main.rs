@@ -1,29 +1,58 @@
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
 use tokio::time::{sleep, Duration};
-use tracing::{info, warn, error, Level};
+use tokio::sync::mpsc::UnboundedReceiver;
+use tracing::{info, error, warn, Level};
 use tracing_subscriber::FmtSubscriber;
 use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
 use std::sync::Arc;
 
-mod ingestion;
-mod analysis;
-mod storage;
-mod types;
-mod error;
-mod visualization;
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+use solana_wallet_analyzer::{
+    analysis::{
+        metrics::MetricsCalculator,
+        pricing::{observations, PriceHistory, PriceOracle, DEFAULT_MAX_PRICE_AGE, USDC_MINT},
+        ranking::WalletRanker,
+    },
+    error::{AnalyzerError, Result},
+    ingestion::{
+        backfill::{BackfillBound, BackfillRange},
+        balances::WRAPPED_SOL_MINT,
+        cassette::CassetteMode,
+        client::{ClientConfig, SolanaClient, TOKEN_PROGRAM_IDS},
+        cursor::MAX_SIGNATURES_PER_PAGE,
+        decoders::DecoderRegistry,
+        dedup::unseen,
+        dump::DumpReader,
+        oracles::{read_price_dump, OracleFeed},
+        pool::RpcEndpoint,
+        reconcile::{flow_verdict, verdict, Verdict},
+        retry::RetryPolicy,
+        geyser::GeyserStream,
+        stream::{recv_batch, ws_url_from_rpc, LogStream, Signed},
+        transaction::TransactionWithMeta,
+        watchlist::load_watchlist,
+    },
+    storage::redis::{RedisStorage, DEDUP_TTL},
+    types::{CapitalFlow, TokenMetadata, TradeInfo, WalletMetrics},
+    visualization::{cli::display_dashboard, generate_dashboard_data},
+};
 
-use ingestion::client::SolanaClient;
-use analysis::{metrics::MetricsCalculator, ranking::WalletRanker};
-use storage::redis::RedisStorage;
-use types::{TradeInfo, WalletMetrics};
-use error::{AnalyzerError, Result};
-use visualization::{self, cli::display_dashboard, generate_dashboard_data};
+const ENDPOINT_STATS_INTERVAL: Duration = Duration::from_secs(60);
+const BLOCKS_CURSOR: &str = "blocks";
 
 #[derive(Parser, Debug)]
 #[command(author, version, about)]
 struct Args {
-    #[arg(long, env = "SOLANA_RPC_URL")]
-    rpc_url: String,
+    /// RPC endpoint, optionally weighted as `<url>#<weight>`; repeat the flag
+    /// (or comma-separate in the env var) to pool several endpoints; required
+    /// except for `import` and `--replay`
+    #[arg(long, env = "SOLANA_RPC_URL", value_delimiter = ',')]
+    rpc_url: Vec<RpcEndpoint>,
 
     #[arg(long, env = "REDIS_URL", default_value = "redis://127.0.0.1/")]
     redis_url: String,
@@ -34,11 +63,171 @@ struct Args {
     #[arg(long, default_value = "5")]
     update_interval: u64,
 
+    /// Transactions fetched from the RPC node in parallel
+    #[arg(long, default_value = "16")]
+    max_concurrent_requests: usize,
+
+    /// Sustained RPC request rate; 0 disables rate limiting
+    #[arg(long, default_value = "40")]
+    requests_per_second: f64,
+
+    /// Append every RPC request and response to this cassette file
+    #[arg(long, conflicts_with = "replay")]
+    record: Option<PathBuf>,
+
+    /// Serve RPC requests from a cassette recorded with `--record`, without network access
+    #[arg(long)]
+    replay: Option<PathBuf>,
+
+    /// Attempts per RPC request on rate limits, 5xx responses and network errors
+    #[arg(long, default_value = "5")]
+    max_retries: u32,
+
     #[arg(long, default_value = "info")]
     log_level: String,
 
     #[arg(long)]
     no_dashboard: bool,
+
+    /// Where new transactions are discovered
+    #[arg(long, value_enum, default_value_t = Source::Poll)]
+    source: Source,
+
+    /// PubSub endpoint for `--source websocket`; derived from the RPC URL when unset
+    #[arg(long, env = "SOLANA_WS_URL")]
+    ws_url: Option<String>,
+
+    /// Yellowstone gRPC endpoint for `--source geyser`
+    #[arg(long, env = "GEYSER_URL", required_if_eq("source", "geyser"))]
+    geyser_url: Option<String>,
+
+    /// Access token sent as the `x-token` header to the Geyser endpoint
+    #[arg(long, env = "GEYSER_X_TOKEN")]
+    geyser_x_token: Option<String>,
+
+    /// Commitment transactions are read at. Below `finalized`, trades are
+    /// provisional until reconciled against the finalized ledger
+    #[arg(long, value_enum, default_value_t = Commitment::Confirmed)]
+    commitment: Commitment,
+
+    /// Seconds between checks of provisional trades against the finalized ledger
+    #[arg(long, default_value = "30")]
+    reconcile_interval: u64,
+
+    /// Blocks fetched per cycle with `--source blocks`
+    #[arg(long, default_value = "10")]
+    blocks_per_batch: usize,
+
+    /// Seconds a processed transaction signature is remembered, so that
+    /// overlapping batches and restarts do not count it twice
+    #[arg(long, default_value_t = DEDUP_TTL)]
+    dedup_ttl: u64,
+
+    /// Wallet whose full history is synced and kept fresh every update
+    /// interval, alongside the chosen source; repeat or comma-separate
+    #[arg(long, env = "WATCHLIST", value_delimiter = ',')]
+    watch: Vec<Pubkey>,
+
+    /// File listing wallets to watch, one address per line; `#` starts a comment
+    #[arg(long, env = "WATCHLIST_FILE")]
+    watchlist_file: Option<PathBuf>,
+
+    /// Pyth or Switchboard USD price account of a mint, as
+    /// `<MINT>=<PRICE_ACCOUNT>`, polled every update interval and preferred
+    /// over swap-implied prices; give SOL's under the wrapped SOL mint;
+    /// repeat or comma-separate
+    #[arg(long, env = "ORACLES", value_delimiter = ',')]
+    oracle: Vec<OracleFeed>,
+
+    /// JSON-lines dump of `--oracle` price accounts, one `solana account
+    /// --output json` per line, loaded at startup; repeatable
+    #[arg(long)]
+    oracle_dump: Vec<PathBuf>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Source {
+    /// Page through getSignaturesForAddress every update interval
+    Poll,
+    /// Subscribe to DEX program logs over the PubSub websocket
+    Websocket,
+    /// Subscribe to DEX transactions on a Yellowstone gRPC (Geyser) endpoint
+    Geyser,
+    /// Walk consecutive confirmed blocks with getBlocks/getBlock
+    Blocks,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Commitment {
+    Confirmed,
+    Finalized,
+}
+
+impl From<Commitment> for CommitmentConfig {
+    fn from(commitment: Commitment) -> Self {
+        match commitment {
+            Commitment::Confirmed => CommitmentConfig::confirmed(),
+            Commitment::Finalized => CommitmentConfig::finalized(),
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Analyze a historical slot or time range instead of polling for new transactions
+    Backfill(BackfillArgs),
+    /// Analyze recorded getTransaction/getBlock responses from JSON-lines files, without RPC
+    Import(ImportArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct ImportArgs {
+    /// JSON-lines files with one RPC response per line
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+struct BackfillArgs {
+    #[arg(long, conflicts_with = "start_time", required_unless_present = "start_time")]
+    start_slot: Option<u64>,
+
+    /// Defaults to the current tip of the chain
+    #[arg(long, conflicts_with = "end_time")]
+    end_slot: Option<u64>,
+
+    /// RFC 3339 timestamp or Unix seconds
+    #[arg(long, value_parser = parse_timestamp)]
+    start_time: Option<i64>,
+
+    /// RFC 3339 timestamp or Unix seconds; defaults to now
+    #[arg(long, value_parser = parse_timestamp)]
+    end_time: Option<i64>,
+}
+
+impl BackfillArgs {
+    fn range(&self) -> BackfillRange {
+        let start = match (self.start_slot, self.start_time) {
+            (Some(slot), _) => BackfillBound::Slot(slot),
+            (None, Some(ts)) => BackfillBound::Timestamp(ts),
+            (None, None) => unreachable!("clap requires a start bound"),
+        };
+        let end = self.end_slot.map(BackfillBound::Slot)
+            .or(self.end_time.map(BackfillBound::Timestamp));
+        BackfillRange { start, end }
+    }
+}
+
+fn parse_timestamp(value: &str) -> std::result::Result<i64, String> {
+    if let Ok(ts) = value.parse::<i64>() {
+        return Ok(ts);
+    }
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.timestamp())
+        .map_err(|e| format!("expected RFC 3339 or Unix seconds: {}", e))
 }
 
 #[tokio::main]
@@ -61,7 +250,7 @@ async fn main() -> Result<()> {
         .with_file(true)
         .with_line_number(true)
         .pretty()
-        .build();
+        .finish();
 
     tracing::subscriber::set_global_default(subscriber)
         .expect("Failed to set tracing subscriber");
@@ -70,15 +259,125 @@ async fn main() -> Result<()> {
     
     // Initialize components
     let storage = Arc::new(RedisStorage::new(&args.redis_url)
-        .map_err(|e| AnalyzerError::StorageError(format!("Failed to initialize Redis: {}", e)))?);    
-    let client = Arc::new(SolanaClient::new(&args.rpc_url)
-        .map_err(|e| AnalyzerError::SolanaClientError(format!("Failed to initialize Solana client: {}", e)))?);    
+        .map_err(|e| AnalyzerError::StorageError(format!("Failed to initialize Redis: {}", e)))?
+        .with_dedup_ttl(args.dedup_ttl));    
     let metrics_calculator = Arc::new(MetricsCalculator::new());
     let wallet_ranker = Arc::new(WalletRanker::new());
+    load_oracle_dumps(&storage, &args.oracle_dump, &args.oracle).await?;
+
+    if let Some(Command::Import(import_args)) = &args.command {
+        let client = SolanaClient::offline(DecoderRegistry::default());
+        return run_import(
+            &client,
+            &storage,
+            &metrics_calculator,
+            &wallet_ranker,
+            &import_args.files,
+            args.batch_size as usize,
+            !args.no_dashboard,
+        ).await;
+    }
+    let watchlist = load_watchlist(&args.watch, args.watchlist_file.as_deref())?;
+    let mut endpoints = args.rpc_url.clone();
+    if endpoints.is_empty() {
+        match &args.replay {
+            Some(path) => endpoints.push(RpcEndpoint::new(&format!("replay:{}", path.display()))),
+            None => return Err(AnalyzerError::DataError("--rpc-url is required".into())),
+        }
+    }
+    let cassette = match (&args.record, &args.replay) {
+        (Some(path), _) => Some(CassetteMode::Record(path.clone())),
+        (None, Some(path)) => Some(CassetteMode::Replay(path.clone())),
+        (None, None) => None,
+    };
+
+    let client_config = ClientConfig {
+        max_concurrent_requests: args.max_concurrent_requests,
+        requests_per_second: args.requests_per_second,
+        burst: args.max_concurrent_requests as u32,
+        retry: RetryPolicy {
+            max_attempts: args.max_retries.max(1),
+            ..RetryPolicy::default()
+        },
+        cassette,
+        commitment: args.commitment.into(),
+    };
+    let client = Arc::new(SolanaClient::with_endpoints(endpoints, DecoderRegistry::default(), client_config)
+        .map_err(|e| AnalyzerError::SolanaClientError(format!("Failed to initialize Solana client: {}", e)))?);    
     
     info!("Initialization complete");
-    
-    // Main processing loop
+
+    let stats_client = Arc::clone(&client);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(ENDPOINT_STATS_INTERVAL);
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            stats_client.log_endpoint_stats();
+        }
+    });
+
+    if !args.oracle.is_empty() {
+        tokio::spawn(run_oracle_poller(
+            Arc::clone(&client),
+            Arc::clone(&storage),
+            args.oracle.clone(),
+            Duration::from_secs(args.update_interval),
+        ));
+    }
+
+    if client.is_provisional() {
+        tokio::spawn(run_reconciler(
+            Arc::clone(&client),
+            Arc::clone(&storage),
+            Arc::clone(&metrics_calculator),
+            Arc::clone(&wallet_ranker),
+            Duration::from_secs(args.reconcile_interval),
+            !args.no_dashboard,
+        ));
+    }
+
+    if !watchlist.is_empty() {
+        info!("Watching {} wallets", watchlist.len());
+        tokio::spawn(run_watchlist(
+            Arc::clone(&client),
+            Arc::clone(&storage),
+            Arc::clone(&metrics_calculator),
+            Arc::clone(&wallet_ranker),
+            watchlist,
+            args.batch_size as usize,
+            Duration::from_secs(args.update_interval),
+            !args.no_dashboard,
+        ));
+    }
+
+    if let Some(Command::Backfill(backfill_args)) = &args.command {
+        return run_backfill(
+            client,
+            storage,
+            metrics_calculator,
+            wallet_ranker,
+            backfill_args.range(),
+            !args.no_dashboard,
+        ).await;
+    }
+
+    match args.source {
+        Source::Websocket => {
+            return run_stream(client, storage, metrics_calculator, wallet_ranker, &args).await;
+        }
+        Source::Geyser => {
+            return run_geyser(client, storage, metrics_calculator, wallet_ranker, &args).await;
+        }
+        Source::Blocks => {
+            return run_blocks(client, storage, metrics_calculator, wallet_ranker, &args).await;
+        }
+        Source::Poll => {}
+    }
+
+    // Main processing loop; transient failures back off before the next poll
+    let backoff = RetryPolicy::default();
+    let mut failures = 0;
     loop {
         match process_batch(
             Arc::clone(&client),
@@ -90,10 +389,301 @@ async fn main() -> Result<()> {
         ).await {
             Ok(processed) => {
                 info!("Successfully processed {} transactions", processed);
+                failures = 0;
             }
             Err(e) => {
                 error!("Error processing batch: {}", e);
-                sleep(Duration::from_secs(1)).await;
+                if e.is_retryable() {
+                    failures += 1;
+                    sleep(backoff.delay(failures, &e)).await;
+                }
+            }
+        }
+
+        sleep(Duration::from_secs(args.update_interval)).await;
+    }
+}
+
+/// Walks every DEX program's signature history backwards through `range`,
+/// resuming from the last completed page of a previous run.
+async fn run_backfill(
+    client: Arc<SolanaClient>,
+    storage: Arc<RedisStorage>,
+    metrics_calculator: Arc<MetricsCalculator>,
+    wallet_ranker: Arc<WalletRanker>,
+    range: BackfillRange,
+    show_dashboard: bool,
+) -> Result<()> {
+    info!("Backfilling {}", range.id());
+
+    // Aggregator transactions mention several DEX programs; fetch each only once
+    let mut seen = HashSet::new();
+
+    for program_id in client.dex_program_ids() {
+        let cursor_name = format!("backfill:{}:{}", program_id, range.id());
+        let mut cursor = storage.get_cursor(&cursor_name).await
+            .map_err(|e| AnalyzerError::StorageError(format!("Failed to load cursor: {}", e)))?;
+        let mut first = None;
+        let mut processed = 0;
+
+        loop {
+            let page = client.get_signatures_before(&program_id, cursor.as_ref(), MAX_SIGNATURES_PER_PAGE).await?;
+            if first.is_none() {
+                first = page.first().cloned();
+            }
+            let oldest = page.last().cloned();
+            let page = range.select_page(page, MAX_SIGNATURES_PER_PAGE);
+
+            let signatures: Vec<_> = page.signatures.into_iter()
+                .map(|s| s.signature)
+                .filter(|signature| seen.insert(signature.clone()))
+                .collect();
+            let transactions = client.fetch_transactions(&signatures).await?;
+            processed += process_transactions(
+                &client,
+                &storage,
+                &metrics_calculator,
+                &wallet_ranker,
+                &transactions,
+                show_dashboard,
+            ).await?;
+
+            if let Some(next) = &page.cursor {
+                storage.store_cursor(&cursor_name, next).await
+                    .map_err(|e| AnalyzerError::StorageError(format!("Failed to store cursor: {}", e)))?;
+            }
+            cursor = page.cursor;
+
+            if let (Some(first), Some(oldest)) = (&first, &oldest) {
+                let progress = range.progress(first, oldest).unwrap_or_default();
+                info!(
+                    "Backfill {}: reached slot {}, {} transactions processed, {:.1}% done",
+                    program_id, oldest.slot, processed, progress * 100.0
+                );
+            }
+
+            if page.done {
+                break;
+            }
+        }
+
+        info!("Backfill {} complete: {} transactions processed", program_id, processed);
+    }
+
+    Ok(())
+}
+
+/// Runs recorded RPC responses through the pipeline in batches of
+/// `batch_size`. Unreadable lines are logged and skipped, and a transaction
+/// present in several dumps is processed once.
+async fn run_import(
+    client: &SolanaClient,
+    storage: &RedisStorage,
+    metrics_calculator: &MetricsCalculator,
+    wallet_ranker: &WalletRanker,
+    files: &[PathBuf],
+    batch_size: usize,
+    show_dashboard: bool,
+) -> Result<()> {
+    let mut seen = HashSet::new();
+    let mut processed = 0;
+    let mut skipped = 0;
+
+    for path in files {
+        info!("Importing {}", path.display());
+        let mut batch = Vec::with_capacity(batch_size);
+        for transaction in DumpReader::open(path)? {
+            match transaction {
+                Ok(tx) if seen.insert(tx.signature.clone()) => batch.push(tx),
+                Ok(_) => {}
+                Err(e) => {
+                    warn!("Skipping {}: {}", path.display(), e);
+                    skipped += 1;
+                }
+            }
+            if batch.len() >= batch_size.max(1) {
+                processed += process_transactions(client, storage, metrics_calculator, wallet_ranker, &batch, show_dashboard).await?;
+                batch.clear();
+            }
+        }
+        if !batch.is_empty() {
+            processed += process_transactions(client, storage, metrics_calculator, wallet_ranker, &batch, show_dashboard).await?;
+        }
+    }
+
+    info!("Import complete: {} transactions processed, {} skipped", processed, skipped);
+    Ok(())
+}
+
+/// Processes DEX transactions as their signatures arrive over the websocket,
+/// in batches of up to `batch_size` collected for at most `update_interval`.
+async fn run_stream(
+    client: Arc<SolanaClient>,
+    storage: Arc<RedisStorage>,
+    metrics_calculator: Arc<MetricsCalculator>,
+    wallet_ranker: Arc<WalletRanker>,
+    args: &Args,
+) -> Result<()> {
+    let ws_url = args.ws_url.clone()
+        .or_else(|| args.rpc_url.first().map(|endpoint| ws_url_from_rpc(&endpoint.url)))
+        .ok_or_else(|| AnalyzerError::DataError("--ws-url is required for the websocket source".into()))?;
+    info!("Streaming DEX program logs from {}", ws_url);
+
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+    let stream = LogStream::new(&ws_url, client.dex_program_ids(), Arc::clone(&client));
+    let handle = stream.spawn(sender);
+
+    process_stream(&mut receiver, args, async |signatures: &[String]| {
+        let transactions = client.fetch_transactions(signatures).await?;
+        process_transactions(
+            &client,
+            &storage,
+            &metrics_calculator,
+            &wallet_ranker,
+            &transactions,
+            !args.no_dashboard,
+        ).await
+    }).await;
+
+    handle.await
+        .map_err(|e| AnalyzerError::NetworkError(format!("Websocket stream stopped: {}", e)))
+}
+
+/// Hands each batch of up to `batch_size` items, collected for at most
+/// `update_interval`, to `process`. A batch that fails is retried before the
+/// next one is taken, so none of its items are lost: with backoff while the
+/// error is retryable, and after `update_interval` otherwise.
+async fn process_stream<T: Signed>(
+    receiver: &mut UnboundedReceiver<T>,
+    args: &Args,
+    mut process: impl AsyncFnMut(&[T]) -> Result<usize>,
+) {
+    let window = Duration::from_secs(args.update_interval);
+    let backoff = RetryPolicy::default();
+    let mut failures = 0;
+    let mut failed = None;
+    loop {
+        let batch = match failed.take() {
+            Some(batch) => batch,
+            None => match recv_batch(receiver, args.batch_size as usize, window).await {
+                Some(batch) => batch,
+                None => return,
+            },
+        };
+
+        match process(&batch).await {
+            Ok(processed) => {
+                info!("Successfully processed {} transactions", processed);
+                failures = 0;
+            }
+            Err(e) => {
+                error!("Error processing batch, retrying it: {}", e);
+                let delay = match e.is_retryable() {
+                    true => {
+                        failures += 1;
+                        backoff.delay(failures, &e)
+                    }
+                    false => window,
+                };
+                sleep(delay).await;
+                failed = Some(batch);
+            }
+        }
+    }
+}
+
+/// Processes DEX transactions pushed by a Geyser endpoint, batched like
+/// `run_stream`. Transactions arrive with their meta, so no RPC fetch is needed.
+async fn run_geyser(
+    client: Arc<SolanaClient>,
+    storage: Arc<RedisStorage>,
+    metrics_calculator: Arc<MetricsCalculator>,
+    wallet_ranker: Arc<WalletRanker>,
+    args: &Args,
+) -> Result<()> {
+    let endpoint = args.geyser_url.as_deref()
+        .ok_or_else(|| AnalyzerError::DataError("--geyser-url is required for the geyser source".into()))?;
+    info!("Streaming DEX transactions from Geyser endpoint {}", endpoint);
+
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+    let stream = GeyserStream::new(endpoint, args.geyser_x_token.clone(), client.dex_program_ids())
+        .with_commitment(client.commitment());
+    let handle = stream.spawn(sender);
+
+    // The stream has moved past a batch once it is received, so a failed one
+    // is retried rather than dropped
+    process_stream(&mut receiver, args, async |transactions: &[TransactionWithMeta]| {
+        process_transactions(
+            &client,
+            &storage,
+            &metrics_calculator,
+            &wallet_ranker,
+            transactions,
+            !args.no_dashboard,
+        ).await
+    }).await;
+
+    handle.await
+        .map_err(|e| AnalyzerError::NetworkError(format!("Geyser stream stopped: {}", e)))
+}
+
+/// Scans every confirmed block from the stored slot cursor on, or from the
+/// current tip on the first run, keeping the DEX transactions. The cursor
+/// advances only once a batch of blocks has been processed.
+async fn run_blocks(
+    client: Arc<SolanaClient>,
+    storage: Arc<RedisStorage>,
+    metrics_calculator: Arc<MetricsCalculator>,
+    wallet_ranker: Arc<WalletRanker>,
+    args: &Args,
+) -> Result<()> {
+    let stored = storage.get_slot_cursor(BLOCKS_CURSOR).await
+        .map_err(|e| AnalyzerError::StorageError(format!("Failed to load cursor: {}", e)))?;
+    let mut last_slot = match stored {
+        Some(slot) => slot,
+        None => client.get_slot().await?.saturating_sub(1),
+    };
+    info!("Scanning blocks after slot {}", last_slot);
+
+    let backoff = RetryPolicy::default();
+    let mut failures = 0;
+    loop {
+        let batch = async {
+            let slots = client.get_block_slots(last_slot + 1, args.blocks_per_batch.max(1)).await?;
+            let Some(&newest) = slots.last() else {
+                return Ok(None);
+            };
+            let transactions = client.fetch_blocks(&slots).await?;
+            let processed = process_transactions(
+                &client,
+                &storage,
+                &metrics_calculator,
+                &wallet_ranker,
+                &transactions,
+                !args.no_dashboard,
+            ).await?;
+            storage.store_slot_cursor(BLOCKS_CURSOR, newest).await
+                .map_err(|e| AnalyzerError::StorageError(format!("Failed to store cursor: {}", e)))?;
+            Ok::<_, AnalyzerError>(Some((slots.len(), newest, processed)))
+        };
+
+        match batch.await {
+            Ok(Some((blocks, newest, processed))) => {
+                info!("Processed {} DEX transactions from {} blocks up to slot {}", processed, blocks, newest);
+                last_slot = newest;
+                failures = 0;
+                // Keep going without pausing while behind the tip
+                if blocks >= args.blocks_per_batch {
+                    continue;
+                }
+            }
+            Ok(None) => {}
+            Err(e) => {
+                error!("Error scanning blocks: {}", e);
+                if e.is_retryable() {
+                    failures += 1;
+                    sleep(backoff.delay(failures, &e)).await;
+                }
             }
         }
 
@@ -101,6 +691,108 @@ async fn main() -> Result<()> {
     }
 }
 
+/// Stores the current price of every oracle feed each `interval`.
+async fn run_oracle_poller(
+    client: Arc<SolanaClient>,
+    storage: Arc<RedisStorage>,
+    feeds: Vec<OracleFeed>,
+    interval: Duration,
+) {
+    loop {
+        match client.fetch_oracle_prices(&feeds).await {
+            Ok(prices) => {
+                if let Err(e) = storage.store_oracle_prices(&prices).await.map_err(|e| e.to_string()) {
+                    warn!("Failed to store oracle prices: {}", e);
+                }
+            }
+            Err(e) => warn!("Failed to fetch oracle prices: {}", e),
+        }
+        sleep(interval).await;
+    }
+}
+
+/// Stores the prices of `feeds` recorded in account dumps. Unreadable lines
+/// are logged and skipped.
+async fn load_oracle_dumps(storage: &RedisStorage, files: &[PathBuf], feeds: &[OracleFeed]) -> Result<()> {
+    for path in files {
+        let file = File::open(path)
+            .map_err(|e| AnalyzerError::DataError(format!("Failed to open {}: {}", path.display(), e)))?;
+        let mut prices = Vec::new();
+        for price in read_price_dump(BufReader::new(file), feeds) {
+            match price {
+                Ok(price) => prices.push(price),
+                Err(e) => warn!("Skipping {}: {}", path.display(), e),
+            }
+        }
+        info!("Loaded {} oracle prices from {}", prices.len(), path.display());
+        storage.store_oracle_prices(&prices).await
+            .map_err(|e| AnalyzerError::StorageError(format!("Failed to store oracle prices: {}", e)))?;
+    }
+    Ok(())
+}
+
+/// Syncs the history of every watched wallet each `interval`, so they get
+/// metrics whether or not they show up in the sampled transactions. A
+/// wallet's batches, of up to `batch_size` transactions, are processed
+/// until it is caught up at both ends, and its cursor is stored after each.
+#[allow(clippy::too_many_arguments)]
+async fn run_watchlist(
+    client: Arc<SolanaClient>,
+    storage: Arc<RedisStorage>,
+    metrics_calculator: Arc<MetricsCalculator>,
+    wallet_ranker: Arc<WalletRanker>,
+    watchlist: Vec<Pubkey>,
+    batch_size: usize,
+    interval: Duration,
+    show_dashboard: bool,
+) {
+    loop {
+        for wallet in &watchlist {
+            if let Err(e) = sync_wallet(&client, &storage, &metrics_calculator, &wallet_ranker, wallet, batch_size, show_dashboard).await {
+                error!("Error syncing watched wallet {}: {}", wallet, e);
+            }
+        }
+        sleep(interval).await;
+    }
+}
+
+async fn sync_wallet(
+    client: &SolanaClient,
+    storage: &RedisStorage,
+    metrics_calculator: &MetricsCalculator,
+    wallet_ranker: &WalletRanker,
+    wallet: &Pubkey,
+    batch_size: usize,
+    show_dashboard: bool,
+) -> Result<()> {
+    let address = wallet.to_string();
+    let mut cursor = storage.get_watch_cursor(&address).await
+        .map_err(|e| AnalyzerError::StorageError(format!("Failed to load cursor: {}", e)))?;
+
+    loop {
+        let batch = client.get_wallet_transactions(wallet, &cursor, batch_size.max(1)).await?;
+        let processed = process_transactions(
+            client,
+            storage,
+            metrics_calculator,
+            wallet_ranker,
+            &batch.transactions,
+            show_dashboard,
+        ).await?;
+        storage.store_watch_cursor(&address, &batch.cursor).await
+            .map_err(|e| AnalyzerError::StorageError(format!("Failed to store cursor: {}", e)))?;
+        cursor = batch.cursor;
+
+        if processed > 0 {
+            info!("Synced {} transactions of watched wallet {}", processed, address);
+        }
+        if !batch.more {
+            return Ok(());
+        }
+    }
+}
+
+/// Polls every token program for new transactions, each from its own cursor.
 async fn process_batch(
     client: Arc<SolanaClient>,
     storage: Arc<RedisStorage>,
@@ -109,33 +801,279 @@ async fn process_batch(
     batch_size: u64,
     show_dashboard: bool,
 ) -> Result<usize> {
-    let transactions = client.get_recent_transactions().await
-        .map_err(|e| AnalyzerError::SolanaClientError(format!("Failed to fetch transactions: {}", e)))?;
-    
+    let mut processed_count = 0;
+    for program in TOKEN_PROGRAM_IDS {
+        let cursor_address = program.to_string();
+        let cursor = storage.get_cursor(&cursor_address).await
+            .map_err(|e| AnalyzerError::StorageError(format!("Failed to load cursor: {}", e)))?;
+        let batch = client.get_recent_transactions(&program, cursor.as_ref(), batch_size).await?;
+
+        processed_count += process_transactions(
+            &client,
+            &storage,
+            &metrics_calculator,
+            &wallet_ranker,
+            &batch.transactions,
+            show_dashboard,
+        ).await?;
+
+        // Only advance the cursor once the batch has been fully processed, so a
+        // crash or restart resumes from the last completed batch
+        if let Some(cursor) = &batch.cursor {
+            storage.store_cursor(&cursor_address, cursor).await
+                .map_err(|e| AnalyzerError::StorageError(format!("Failed to store cursor: {}", e)))?;
+        }
+    }
+
+    Ok(processed_count)
+}
+
+/// Decodes trades, and the capital flows of transactions without one,
+/// appends them to each wallet's history and recomputes the metrics and
+/// rankings of every wallet involved. Transactions whose signatures were
+/// processed before, within the dedup TTL, or are claimed by another task,
+/// are skipped.
+async fn process_transactions(
+    client: &SolanaClient,
+    storage: &RedisStorage,
+    metrics_calculator: &MetricsCalculator,
+    wallet_ranker: &WalletRanker,
+    transactions: &[TransactionWithMeta],
+    show_dashboard: bool,
+) -> Result<usize> {
+    info!("Fetched {} transactions", transactions.len());
+
+    // Claim each transaction, skipping those processed in an earlier batch or
+    // run, or being processed by another task right now
+    let signatures: Vec<_> = transactions.iter().map(|tx| tx.signature.clone()).collect();
+    let claimed = storage.claim_signatures(&signatures).await
+        .map_err(|e| AnalyzerError::StorageError(format!("Failed to claim signatures: {}", e)))?;
+    let seen: Vec<_> = claimed.iter().map(|claimed| !claimed).collect();
+    let (transactions, duplicates) = unseen(transactions, &seen);
+    if duplicates > 0 {
+        match storage.count_duplicates(duplicates as u64).await.map_err(|e| e.to_string()) {
+            Ok(total) => info!("Skipped {} already processed transactions, {} in total", duplicates, total),
+            Err(e) => warn!("Failed to count duplicate transactions: {}", e),
+        }
+    }
     let processed_count = transactions.len();
-    info!("Fetched {} transactions", processed_count);
 
+    let claims: Vec<_> = transactions.iter().map(|tx| tx.signature.as_str()).collect();
+    let (trade_infos, flows) = match store_batch(client, storage, metrics_calculator, &transactions).await {
+        Ok(stored) => stored,
+        Err(e) => {
+            // Let a later attempt pick the batch up again
+            if let Err(e) = storage.release_signatures(&claims).await.map_err(|e| e.to_string()) {
+                warn!("Failed to release claimed signatures: {}", e);
+            }
+            return Err(e);
+        }
+    };
+
+    // Only once everything they yielded is stored, so a failed batch is retried
+    storage.mark_processed(&claims).await
+        .map_err(|e| AnalyzerError::StorageError(format!("Failed to mark transactions processed: {}", e)))?;
+
+    let wallets: HashSet<_> = trade_infos.iter()
+        .map(|trade| trade.wallet_address.clone())
+        .chain(flows.iter().map(|flow| flow.wallet_address.clone()))
+        .collect();
+
+    info!("Found trades for {} unique wallets", wallets.len());
+
+    let all_metrics = recompute_wallets(storage, metrics_calculator, &wallets).await;
+    let top_wallets = match update_top_wallets(storage, wallet_ranker, &wallets, all_metrics).await {
+        Ok(top_wallets) => top_wallets,
+        Err(e) => {
+            error!("Failed to update top wallets: {}", e);
+            return Ok(processed_count);
+        }
+    };
+
+    // Generate and display dashboard if enabled
+    if show_dashboard {
+        let dashboard_data = generate_dashboard_data(&top_wallets);
+        display_dashboard(&dashboard_data);
+    }
+
+    Ok(processed_count)
+}
+
+/// Replaces the entries of `wallets` among the stored top wallets with their
+/// recomputed metrics, and stores the new top 100, which is returned.
+async fn update_top_wallets(
+    storage: &RedisStorage,
+    wallet_ranker: &WalletRanker,
+    wallets: &HashSet<String>,
+    recomputed: Vec<WalletMetrics>,
+) -> Result<Vec<WalletMetrics>> {
+    let mut top_wallets = storage.get_top_wallets(100).await
+        .map_err(|e| AnalyzerError::StorageError(format!("Failed to load top wallets: {}", e)))?;
+    top_wallets.retain(|metrics| !wallets.contains(&metrics.address));
+    top_wallets.extend(recomputed);
+    let top_wallets: Vec<_> = wallet_ranker.rank_wallets(&top_wallets).into_iter().take(100).collect();
+    if let Err(e) = storage.store_top_wallets(&top_wallets).await {
+        error!("Failed to store top wallets: {}", e);
+    }
+    Ok(top_wallets)
+}
+
+/// Decodes the trades and capital flows of claimed transactions, and stores them.
+async fn store_batch(
+    client: &SolanaClient,
+    storage: &RedisStorage,
+    metrics_calculator: &MetricsCalculator,
+    transactions: &[&TransactionWithMeta],
+) -> Result<(Vec<TradeInfo>, Vec<CapitalFlow>)> {
     // Process transactions in parallel
-    let trade_infos: Vec<_> = transactions.par_iter()
-        .filter_map(|tx| client.extract_trade_info(tx))
+    let mut trade_infos: Vec<_> = transactions.par_iter()
+        .filter_map(|tx| client.extract_trade_info(tx).or_else(|| client.extract_failed_swap(tx)))
         .collect();
+    label_tokens(client, storage, &mut trade_infos).await;
+    price_trades(storage, metrics_calculator, &mut trade_infos).await;
 
-    // Group by wallet (in parallel)
-    let mut wallet_trades = std::collections::HashMap::new();
-    trade_infos.into_par_iter().for_each(|trade| {
-        wallet_trades
-            .entry(trade.wallet_address.clone())
-            .or_insert_with(Vec::new)
-            .push(trade);
-    });
+    storage.store_trades(&trade_infos).await
+        .map_err(|e| AnalyzerError::StorageError(format!("Failed to store trades: {}", e)))?;
+
+    let traded: HashSet<_> = trade_infos.iter().map(|trade| trade.transaction_hash.as_str()).collect();
+    let flows: Vec<_> = transactions.par_iter()
+        .filter(|tx| !traded.contains(tx.signature.as_str()))
+        .flat_map_iter(|tx| client.extract_capital_flows(tx))
+        .collect();
+    storage.store_flows(&flows).await
+        .map_err(|e| AnalyzerError::StorageError(format!("Failed to store capital flows: {}", e)))?;
+
+    Ok((trade_infos, flows))
+}
+
+/// Records the prices implied by `trades` in the stored candles, then values
+/// the trades from the oracle prices and candles around their time.
+async fn price_trades(storage: &RedisStorage, metrics_calculator: &MetricsCalculator, trades: &mut [TradeInfo]) {
+    let (Some(from), Some(to)) = (
+        trades.iter().map(|trade| trade.timestamp).min(),
+        trades.iter().map(|trade| trade.timestamp).max(),
+    ) else {
+        return;
+    };
+
+    let oracle = PriceOracle::default();
+    let observations = observations(trades);
+    if let Err(e) = storage.store_price_observations(&observations, &oracle).await.map_err(|e| e.to_string()) {
+        warn!("Failed to store price candles: {}", e);
+    }
+
+    let mints: Vec<String> = trades.iter()
+        .flat_map(|trade| [trade.token_in_mint.as_str(), trade.token_out_mint.as_str()])
+        .chain([USDC_MINT, WRAPPED_SOL_MINT])
+        .filter(|mint| !mint.is_empty())
+        .map(String::from)
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    let mut prices = PriceHistory::new(DEFAULT_MAX_PRICE_AGE);
+    match storage.get_candles(&mints, from - DEFAULT_MAX_PRICE_AGE, to).await.map_err(|e| e.to_string()) {
+        Ok(candles) => prices.insert(candles),
+        Err(e) => {
+            warn!("Failed to load price candles: {}", e);
+            prices.insert(oracle.candles(observations));
+        }
+    }
+    match storage.get_oracle_prices(&mints, from - DEFAULT_MAX_PRICE_AGE, to + DEFAULT_MAX_PRICE_AGE).await.map_err(|e| e.to_string()) {
+        Ok(oracle_prices) => prices.insert_oracle_prices(oracle_prices),
+        Err(e) => warn!("Failed to load oracle prices: {}", e),
+    }
+    metrics_calculator.value_trades(trades, &prices);
+}
+
+/// Fills in the token symbols of `trades` from cached token metadata,
+/// resolving mints not cached yet from the chain. Tokens that cannot be
+/// resolved are left without a symbol.
+async fn label_tokens(client: &SolanaClient, storage: &RedisStorage, trades: &mut [TradeInfo]) {
+    let mints: Vec<String> = trades.iter()
+        .flat_map(|trade| [&trade.token_in_mint, &trade.token_out_mint])
+        .filter(|mint| !mint.is_empty())
+        .cloned()
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    if mints.is_empty() {
+        return;
+    }
+
+    let mut tokens: HashMap<String, TokenMetadata> = match storage.get_token_metadata(&mints).await.map_err(|e| e.to_string()) {
+        Ok(tokens) => tokens,
+        Err(e) => {
+            warn!("Failed to load token metadata: {}", e);
+            HashMap::new()
+        }
+    };
+    let missing: Vec<Pubkey> = mints.iter()
+        .filter(|mint| !tokens.contains_key(*mint))
+        .filter_map(|mint| Pubkey::from_str(mint).ok())
+        .collect();
+    if !missing.is_empty() && !client.is_offline() {
+        match client.fetch_token_metadata(&missing).await {
+            Ok(fetched) => {
+                if let Err(e) = storage.store_token_metadata(&fetched).await.map_err(|e| e.to_string()) {
+                    warn!("Failed to store token metadata: {}", e);
+                }
+                tokens.extend(fetched.into_iter().map(|token| (token.mint.clone(), token)));
+            }
+            Err(e) => warn!("Failed to fetch token metadata: {}", e),
+        }
+    }
+
+    for trade in trades.iter_mut() {
+        if let Some(token) = tokens.get(&trade.token_in_mint) {
+            trade.token_in_symbol = token.label();
+        }
+        if let Some(token) = tokens.get(&trade.token_out_mint) {
+            trade.token_out_symbol = token.label();
+        }
+    }
+}
+
+/// Recomputes and stores the metrics of `wallets` from their full trade
+/// and capital flow histories. Wallets left without trades are removed from
+/// the rankings.
+async fn recompute_wallets(
+    storage: &RedisStorage,
+    metrics_calculator: &MetricsCalculator,
+    wallets: &HashSet<String>,
+) -> Vec<WalletMetrics> {
+    // Load each wallet's full history, in chronological order
+    let mut wallet_histories = Vec::with_capacity(wallets.len());
+    for wallet in wallets {
+        // Errors are not Send, so none may be held across an await
+        match storage.get_wallet_trades(wallet).await.map_err(|e| e.to_string()) {
+            Ok(trades) if trades.is_empty() => {
+                if let Err(e) = storage.remove_wallet(wallet).await.map_err(|e| e.to_string()) {
+                    error!("Failed to remove wallet {}: {}", wallet, e);
+                }
+            }
+            Ok(trades) => {
+                let flows = storage.get_wallet_flows(wallet).await.map_err(|e| e.to_string()).unwrap_or_else(|e| {
+                    error!("Failed to load capital flows for {}: {}", wallet, e);
+                    Vec::new()
+                });
+                wallet_histories.push((trades, flows));
+            }
+            Err(e) => error!("Failed to load trades for {}: {}", wallet, e),
+        }
+    }
 
-    info!("Found trades for {} unique wallets", wallet_trades.len());
+    wallet_histories.par_iter_mut().for_each(|(trades, _)| {
+        metrics_calculator.apply_realized_pnl(trades);
+    });
 
     // Calculate metrics in parallel
-    let all_metrics: Vec<_> = wallet_trades.par_iter()
-        .filter_map(|(_, trades)| {
+    let all_metrics: Vec<_> = wallet_histories.par_iter()
+        .filter_map(|(trades, flows)| {
             match metrics_calculator.calculate_metrics(trades) {
-                Ok(metrics) => Some(metrics),
+                Ok(mut metrics) => {
+                    metrics_calculator.apply_capital_flows(&mut metrics, flows);
+                    Some(metrics)
+                }
                 Err(e) => {
                     error!("Failed to calculate metrics: {}", e);
                     None
@@ -144,29 +1082,123 @@ async fn process_batch(
         })
         .collect();
 
-    // Store metrics in parallel batches
-    let metrics_chunks = all_metrics.chunks(100);
-    for chunk in metrics_chunks {
-        let futures: Vec<_> = chunk.iter()
-            .map(|metrics| storage.store_metrics(metrics))
-            .collect();
-        
-        futures::future::join_all(futures).await;
+    for metrics in &all_metrics {
+        if let Err(e) = storage.store_metrics(metrics).await.map_err(|e| e.to_string()) {
+            error!("Failed to store metrics for {}: {}", metrics.address, e);
+        }
     }
 
-    // Rank wallets and update top performers
-    let ranked_wallets = wallet_ranker.rank_wallets(&all_metrics);
-    let top_wallets = ranked_wallets.into_iter().take(100).collect::<Vec<_>>();
-    
-    if let Err(e) = storage.store_top_wallets(&top_wallets).await {
-        error!("Failed to store top wallets: {}", e);
+    all_metrics
+}
+
+/// Periodically checks provisional trades and capital flows whose slots have
+/// been finalized. Finalized ones lose the provisional flag; rolled back ones are removed
+/// and their wallets' metrics and the top wallets recomputed. Transactions
+/// that moved to another slot are refetched and processed again.
+async fn run_reconciler(
+    client: Arc<SolanaClient>,
+    storage: Arc<RedisStorage>,
+    metrics_calculator: Arc<MetricsCalculator>,
+    wallet_ranker: Arc<WalletRanker>,
+    interval: Duration,
+    show_dashboard: bool,
+) {
+    loop {
+        sleep(interval).await;
+        if let Err(e) = reconcile(&client, &storage, &metrics_calculator, &wallet_ranker, show_dashboard).await {
+            error!("Error reconciling provisional trades: {}", e);
+        }
     }
+}
 
-    // Generate and display dashboard if enabled
-    if show_dashboard {
-        let dashboard_data = generate_dashboard_data(&top_wallets);
-        display_dashboard(&dashboard_data);
+async fn reconcile(
+    client: &SolanaClient,
+    storage: &RedisStorage,
+    metrics_calculator: &MetricsCalculator,
+    wallet_ranker: &WalletRanker,
+    show_dashboard: bool,
+) -> Result<()> {
+    let finalized_slot = client.get_slot_with_commitment(CommitmentConfig::finalized()).await?;
+    let provisional = storage.get_provisional_trades(finalized_slot).await
+        .map_err(|e| AnalyzerError::StorageError(format!("Failed to load provisional trades: {}", e)))?;
+    let provisional_flows = storage.get_provisional_flows(finalized_slot).await
+        .map_err(|e| AnalyzerError::StorageError(format!("Failed to load provisional flows: {}", e)))?;
+    if provisional.is_empty() && provisional_flows.is_empty() {
+        return Ok(());
     }
 
-    Ok(processed_count)
-}
\ No newline at end of file
+    let mut signatures: Vec<_> = provisional.iter().map(|trade| trade.transaction_hash.clone())
+        .chain(provisional_flows.iter().map(|flow| flow.transaction_hash.clone()))
+        .collect();
+    signatures.sort();
+    signatures.dedup();
+    let statuses: HashMap<_, _> = signatures.iter().cloned()
+        .zip(client.get_signature_statuses(&signatures).await?)
+        .collect();
+
+    let mut finalized: Vec<TradeInfo> = Vec::new();
+    let mut reverted: Vec<TradeInfo> = Vec::new();
+    let mut moved = HashSet::new();
+    for trade in provisional {
+        let status = statuses.get(&trade.transaction_hash).and_then(Option::as_ref);
+        match verdict(&trade, finalized_slot, status) {
+            Verdict::Pending => {}
+            Verdict::Finalized => finalized.push(trade),
+            Verdict::Reverted => reverted.push(trade),
+            Verdict::Moved(slot) => {
+                warn!("Transaction {} moved from slot {} to {}", trade.transaction_hash, trade.slot, slot);
+                moved.insert(trade.transaction_hash.clone());
+                reverted.push(trade);
+            }
+        }
+    }
+    let mut finalized_flows: Vec<CapitalFlow> = Vec::new();
+    let mut reverted_flows: Vec<CapitalFlow> = Vec::new();
+    for flow in provisional_flows {
+        let status = statuses.get(&flow.transaction_hash).and_then(Option::as_ref);
+        match flow_verdict(&flow, finalized_slot, status) {
+            Verdict::Pending => {}
+            Verdict::Finalized => finalized_flows.push(flow),
+            Verdict::Reverted => reverted_flows.push(flow),
+            Verdict::Moved(_) => {
+                moved.insert(flow.transaction_hash.clone());
+                reverted_flows.push(flow);
+            }
+        }
+    }
+
+    storage.finalize_trades(&finalized).await
+        .map_err(|e| AnalyzerError::StorageError(format!("Failed to finalize trades: {}", e)))?;
+    storage.revert_trades(&reverted).await
+        .map_err(|e| AnalyzerError::StorageError(format!("Failed to revert trades: {}", e)))?;
+    if let Err(e) = storage.remove_price_observations(&observations(&reverted), &PriceOracle::default()).await
+        .map_err(|e| e.to_string())
+    {
+        warn!("Failed to remove reverted trades from price candles: {}", e);
+    }
+    storage.finalize_flows(&finalized_flows).await
+        .map_err(|e| AnalyzerError::StorageError(format!("Failed to finalize capital flows: {}", e)))?;
+    storage.revert_flows(&reverted_flows).await
+        .map_err(|e| AnalyzerError::StorageError(format!("Failed to revert capital flows: {}", e)))?;
+    info!(
+        "Reconciled up to slot {}: {} trades and {} flows finalized, {} trades and {} flows reverted",
+        finalized_slot, finalized.len(), finalized_flows.len(), reverted.len(), reverted_flows.len()
+    );
+    if reverted.is_empty() && reverted_flows.is_empty() {
+        return Ok(());
+    }
+
+    let wallets: HashSet<_> = reverted.iter().map(|trade| trade.wallet_address.clone())
+        .chain(reverted_flows.iter().map(|flow| flow.wallet_address.clone()))
+        .collect();
+    let recomputed = recompute_wallets(storage, metrics_calculator, &wallets).await;
+
+    update_top_wallets(storage, wallet_ranker, &wallets, recomputed).await?;
+
+    if !moved.is_empty() {
+        let moved: Vec<_> = moved.into_iter().collect();
+        let transactions = client.fetch_transactions(&moved).await?;
+        process_transactions(client, storage, metrics_calculator, wallet_ranker, &transactions, show_dashboard).await?;
+    }
+    Ok(())
+}
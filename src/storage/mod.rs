@@ -1,7 +1,9 @@
-use redis::Client;
+pub mod redis;
+
+use ::redis::Client;
 
 pub async fn init_redis() -> Result<Client, Box<dyn std::error::Error>> {
-    let client = redis::Client::open("redis://127.0.0.1/")?;
+    let client = Client::open("redis://127.0.0.1/")?;
     Ok(client)
 }
 
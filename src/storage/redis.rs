@@ -1,21 +1,116 @@
-use redis::{Client, Commands, Connection};
-use crate::types::WalletMetrics;
+use redis::{Client, Commands};
+use crate::analysis::pricing::{PriceObservation, PriceOracle};
+use crate::types::{Candle, CapitalFlow, OraclePrice, SignatureCursor, TokenMetadata, TradeInfo, WalletMetrics, WatchCursor};
+use std::collections::{BTreeSet, HashMap};
 use serde_json;
-use std::time::Duration;
-use tracing::{info, error};
 
 pub struct RedisStorage {
     client: Client,
+    dedup_ttl: u64,
 }
 
 const RANKINGS_KEY: &str = "wallet_rankings";
 const TOP_WALLETS_KEY: &str = "top_wallets";
 const METRICS_EXPIRY: u64 = 3600; // 1 hour
+const CURSOR_KEY_PREFIX: &str = "cursor";
+const WATCH_CURSOR_KEY_PREFIX: &str = "cursor:watch";
+const TOKEN_KEY_PREFIX: &str = "token";
+// Decimals never change, but symbols and names occasionally do
+const TOKEN_METADATA_EXPIRY: u64 = 86400; // 1 day
+const TRADES_KEY_PREFIX: &str = "trades";
+const FLOWS_KEY_PREFIX: &str = "flows";
+const TRADE_DATA_KEY_PREFIX: &str = "trade_data";
+const FLOW_DATA_KEY_PREFIX: &str = "flow_data";
+const CANDLES_KEY_PREFIX: &str = "candles";
+const OBSERVATIONS_KEY_PREFIX: &str = "price_observations";
+const ORACLE_KEY_PREFIX: &str = "oracle";
+const SEEN_KEY_PREFIX: &str = "seen";
+const DUPLICATES_KEY: &str = "dedup:duplicates";
+/// How long a processed signature is remembered by default.
+pub const DEDUP_TTL: u64 = 7 * 86400; // 7 days
+/// How long a signature claimed for processing stays claimed.
+pub const CLAIM_TTL: u64 = 600; // 10 minutes
+const PROVISIONAL_TRADES_KEY: &str = "provisional_trades";
+const PROVISIONAL_FLOWS_KEY: &str = "provisional_flows";
 
 impl RedisStorage {
     pub fn new(redis_url: &str) -> Result<Self, Box<dyn std::error::Error>> {
         let client = redis::Client::open(redis_url)?;
-        Ok(Self { client })
+        Ok(Self { client, dedup_ttl: DEDUP_TTL })
+    }
+
+    /// Sets how many seconds processed signatures are remembered for.
+    pub fn with_dedup_ttl(mut self, seconds: u64) -> Self {
+        self.dedup_ttl = seconds;
+        self
+    }
+
+    /// Claims each of `signatures` for processing with `SET NX`, returning
+    /// whether it was claimed (`false` if it was processed within the dedup
+    /// TTL, or is claimed by another task). A claim lapses after
+    /// `CLAIM_TTL` unless the signature is marked processed, so a crashed
+    /// task cannot hold it for the whole dedup TTL.
+    pub async fn claim_signatures(
+        &self,
+        signatures: &[String],
+    ) -> Result<Vec<bool>, Box<dyn std::error::Error>> {
+        if signatures.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut conn = self.client.get_connection()?;
+
+        let mut pipe = redis::pipe();
+        for signature in signatures {
+            pipe.cmd("SET")
+                .arg(format!("{}:{}", SEEN_KEY_PREFIX, signature))
+                .arg(1)
+                .arg("NX")
+                .arg("EX")
+                .arg(CLAIM_TTL);
+        }
+        let replies: Vec<Option<String>> = pipe.query(&mut conn)?;
+        Ok(replies.into_iter().map(|reply| reply.is_some()).collect())
+    }
+
+    /// Gives up claims on `signatures` whose processing failed, so they are
+    /// picked up again.
+    pub async fn release_signatures(
+        &self,
+        signatures: &[&str],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if signatures.is_empty() {
+            return Ok(());
+        }
+        let mut conn = self.client.get_connection()?;
+
+        let keys: Vec<_> = signatures.iter().map(|signature| format!("{}:{}", SEEN_KEY_PREFIX, signature)).collect();
+        conn.del::<_, ()>(keys)?;
+        Ok(())
+    }
+
+    /// Remembers claimed `signatures` as processed for the dedup TTL.
+    pub async fn mark_processed(
+        &self,
+        signatures: &[&str],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut conn = self.client.get_connection()?;
+
+        let mut pipe = redis::pipe();
+        for signature in signatures {
+            pipe.set_ex(format!("{}:{}", SEEN_KEY_PREFIX, signature), 1, self.dedup_ttl as usize);
+        }
+        pipe.query::<()>(&mut conn)?;
+        Ok(())
+    }
+
+    /// Adds to the running count of duplicate transactions skipped, and
+    /// returns the new total.
+    pub async fn count_duplicates(
+        &self,
+        duplicates: u64,
+    ) -> Result<u64, Box<dyn std::error::Error>> {
+        let mut conn = self.client.get_connection()?;
+        Ok(conn.incr(DUPLICATES_KEY, duplicates)?)
     }
 
     pub async fn store_metrics(
@@ -27,10 +122,10 @@ impl RedisStorage {
         // Store full metrics as JSON
         let metrics_json = serde_json::to_string(metrics)?;
         let key = format!("wallet:{}", metrics.address);
-        conn.set_ex(&key, metrics_json, METRICS_EXPIRY as usize)?;
+        conn.set_ex::<_, _, ()>(&key, metrics_json, METRICS_EXPIRY as usize)?;
 
         // Update rankings
-        conn.zadd(
+        conn.zadd::<_, _, _, ()>(
             RANKINGS_KEY,
             metrics.address.clone(),
             metrics.total_profit_loss,
@@ -47,7 +142,7 @@ impl RedisStorage {
         
         // Store the complete list of top wallets
         let wallets_json = serde_json::to_string(wallets)?;
-        conn.set_ex(TOP_WALLETS_KEY, wallets_json, METRICS_EXPIRY as usize)?;
+        conn.set_ex::<_, _, ()>(TOP_WALLETS_KEY, wallets_json, METRICS_EXPIRY as usize)?;
 
         // Update individual wallet rankings
         let mut pipe = redis::pipe();
@@ -61,7 +156,7 @@ impl RedisStorage {
             pipe.zadd(RANKINGS_KEY, &wallet.address, -(idx as i64));
         }
 
-        pipe.execute(&mut conn);
+        pipe.query::<()>(&mut conn)?;
         Ok(())
     }
 
@@ -94,6 +189,20 @@ impl RedisStorage {
         Ok(metrics)
     }
 
+    /// Drops a wallet's metrics and ranking, for wallets left without trades.
+    pub async fn remove_wallet(
+        &self,
+        address: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut conn = self.client.get_connection()?;
+
+        let mut pipe = redis::pipe();
+        pipe.del(format!("wallet:{}", address));
+        pipe.zrem(RANKINGS_KEY, address);
+        pipe.query::<()>(&mut conn)?;
+        Ok(())
+    }
+
     pub async fn get_wallet_metrics(
         &self,
         address: &str,
@@ -109,4 +218,509 @@ impl RedisStorage {
 
         Ok(None)
     }
-}
\ No newline at end of file
+
+    /// Returns the cursor stored under `name`, if any.
+    pub async fn get_cursor(
+        &self,
+        name: &str,
+    ) -> Result<Option<SignatureCursor>, Box<dyn std::error::Error>> {
+        let mut conn = self.client.get_connection()?;
+        let key = format!("{}:{}", CURSOR_KEY_PREFIX, name);
+
+        let data: Option<String> = conn.get(&key)?;
+        match data {
+            Some(data) => Ok(Some(serde_json::from_str(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Persists a cursor under `name`. Cursors never expire.
+    pub async fn store_cursor(
+        &self,
+        name: &str,
+        cursor: &SignatureCursor,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut conn = self.client.get_connection()?;
+        let key = format!("{}:{}", CURSOR_KEY_PREFIX, name);
+
+        let cursor_json = serde_json::to_string(cursor)?;
+        conn.set::<_, _, ()>(&key, cursor_json)?;
+        Ok(())
+    }
+
+    /// Returns the slot stored under `name`, if any.
+    pub async fn get_slot_cursor(
+        &self,
+        name: &str,
+    ) -> Result<Option<u64>, Box<dyn std::error::Error>> {
+        let mut conn = self.client.get_connection()?;
+        let key = format!("{}:{}", CURSOR_KEY_PREFIX, name);
+
+        Ok(conn.get(&key)?)
+    }
+
+    /// Persists the last fully processed slot under `name`.
+    pub async fn store_slot_cursor(
+        &self,
+        name: &str,
+        slot: u64,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut conn = self.client.get_connection()?;
+        let key = format!("{}:{}", CURSOR_KEY_PREFIX, name);
+
+        conn.set::<_, _, ()>(&key, slot)?;
+        Ok(())
+    }
+
+    /// Returns the sync progress of a watched wallet; empty if it was never synced.
+    pub async fn get_watch_cursor(
+        &self,
+        address: &str,
+    ) -> Result<WatchCursor, Box<dyn std::error::Error>> {
+        let mut conn = self.client.get_connection()?;
+        let key = format!("{}:{}", WATCH_CURSOR_KEY_PREFIX, address);
+
+        let data: Option<String> = conn.get(&key)?;
+        match data {
+            Some(data) => Ok(serde_json::from_str(&data)?),
+            None => Ok(WatchCursor::default()),
+        }
+    }
+
+    /// Persists the sync progress of a watched wallet.
+    pub async fn store_watch_cursor(
+        &self,
+        address: &str,
+        cursor: &WatchCursor,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut conn = self.client.get_connection()?;
+        let key = format!("{}:{}", WATCH_CURSOR_KEY_PREFIX, address);
+
+        conn.set::<_, _, ()>(&key, serde_json::to_string(cursor)?)?;
+        Ok(())
+    }
+
+    /// Returns the cached metadata of whichever of `mints` have it, by mint.
+    pub async fn get_token_metadata(
+        &self,
+        mints: &[String],
+    ) -> Result<HashMap<String, TokenMetadata>, Box<dyn std::error::Error>> {
+        let mut conn = self.client.get_connection()?;
+
+        let mut pipe = redis::pipe();
+        for mint in mints {
+            pipe.get(format!("{}:{}", TOKEN_KEY_PREFIX, mint));
+        }
+        let data: Vec<Option<String>> = pipe.query(&mut conn)?;
+
+        let mut metadata = HashMap::new();
+        for json in data.into_iter().flatten() {
+            let token: TokenMetadata = serde_json::from_str(&json)?;
+            metadata.insert(token.mint.clone(), token);
+        }
+        Ok(metadata)
+    }
+
+    pub async fn store_token_metadata(
+        &self,
+        tokens: &[TokenMetadata],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut conn = self.client.get_connection()?;
+
+        let mut pipe = redis::pipe();
+        for token in tokens {
+            let key = format!("{}:{}", TOKEN_KEY_PREFIX, token.mint);
+            pipe.set_ex(key, serde_json::to_string(token)?, TOKEN_METADATA_EXPIRY as usize);
+        }
+        pipe.query::<()>(&mut conn)?;
+        Ok(())
+    }
+
+    /// Stores trades in each wallet's history under their transaction
+    /// signatures, indexed by timestamp, so storing a trade again replaces it.
+    /// Provisional trades are also indexed by slot for reconciliation.
+    pub async fn store_trades(
+        &self,
+        trades: &[TradeInfo],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut conn = self.client.get_connection()?;
+
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        for trade in trades {
+            let signature = &trade.transaction_hash;
+            let member = format!("{}:{}", trade.wallet_address, signature);
+            pipe.hset(format!("{}:{}", TRADE_DATA_KEY_PREFIX, trade.wallet_address), signature, serde_json::to_string(trade)?);
+            pipe.zadd(format!("{}:{}", TRADES_KEY_PREFIX, trade.wallet_address), signature, trade.timestamp);
+            match trade.provisional {
+                true => pipe.zadd(PROVISIONAL_TRADES_KEY, member, trade.slot),
+                false => pipe.zrem(PROVISIONAL_TRADES_KEY, member),
+            };
+        }
+
+        pipe.query::<()>(&mut conn)?;
+        Ok(())
+    }
+
+    /// Returns provisional trades from slots up to `max_slot`, oldest first.
+    pub async fn get_provisional_trades(
+        &self,
+        max_slot: u64,
+    ) -> Result<Vec<TradeInfo>, Box<dyn std::error::Error>> {
+        let mut conn = self.client.get_connection()?;
+
+        let members: Vec<String> = conn.zrangebyscore(PROVISIONAL_TRADES_KEY, 0, max_slot)?;
+        let mut pipe = redis::pipe();
+        for member in &members {
+            let (wallet, signature) = member.split_once(':').ok_or("malformed provisional trade")?;
+            pipe.hget(format!("{}:{}", TRADE_DATA_KEY_PREFIX, wallet), signature);
+        }
+        let data: Vec<Option<String>> = pipe.query(&mut conn)?;
+        let trades = data
+            .iter()
+            .flatten()
+            .map(|json| serde_json::from_str(json))
+            .collect::<Result<Vec<TradeInfo>, _>>()?;
+        Ok(trades)
+    }
+
+    /// Clears the provisional flag of trades whose transactions were finalized.
+    pub async fn finalize_trades(
+        &self,
+        trades: &[TradeInfo],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut conn = self.client.get_connection()?;
+
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        for trade in trades {
+            let finalized = TradeInfo { provisional: false, ..trade.clone() };
+            let key = format!("{}:{}", TRADE_DATA_KEY_PREFIX, trade.wallet_address);
+            pipe.hset(key, &trade.transaction_hash, serde_json::to_string(&finalized)?);
+            pipe.zrem(PROVISIONAL_TRADES_KEY, format!("{}:{}", trade.wallet_address, trade.transaction_hash));
+        }
+
+        pipe.query::<()>(&mut conn)?;
+        Ok(())
+    }
+
+    /// Removes trades that were rolled back from their wallets' histories,
+    /// and forgets that their transactions were processed.
+    pub async fn revert_trades(
+        &self,
+        trades: &[TradeInfo],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut conn = self.client.get_connection()?;
+
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        for trade in trades {
+            let signature = &trade.transaction_hash;
+            pipe.hdel(format!("{}:{}", TRADE_DATA_KEY_PREFIX, trade.wallet_address), signature);
+            pipe.zrem(format!("{}:{}", TRADES_KEY_PREFIX, trade.wallet_address), signature);
+            pipe.zrem(PROVISIONAL_TRADES_KEY, format!("{}:{}", trade.wallet_address, signature));
+            // Let the transaction be processed again if it reappears
+            pipe.del(format!("{}:{}", SEEN_KEY_PREFIX, signature));
+        }
+
+        pipe.query::<()>(&mut conn)?;
+        Ok(())
+    }
+
+    /// Stores deposits, withdrawals and transfers in each wallet's flow
+    /// history under their transaction signature and mint, indexed by
+    /// timestamp. Provisional flows are also indexed by slot for
+    /// reconciliation.
+    pub async fn store_flows(
+        &self,
+        flows: &[CapitalFlow],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut conn = self.client.get_connection()?;
+
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        for flow in flows {
+            let id = flow_id(flow);
+            let member = format!("{}:{}", flow.wallet_address, id);
+            pipe.hset(format!("{}:{}", FLOW_DATA_KEY_PREFIX, flow.wallet_address), &id, serde_json::to_string(flow)?);
+            pipe.zadd(format!("{}:{}", FLOWS_KEY_PREFIX, flow.wallet_address), &id, flow.timestamp);
+            match flow.provisional {
+                true => pipe.zadd(PROVISIONAL_FLOWS_KEY, member, flow.slot),
+                false => pipe.zrem(PROVISIONAL_FLOWS_KEY, member),
+            };
+        }
+
+        pipe.query::<()>(&mut conn)?;
+        Ok(())
+    }
+
+    /// Returns provisional flows from slots up to `max_slot`, oldest first.
+    pub async fn get_provisional_flows(
+        &self,
+        max_slot: u64,
+    ) -> Result<Vec<CapitalFlow>, Box<dyn std::error::Error>> {
+        let mut conn = self.client.get_connection()?;
+
+        let members: Vec<String> = conn.zrangebyscore(PROVISIONAL_FLOWS_KEY, 0, max_slot)?;
+        let mut pipe = redis::pipe();
+        for member in &members {
+            let (wallet, id) = member.split_once(':').ok_or("malformed provisional flow")?;
+            pipe.hget(format!("{}:{}", FLOW_DATA_KEY_PREFIX, wallet), id);
+        }
+        let data: Vec<Option<String>> = pipe.query(&mut conn)?;
+        let flows = data
+            .iter()
+            .flatten()
+            .map(|json| serde_json::from_str(json))
+            .collect::<Result<Vec<CapitalFlow>, _>>()?;
+        Ok(flows)
+    }
+
+    /// Clears the provisional flag of flows whose transactions were finalized.
+    pub async fn finalize_flows(
+        &self,
+        flows: &[CapitalFlow],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut conn = self.client.get_connection()?;
+
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        for flow in flows {
+            let id = flow_id(flow);
+            let finalized = CapitalFlow { provisional: false, ..flow.clone() };
+            pipe.hset(format!("{}:{}", FLOW_DATA_KEY_PREFIX, flow.wallet_address), &id, serde_json::to_string(&finalized)?);
+            pipe.zrem(PROVISIONAL_FLOWS_KEY, format!("{}:{}", flow.wallet_address, id));
+        }
+
+        pipe.query::<()>(&mut conn)?;
+        Ok(())
+    }
+
+    /// Removes flows that were rolled back from their wallets' histories,
+    /// and forgets that their transactions were processed.
+    pub async fn revert_flows(
+        &self,
+        flows: &[CapitalFlow],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut conn = self.client.get_connection()?;
+
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        for flow in flows {
+            let id = flow_id(flow);
+            pipe.hdel(format!("{}:{}", FLOW_DATA_KEY_PREFIX, flow.wallet_address), &id);
+            pipe.zrem(format!("{}:{}", FLOWS_KEY_PREFIX, flow.wallet_address), &id);
+            pipe.zrem(PROVISIONAL_FLOWS_KEY, format!("{}:{}", flow.wallet_address, id));
+            pipe.del(format!("{}:{}", SEEN_KEY_PREFIX, flow.transaction_hash));
+        }
+
+        pipe.query::<()>(&mut conn)?;
+        Ok(())
+    }
+
+    /// Returns a wallet's capital flows, oldest first.
+    pub async fn get_wallet_flows(
+        &self,
+        address: &str,
+    ) -> Result<Vec<CapitalFlow>, Box<dyn std::error::Error>> {
+        let mut conn = self.client.get_connection()?;
+        let key = format!("{}:{}", FLOW_DATA_KEY_PREFIX, address);
+
+        let data: Vec<String> = conn.hvals(&key)?;
+        let mut flows = data
+            .iter()
+            .map(|flow| serde_json::from_str(flow))
+            .collect::<Result<Vec<CapitalFlow>, _>>()?;
+        flows.sort_by_key(|flow| (flow.timestamp, flow.slot, flow.transaction_index.unwrap_or(0)));
+        Ok(flows)
+    }
+
+    /// Records price observations by interval, keyed by transaction so a
+    /// reprocessed swap replaces its earlier observation rather than adding
+    /// to it, then rebuilds the candles of the intervals they fall in.
+    pub async fn store_price_observations(
+        &self,
+        observations: &[PriceObservation],
+        oracle: &PriceOracle,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if observations.is_empty() {
+            return Ok(());
+        }
+        let mut conn = self.client.get_connection()?;
+
+        let mut pipe = redis::pipe();
+        let mut intervals = BTreeSet::new();
+        for observation in observations {
+            let start = oracle.bucket(observation.timestamp);
+            let key = format!("{}:{}:{}", OBSERVATIONS_KEY_PREFIX, observation.mint, start);
+            pipe.hset(key, &observation.transaction_hash, serde_json::to_string(observation)?);
+            intervals.insert((observation.mint.clone(), start));
+        }
+        pipe.query::<()>(&mut conn)?;
+
+        rebuild_candles(&mut conn, oracle, intervals)?;
+        Ok(())
+    }
+
+    /// Drops the observations of swaps that were rolled back, and rebuilds
+    /// the candles of the intervals they fell in.
+    pub async fn remove_price_observations(
+        &self,
+        observations: &[PriceObservation],
+        oracle: &PriceOracle,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if observations.is_empty() {
+            return Ok(());
+        }
+        let mut conn = self.client.get_connection()?;
+
+        let mut pipe = redis::pipe();
+        let mut intervals = BTreeSet::new();
+        for observation in observations {
+            let start = oracle.bucket(observation.timestamp);
+            let key = format!("{}:{}:{}", OBSERVATIONS_KEY_PREFIX, observation.mint, start);
+            pipe.hdel(key, &observation.transaction_hash);
+            intervals.insert((observation.mint.clone(), start));
+        }
+        pipe.query::<()>(&mut conn)?;
+
+        rebuild_candles(&mut conn, oracle, intervals)?;
+        Ok(())
+    }
+
+    /// Returns the candles of `mints` starting between `from` and `to`
+    /// inclusive, each mint's oldest first.
+    pub async fn get_candles(
+        &self,
+        mints: &[String],
+        from: i64,
+        to: i64,
+    ) -> Result<Vec<Candle>, Box<dyn std::error::Error>> {
+        let mut conn = self.client.get_connection()?;
+
+        let mut pipe = redis::pipe();
+        for mint in mints {
+            pipe.zrangebyscore(format!("{}:{}", CANDLES_KEY_PREFIX, mint), from, to);
+        }
+        let data: Vec<Vec<String>> = pipe.query(&mut conn)?;
+
+        let candles = data
+            .iter()
+            .flatten()
+            .map(|candle| serde_json::from_str(candle))
+            .collect::<Result<Vec<Candle>, _>>()?;
+        Ok(candles)
+    }
+
+    /// Returns the latest candle of `mint` starting at or before `timestamp`.
+    pub async fn get_candle_at(
+        &self,
+        mint: &str,
+        timestamp: i64,
+    ) -> Result<Option<Candle>, Box<dyn std::error::Error>> {
+        let mut conn = self.client.get_connection()?;
+        let key = format!("{}:{}", CANDLES_KEY_PREFIX, mint);
+
+        let data: Vec<String> = conn.zrevrangebyscore_limit(&key, timestamp, "-inf", 0, 1)?;
+        match data.first() {
+            Some(json) => Ok(Some(serde_json::from_str(json)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Adds oracle prices to each mint's history, scored by publish time.
+    /// A price read again before the next publish is stored once.
+    pub async fn store_oracle_prices(
+        &self,
+        prices: &[OraclePrice],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut conn = self.client.get_connection()?;
+
+        let mut pipe = redis::pipe();
+        for price in prices {
+            let key = format!("{}:{}", ORACLE_KEY_PREFIX, price.mint);
+            pipe.zadd(key, serde_json::to_string(price)?, price.publish_time);
+        }
+        pipe.query::<()>(&mut conn)?;
+        Ok(())
+    }
+
+    /// Returns the oracle prices of `mints` published between `from` and `to`
+    /// inclusive.
+    pub async fn get_oracle_prices(
+        &self,
+        mints: &[String],
+        from: i64,
+        to: i64,
+    ) -> Result<Vec<OraclePrice>, Box<dyn std::error::Error>> {
+        let mut conn = self.client.get_connection()?;
+
+        let mut pipe = redis::pipe();
+        for mint in mints {
+            pipe.zrangebyscore(format!("{}:{}", ORACLE_KEY_PREFIX, mint), from, to);
+        }
+        let data: Vec<Vec<String>> = pipe.query(&mut conn)?;
+
+        let prices = data
+            .iter()
+            .flatten()
+            .map(|price| serde_json::from_str(price))
+            .collect::<Result<Vec<OraclePrice>, _>>()?;
+        Ok(prices)
+    }
+
+    /// Returns a wallet's full trade history, oldest first. Trades within the
+    /// same second are ordered by slot and position in the block.
+    pub async fn get_wallet_trades(
+        &self,
+        address: &str,
+    ) -> Result<Vec<TradeInfo>, Box<dyn std::error::Error>> {
+        let mut conn = self.client.get_connection()?;
+        let key = format!("{}:{}", TRADE_DATA_KEY_PREFIX, address);
+
+        let data: Vec<String> = conn.hvals(&key)?;
+        let mut trades = data
+            .iter()
+            .map(|trade| serde_json::from_str(trade))
+            .collect::<Result<Vec<TradeInfo>, _>>()?;
+        trades.sort_by_key(TradeInfo::chronological_key);
+        Ok(trades)
+    }
+}
+
+/// Identifies a flow among its wallet's flows; a transaction moves each mint
+/// in or out of a wallet at most once.
+fn flow_id(flow: &CapitalFlow) -> String {
+    format!("{}:{}", flow.transaction_hash, flow.mint)
+}
+
+/// Replaces the stored candle of each `(mint, start)` interval with one built
+/// from the interval's observations. The observations are watched while the
+/// candle is written, and the rebuild retried if they changed meanwhile, so
+/// concurrent writers never drop one another's swaps.
+fn rebuild_candles(
+    conn: &mut redis::Connection,
+    oracle: &PriceOracle,
+    intervals: impl IntoIterator<Item = (String, i64)>,
+) -> redis::RedisResult<()> {
+    for (mint, start) in intervals {
+        let observations_key = format!("{}:{}:{}", OBSERVATIONS_KEY_PREFIX, mint, start);
+        let candles_key = format!("{}:{}", CANDLES_KEY_PREFIX, mint);
+        let _: () = redis::transaction(conn, &[&observations_key], |conn, pipe| {
+            let stored: Vec<String> = conn.hvals(&observations_key)?;
+            let observations = stored.iter()
+                .filter_map(|json| serde_json::from_str(json).ok())
+                .collect();
+
+            pipe.zrembyscore(&candles_key, start, start).ignore();
+            // Every observation is of the same mint and interval
+            if let Some(candle) = oracle.candles(observations).pop() {
+                let json = serde_json::to_string(&candle)
+                    .map_err(|e| redis::RedisError::from((redis::ErrorKind::TypeError, "Failed to encode candle", e.to_string())))?;
+                pipe.zadd(&candles_key, json, start).ignore();
+            }
+            pipe.query(conn)
+        })?;
+    }
+    Ok(())
+}
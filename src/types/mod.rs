@@ -1,20 +1,288 @@
 use serde::{Deserialize, Serialize};
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct WalletMetrics {
     pub address: String,
+    /// Realized profit/loss in SOL, before costs.
     pub total_profit_loss: f64,
     pub win_rate: f64,
     pub avg_trade_size: f64,
     pub trade_count: u64,
     pub last_updated: i64,
+    /// Fees and tips in SOL, including those of failed transactions.
+    #[serde(default)]
+    pub total_costs: f64,
+    /// `total_profit_loss` less `total_costs`.
+    #[serde(default)]
+    pub net_profit_loss: f64,
+    /// `net_profit_loss` in USD at the SOL/USD price of each trade; unknown
+    /// unless every trade has one.
+    #[serde(default)]
+    pub net_profit_loss_usd: Option<f64>,
+    #[serde(default)]
+    pub failed_transaction_count: u64,
+    /// SOL received from other wallets and exchanges; token flows are not valued.
+    #[serde(default)]
+    pub capital_in: f64,
+    /// SOL sent to other wallets and exchanges.
+    #[serde(default)]
+    pub capital_out: f64,
+    /// `net_profit_loss` as a percentage of `capital_in`; unknown for wallets
+    /// that never received SOL.
+    #[serde(default)]
+    pub roi: Option<f64>,
+    /// Symbol of the token the wallet traded most often, or the start of its
+    /// mint address when it has none.
+    #[serde(default)]
+    pub most_traded_token: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TradeInfo {
+    /// Beneficial owner of the traded balances; rankings are keyed on it.
     pub wallet_address: String,
+    /// Account that paid the transaction fee, which differs from the owner
+    /// for relayed, bot-submitted and multisig transactions.
+    #[serde(default)]
+    pub fee_payer: String,
+    /// Block time of the transaction, in Unix seconds.
     pub timestamp: i64,
+    #[serde(default)]
+    pub slot: u64,
+    /// Position of the transaction within its block, when known.
+    #[serde(default)]
+    pub transaction_index: Option<u32>,
+    /// Read below `finalized` commitment and not yet reconciled, so it may
+    /// still be rolled back.
+    #[serde(default)]
+    pub provisional: bool,
+    /// A failed swap attempt, recorded only for its costs; it has no legs
+    /// and is not counted as a trade.
+    #[serde(default)]
+    pub failed: bool,
+    /// Signature fee in lamports; zero unless the wallet paid it.
+    #[serde(default)]
+    pub base_fee: u64,
+    /// Compute budget priority fee in lamports; zero unless the wallet paid it.
+    #[serde(default)]
+    pub priority_fee: u64,
+    /// Jito tips the wallet sent, in lamports.
+    #[serde(default)]
+    pub tip: u64,
+    /// Trade size in SOL. For swaps with no SOL leg, the value of the tokens
+    /// at the time, or zero when no price was known.
     pub amount: f64,
+    /// USD per SOL at the time of the trade, when known.
+    #[serde(default)]
+    pub sol_usd_price: Option<f64>,
+    /// Realized profit/loss in SOL, filled in by `MetricsCalculator::apply_realized_pnl`.
     pub profit_loss: f64,
     pub transaction_hash: String,
-}
\ No newline at end of file
+    pub token_in_mint: String,
+    pub token_in_amount: f64,
+    pub token_out_mint: String,
+    pub token_out_amount: f64,
+    /// Symbols of the traded tokens, when their metadata is known.
+    #[serde(default)]
+    pub token_in_symbol: String,
+    #[serde(default)]
+    pub token_out_symbol: String,
+    /// Venue that executed the swap, e.g. `raydium-amm-v4`.
+    pub venue: String,
+    /// Version of the decoder that produced this trade.
+    pub decoder_version: String,
+    /// Intermediate hops of an aggregator route, in execution order.
+    pub route: Vec<RouteHop>,
+}
+
+impl TradeInfo {
+    /// Sort key for chronological order: block time, then slot, then
+    /// position within the block.
+    pub fn chronological_key(&self) -> (i64, u64, u32) {
+        (self.timestamp, self.slot, self.transaction_index.unwrap_or(0))
+    }
+
+    /// Fees and tip the wallet paid for the transaction, in SOL. Fees are
+    /// only charged when the wallet is the fee payer.
+    pub fn costs(&self) -> f64 {
+        let fees = match self.fee_payer.is_empty() || self.fee_payer == self.wallet_address {
+            true => self.base_fee + self.priority_fee,
+            false => 0,
+        };
+        (fees + self.tip) as f64 / LAMPORTS_PER_SOL as f64
+    }
+}
+
+/// How a non-swap balance movement reached or left a wallet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FlowKind {
+    SolTransfer,
+    TokenTransfer,
+    /// Sent to a known exchange wallet.
+    ExchangeDeposit,
+    /// Received from a known exchange wallet.
+    ExchangeWithdrawal,
+}
+
+/// A balance movement that is not a trade, such as funding a wallet. Kept
+/// apart from trades so it never counts towards profit/loss.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CapitalFlow {
+    pub wallet_address: String,
+    /// Wallet on the other side, when the transaction shows one.
+    pub counterparty: String,
+    pub kind: FlowKind,
+    pub mint: String,
+    /// In UI units; positive for inflows, negative for outflows.
+    pub amount: f64,
+    pub timestamp: i64,
+    pub slot: u64,
+    pub transaction_index: Option<u32>,
+    pub transaction_hash: String,
+    /// Read below `finalized` commitment and not yet reconciled.
+    #[serde(default)]
+    pub provisional: bool,
+}
+
+/// Open, high, low and close price of a mint over one interval, in SOL per
+/// UI unit, as implied by the swaps observed in it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Candle {
+    pub mint: String,
+    /// Start of the interval, in Unix seconds.
+    pub start: i64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    /// SOL traded in the interval.
+    pub volume: f64,
+    pub trade_count: u64,
+    /// Block times of the trades the open and close were taken from, so
+    /// candles built from out-of-order batches merge correctly.
+    pub first_trade: i64,
+    pub last_trade: i64,
+}
+
+impl Candle {
+    /// Folds another candle for the same mint and interval into this one.
+    pub fn merge(&mut self, other: &Candle) {
+        if other.first_trade < self.first_trade {
+            self.open = other.open;
+            self.first_trade = other.first_trade;
+        }
+        if other.last_trade >= self.last_trade {
+            self.close = other.close;
+            self.last_trade = other.last_trade;
+        }
+        self.high = self.high.max(other.high);
+        self.low = self.low.min(other.low);
+        self.volume += other.volume;
+        self.trade_count += other.trade_count;
+    }
+}
+
+/// Program an oracle price was read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OracleSource {
+    Pyth,
+    Switchboard,
+}
+
+/// USD price of a mint published by an on-chain oracle.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OraclePrice {
+    pub mint: String,
+    pub source: OracleSource,
+    /// USD per UI unit.
+    pub price: f64,
+    /// Confidence interval (Pyth) or standard deviation (Switchboard) of
+    /// the price, in USD.
+    pub confidence: f64,
+    /// When the price was published, in Unix seconds.
+    pub publish_time: i64,
+}
+
+/// One leg of an aggregator route. Amounts are in the mint's base units.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RouteHop {
+    /// Venue name of the AMM program, when a decoder is registered for it.
+    pub venue: String,
+    pub amm: String,
+    pub input_mint: String,
+    pub input_amount: u64,
+    pub output_mint: String,
+    pub output_amount: u64,
+}
+
+/// Last signature processed for an address; polling resumes right after it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignatureCursor {
+    pub signature: String,
+    pub slot: u64,
+    /// Catch-up walk in progress, when more signatures followed the cursor
+    /// than fit in one batch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backlog: Option<Backlog>,
+}
+
+/// A walk from the tip back to a cursor, taken a batch at a time, newest
+/// first. The signatures between the cursor and `before` are still to come;
+/// once they are processed, `newest` becomes the cursor.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Backlog {
+    pub before: String,
+    pub newest: String,
+    pub newest_slot: u64,
+}
+
+/// Progress through a watched wallet's signature history, which is synced
+/// in both directions: forward from the newest signature processed, and
+/// backward from the oldest until the start of the history is reached.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WatchCursor {
+    pub newest: Option<SignatureCursor>,
+    pub oldest: Option<SignatureCursor>,
+    /// The backward walk reached the wallet's first transaction.
+    pub complete: bool,
+}
+
+/// Mint details of a token, with its symbol and name from Metaplex metadata
+/// or the Token-2022 metadata extension.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TokenMetadata {
+    pub mint: String,
+    pub decimals: u8,
+    /// Total supply in base units.
+    pub supply: u64,
+    /// Empty when the token has no metadata.
+    pub symbol: String,
+    pub name: String,
+    /// Owned by the Token-2022 program rather than the original Token program.
+    pub token_2022: bool,
+    /// Token-2022 extensions enabled on the mint, e.g. `TransferFeeConfig`.
+    pub extensions: Vec<String>,
+}
+
+impl TokenMetadata {
+    /// Name to display the token under: its symbol, or the start of its
+    /// mint address when it has none.
+    pub fn label(&self) -> String {
+        if self.symbol.is_empty() {
+            short_mint(&self.mint)
+        } else {
+            self.symbol.clone()
+        }
+    }
+
+    /// Converts an amount in base units to whole tokens.
+    pub fn ui_amount(&self, amount: u64) -> f64 {
+        amount as f64 / 10f64.powi(self.decimals as i32)
+    }
+}
+
+/// Start of a mint address, to stand in for a missing symbol.
+pub fn short_mint(mint: &str) -> String {
+    mint.chars().take(8).collect()
+}
@@ -25,16 +25,20 @@ fn display_summary(summary: &PerformanceSummary) {
 
 fn display_top_wallets(wallets: &[WalletMetrics]) {
     println!("{}", "Top Performing Wallets".bold().underline());
-    println!("{:<44} {:>12} {:>10} {:>12}", "Wallet", "Profit/Loss", "Win Rate", "Trade Count");
-    println!("{}", "=".repeat(80));
+    println!("{:<44} {:>12} {:>12} {:>12} {:>9} {:>10} {:>12} {:>10}", "Wallet", "Profit/Loss", "Net P/L", "Net P/L $", "ROI", "Win Rate", "Trade Count", "Top Token");
+    println!("{}", "=".repeat(127));
 
     for wallet in wallets.iter().take(10) {
         println!(
-            "{:<44} {:>12.2} {:>9.1}% {:>12}",
+            "{:<44} {:>12.2} {:>12.4} {:>12} {:>9} {:>9.1}% {:>12} {:>10}",
             wallet.address,
             wallet.total_profit_loss,
+            wallet.net_profit_loss,
+            wallet.net_profit_loss_usd.map(|usd| format!("{:.2}", usd)).unwrap_or_else(|| "-".to_string()),
+            wallet.roi.map(|roi| format!("{:.1}%", roi)).unwrap_or_else(|| "-".to_string()),
             wallet.win_rate,
-            wallet.trade_count
+            wallet.trade_count,
+            wallet.most_traded_token
         );
     }
 }
@@ -1,3 +1,5 @@
+pub mod cli;
+
 use serde::Serialize;
 use std::collections::HashMap;
 use crate::types::WalletMetrics;
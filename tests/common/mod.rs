@@ -0,0 +1,441 @@
+#![allow(dead_code)]
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server, StatusCode};
+use serde_json::{json, Value};
+use solana_sdk::{
+    hash::Hash,
+    instruction::CompiledInstruction,
+    message::Message,
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::Transaction,
+};
+use solana_transaction_status::{
+    Encodable, EncodedConfirmedTransactionWithStatusMeta, EncodedTransactionWithStatusMeta,
+    TransactionStatusMeta, UiTransactionEncoding,
+};
+use solana_wallet_analyzer::ingestion::transaction::{TokenBalance, TransactionMeta, TransactionWithMeta};
+use std::collections::{BTreeMap, HashMap};
+use std::convert::Infallible;
+use std::io::{BufRead, BufReader, Write};
+use std::str::FromStr;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::time::{sleep, Duration};
+
+pub const RAYDIUM: &str = "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8";
+pub const BONK: &str = "DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263";
+pub const USDC: &str = "EPjFWdd5AufqSSqeM2qxKzJ4bX4p4jE7P4rfnoevaLo";
+const TOKEN_PROGRAM: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+
+/// Local JSON-RPC endpoint standing in for a Solana RPC node. Every request is
+/// answered by `handler` after `delay`, and load is recorded for assertions.
+/// The `getVersion` probe the RPC client sends first is answered directly.
+pub struct MockRpc {
+    pub url: String,
+    pub requests: Arc<AtomicUsize>,
+    pub max_in_flight: Arc<AtomicUsize>,
+}
+
+impl MockRpc {
+    pub async fn start<F>(delay: Duration, handler: F) -> Self
+    where
+        F: Fn(&Value) -> Response<Body> + Send + Sync + 'static,
+    {
+        let handler = Arc::new(handler);
+        let requests = Arc::new(AtomicUsize::new(0));
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+
+        let make_service = {
+            let requests = Arc::clone(&requests);
+            let max_in_flight = Arc::clone(&max_in_flight);
+            make_service_fn(move |_| {
+                let handler = Arc::clone(&handler);
+                let requests = Arc::clone(&requests);
+                let in_flight = Arc::clone(&in_flight);
+                let max_in_flight = Arc::clone(&max_in_flight);
+                async move {
+                    Ok::<_, Infallible>(service_fn(move |request: Request<Body>| {
+                        let handler = Arc::clone(&handler);
+                        let requests = Arc::clone(&requests);
+                        let in_flight = Arc::clone(&in_flight);
+                        let max_in_flight = Arc::clone(&max_in_flight);
+                        async move {
+                            let body = hyper::body::to_bytes(request.into_body()).await.unwrap();
+                            let request: Value = serde_json::from_slice(&body).unwrap();
+                            if request["method"] == "getVersion" {
+                                return Ok(rpc_result(&request, json!({ "solana-core": "1.18.26" })));
+                            }
+
+                            requests.fetch_add(1, Ordering::SeqCst);
+                            let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                            max_in_flight.fetch_max(current, Ordering::SeqCst);
+                            sleep(delay).await;
+                            let response = handler(&request);
+
+                            in_flight.fetch_sub(1, Ordering::SeqCst);
+                            Ok::<_, Infallible>(response)
+                        }
+                    }))
+                }
+            })
+        };
+
+        let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+        let url = format!("http://{}", server.local_addr());
+        tokio::spawn(server);
+
+        Self {
+            url,
+            requests,
+            max_in_flight,
+        }
+    }
+
+    pub fn request_count(&self) -> usize {
+        self.requests.load(Ordering::SeqCst)
+    }
+}
+
+/// A value stored in `MockRedis`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RedisValue {
+    String(String),
+    Hash(BTreeMap<String, String>),
+    /// Members and their scores, in score order.
+    SortedSet(Vec<(f64, String)>),
+}
+
+/// Values by key, with the expiry they were set with, in seconds.
+type RedisData = HashMap<String, (RedisValue, Option<u64>)>;
+
+/// In-memory stand-in for a Redis server, speaking just enough RESP for
+/// `RedisStorage`: strings (GET, SET with NX and EX, SETEX), hashes (HSET,
+/// HDEL, HVALS), sorted sets (ZADD, ZRANGEBYSCORE, ZREVRANGEBYSCORE,
+/// ZREMRANGEBYSCORE), DEL, EXISTS and MULTI/EXEC. WATCH is accepted but
+/// never aborts a transaction. Runs on its own threads, since storage calls
+/// block.
+pub struct MockRedis {
+    pub url: String,
+    pub data: Arc<Mutex<RedisData>>,
+}
+
+impl MockRedis {
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("redis://{}", listener.local_addr().unwrap());
+        let data = Arc::new(Mutex::new(HashMap::new()));
+
+        let shared = Arc::clone(&data);
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else { break };
+                let data = Arc::clone(&shared);
+                std::thread::spawn(move || serve_redis(stream, &data));
+            }
+        });
+
+        Self { url, data }
+    }
+
+    /// Expiry of `key`, if it is set.
+    pub fn ttl(&self, key: &str) -> Option<Option<u64>> {
+        self.data.lock().unwrap().get(key).map(|(_, ttl)| *ttl)
+    }
+}
+
+fn serve_redis(stream: TcpStream, data: &Mutex<RedisData>) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut writer = stream;
+    let mut queued: Option<Vec<Vec<String>>> = None;
+    while let Some(command) = read_command(&mut reader) {
+        let name = command[0].to_uppercase();
+        let reply = if name == "MULTI" {
+            queued = Some(Vec::new());
+            "+OK\r\n".to_string()
+        } else if name == "EXEC" {
+            let mut data = data.lock().unwrap();
+            let replies = queued.take().unwrap_or_default().iter()
+                .map(|command| execute(command, &mut data))
+                .collect();
+            array(replies)
+        } else if let Some(commands) = queued.as_mut() {
+            commands.push(command);
+            "+QUEUED\r\n".to_string()
+        } else {
+            execute(&command, &mut data.lock().unwrap())
+        };
+        if writer.write_all(reply.as_bytes()).is_err() {
+            break;
+        }
+    }
+}
+
+/// Reads one command, an array of bulk strings.
+fn read_command(reader: &mut impl BufRead) -> Option<Vec<String>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line).ok()? == 0 {
+        return None;
+    }
+    let count: usize = line.trim_end().strip_prefix('*')?.parse().ok()?;
+    (0..count)
+        .map(|_| {
+            line.clear();
+            reader.read_line(&mut line).ok()?;
+            let len: usize = line.trim_end().strip_prefix('$')?.parse().ok()?;
+            let mut bytes = vec![0; len + 2];
+            reader.read_exact(&mut bytes).ok()?;
+            bytes.truncate(len);
+            String::from_utf8(bytes).ok()
+        })
+        .collect()
+}
+
+const NIL: &str = "$-1\r\n";
+const WRONG_TYPE: &str = "-WRONGTYPE Operation against a key holding the wrong kind of value\r\n";
+
+fn bulk(value: &str) -> String {
+    format!("${}\r\n{}\r\n", value.len(), value)
+}
+
+fn integer(value: usize) -> String {
+    format!(":{}\r\n", value)
+}
+
+fn array(replies: Vec<String>) -> String {
+    format!("*{}\r\n{}", replies.len(), replies.concat())
+}
+
+fn execute(command: &[String], data: &mut RedisData) -> String {
+    let args = &command[1..];
+    let score = |value: &str| value.parse::<f64>().unwrap();
+    match command[0].to_uppercase().as_str() {
+        "GET" => match data.get(&args[0]) {
+            Some((RedisValue::String(value), _)) => bulk(value),
+            Some(_) => WRONG_TYPE.to_string(),
+            None => NIL.to_string(),
+        },
+        "SET" => {
+            let options: Vec<_> = args[2..].iter().map(|option| option.to_uppercase()).collect();
+            if options.iter().any(|option| option == "NX") && data.contains_key(&args[0]) {
+                return NIL.to_string();
+            }
+            let ttl = options.iter().position(|option| option == "EX")
+                .and_then(|index| options.get(index + 1)?.parse().ok());
+            data.insert(args[0].clone(), (RedisValue::String(args[1].clone()), ttl));
+            "+OK\r\n".to_string()
+        }
+        "SETEX" => {
+            data.insert(args[0].clone(), (RedisValue::String(args[2].clone()), args[1].parse().ok()));
+            "+OK\r\n".to_string()
+        }
+        "DEL" => integer(args.iter().filter(|key| data.remove(*key).is_some()).count()),
+        "EXISTS" => integer(args.iter().filter(|key| data.contains_key(*key)).count()),
+        "HSET" => {
+            let (RedisValue::Hash(hash), _) = data.entry(args[0].clone()).or_insert((RedisValue::Hash(BTreeMap::new()), None)) else {
+                return WRONG_TYPE.to_string();
+            };
+            integer(args[1..].chunks(2).filter(|pair| hash.insert(pair[0].clone(), pair[1].clone()).is_none()).count())
+        }
+        "HDEL" => {
+            let Some((RedisValue::Hash(hash), _)) = data.get_mut(&args[0]) else {
+                return integer(0);
+            };
+            let removed = args[1..].iter().filter(|field| hash.remove(*field).is_some()).count();
+            if hash.is_empty() {
+                data.remove(&args[0]);
+            }
+            integer(removed)
+        }
+        "HGET" => match data.get(&args[0]) {
+            Some((RedisValue::Hash(hash), _)) => hash.get(&args[1]).map_or(NIL.to_string(), |value| bulk(value)),
+            Some(_) => WRONG_TYPE.to_string(),
+            None => NIL.to_string(),
+        },
+        "HVALS" => match data.get(&args[0]) {
+            Some((RedisValue::Hash(hash), _)) => array(hash.values().map(|value| bulk(value)).collect()),
+            _ => array(Vec::new()),
+        },
+        "ZADD" => {
+            let (RedisValue::SortedSet(members), _) = data.entry(args[0].clone()).or_insert((RedisValue::SortedSet(Vec::new()), None)) else {
+                return WRONG_TYPE.to_string();
+            };
+            let mut added = 0;
+            for pair in args[1..].chunks(2) {
+                let before = members.len();
+                members.retain(|(_, member)| *member != pair[1]);
+                added += usize::from(members.len() == before);
+                members.push((score(&pair[0]), pair[1].clone()));
+            }
+            members.sort_by(|a, b| a.0.total_cmp(&b.0));
+            integer(added)
+        }
+        "ZREM" => {
+            let Some((RedisValue::SortedSet(members), _)) = data.get_mut(&args[0]) else {
+                return integer(0);
+            };
+            let before = members.len();
+            members.retain(|(_, member)| !args[1..].contains(member));
+            integer(before - members.len())
+        }
+        "ZREMRANGEBYSCORE" => {
+            let Some((RedisValue::SortedSet(members), _)) = data.get_mut(&args[0]) else {
+                return integer(0);
+            };
+            let (min, max) = (score(&args[1]), score(&args[2]));
+            let before = members.len();
+            members.retain(|(score, _)| *score < min || *score > max);
+            integer(before - members.len())
+        }
+        name @ ("ZRANGEBYSCORE" | "ZREVRANGEBYSCORE") => {
+            let Some((RedisValue::SortedSet(members), _)) = data.get(&args[0]) else {
+                return array(Vec::new());
+            };
+            let reverse = name == "ZREVRANGEBYSCORE";
+            let (min, max) = match reverse {
+                true => (score(&args[2]), score(&args[1])),
+                false => (score(&args[1]), score(&args[2])),
+            };
+            let mut matching: Vec<_> = members.iter()
+                .filter(|(score, _)| (min..=max).contains(score))
+                .map(|(_, member)| bulk(member))
+                .collect();
+            if reverse {
+                matching.reverse();
+            }
+            if let Some(index) = args.iter().position(|arg| arg.eq_ignore_ascii_case("LIMIT")) {
+                let (offset, count): (usize, usize) = (args[index + 1].parse().unwrap(), args[index + 2].parse().unwrap());
+                matching = matching.into_iter().skip(offset).take(count).collect();
+            }
+            array(matching)
+        }
+        "WATCH" | "UNWATCH" => "+OK\r\n".to_string(),
+        other => format!("-ERR unknown command '{}'\r\n", other),
+    }
+}
+
+pub fn rpc_result(request: &Value, result: Value) -> Response<Body> {
+    let body = json!({ "jsonrpc": "2.0", "result": result, "id": request["id"] });
+    Response::new(Body::from(body.to_string()))
+}
+
+pub fn http_error(status: StatusCode) -> Response<Body> {
+    Response::builder().status(status).body(Body::empty()).unwrap()
+}
+
+/// `getTransaction` result for a transaction signed by `signature` that
+/// invokes a random program.
+pub fn encoded_transaction(signature: &Signature, slot: u64) -> Value {
+    let message = Message::new_with_compiled_instructions(
+        1,
+        0,
+        1,
+        vec![Pubkey::new_unique(), Pubkey::new_unique()],
+        Hash::default(),
+        vec![CompiledInstruction::new_from_raw_parts(1, vec![], vec![0])],
+    );
+    let transaction = Transaction {
+        signatures: vec![*signature],
+        message,
+    };
+    let meta = TransactionStatusMeta {
+        status: Ok(()),
+        fee: 5_000,
+        pre_balances: vec![1_000_000, 1],
+        post_balances: vec![995_000, 1],
+        ..Default::default()
+    };
+
+    let encoded = EncodedConfirmedTransactionWithStatusMeta {
+        slot,
+        block_time: None,
+        transaction: EncodedTransactionWithStatusMeta {
+            transaction: transaction.encode(UiTransactionEncoding::Base64),
+            meta: Some(meta.into()),
+            version: None,
+        },
+    };
+    serde_json::to_value(encoded).unwrap()
+}
+
+pub fn bonk_balance(wallet: &Pubkey, amount: u64) -> Value {
+    json!({
+        "accountIndex": 1,
+        "mint": BONK,
+        "owner": wallet.to_string(),
+        "programId": TOKEN_PROGRAM,
+        "uiTokenAmount": {
+            "amount": amount.to_string(),
+            "decimals": 5,
+            "uiAmount": amount as f64 / 1e5,
+            "uiAmountString": (amount as f64 / 1e5).to_string(),
+        },
+    })
+}
+
+/// Balance of `owner`'s token account at `account_index`, in raw units.
+pub fn token_balance(account_index: usize, mint: &str, owner: &Pubkey, amount: u64, decimals: u8) -> TokenBalance {
+    TokenBalance {
+        account_index,
+        mint: mint.to_string(),
+        owner: Some(owner.to_string()),
+        amount,
+        decimals,
+    }
+}
+
+/// A Raydium swap signed by `wallet`, whose token account is at index 1 and
+/// the pool at index 2.
+fn raydium_message(wallet: &Pubkey) -> Message {
+    Message::new_with_compiled_instructions(
+        1,
+        0,
+        1,
+        vec![*wallet, Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::from_str(RAYDIUM).unwrap()],
+        Hash::default(),
+        vec![CompiledInstruction::new_from_raw_parts(3, vec![9], vec![0, 1, 2])],
+    )
+}
+
+/// A Raydium swap by `wallet` with the given status meta, in slot 1.
+pub fn raydium_transaction(wallet: &Pubkey, meta: TransactionMeta) -> TransactionWithMeta {
+    TransactionWithMeta {
+        signature: Signature::new_unique().to_string(),
+        slot: 1,
+        block_time: Some(1_700_000_000),
+        transaction_index: Some(0),
+        transaction: Transaction {
+            signatures: vec![Signature::default()],
+            message: raydium_message(wallet),
+        }.into(),
+        meta,
+    }
+}
+
+/// Base64 `EncodedTransactionWithStatusMeta` of a Raydium swap by `wallet`
+/// that moves its SOL and BONK balances as given.
+pub fn raydium_swap(wallet: &Pubkey, lamports: (u64, u64), bonk: (Option<u64>, u64)) -> Value {
+    let transaction = Transaction {
+        signatures: vec![Signature::new_unique()],
+        message: raydium_message(wallet),
+    };
+    let account_rent = if bonk.0.is_none() { 2_039_280 } else { 0 };
+
+    json!({
+        "transaction": transaction.encode(UiTransactionEncoding::Base64),
+        "meta": {
+            "err": null,
+            "status": { "Ok": null },
+            "fee": 5_000,
+            "preBalances": [lamports.0, 0, 0, 1],
+            "postBalances": [lamports.1, account_rent, 0, 1],
+            "innerInstructions": [],
+            "logMessages": [],
+            "preTokenBalances": bonk.0.map(|amount| vec![bonk_balance(wallet, amount)]).unwrap_or_default(),
+            "postTokenBalances": [bonk_balance(wallet, bonk.1)],
+        },
+    })
+}
@@ -0,0 +1,235 @@
+mod common;
+
+use common::{token_balance, BONK, RAYDIUM, USDC};
+use solana_sdk::{
+    hash::Hash,
+    instruction::CompiledInstruction,
+    message::{Message, VersionedMessage},
+    pubkey::Pubkey,
+    signature::Signature,
+    system_program,
+    transaction::Transaction,
+};
+use solana_wallet_analyzer::{
+    analysis::metrics::MetricsCalculator,
+    ingestion::{
+        attribution::{attribute, squads_v3_vault, squads_v4_vault, SQUADS_V3_PROGRAM, SQUADS_V4_PROGRAM},
+        balances::{owner_balance_changes, BalanceChange},
+        client::SolanaClient,
+        transaction::{InnerInstructions, TransactionMeta, TransactionWithMeta},
+    },
+    types::TradeInfo,
+};
+use std::str::FromStr;
+
+/// `owner` swaps 10 USDC for 20 BONK against a Raydium pool whose vaults are
+/// owned by a pool authority. `signers` sign, the first paying the fee. With
+/// `multisig`, the swap is a CPI of a top-level instruction of that Squads
+/// program naming the multisig as its first account.
+fn swap(signers: &[Pubkey], owner: &Pubkey, multisig: Option<(Pubkey, Pubkey)>) -> TransactionWithMeta {
+    let authority = Pubkey::new_unique();
+    let mut account_keys = signers.to_vec();
+    let first = account_keys.len();
+    account_keys.extend((0..5).map(|_| Pubkey::new_unique()));
+    account_keys[first + 4] = authority;
+    account_keys.push(Pubkey::from_str(RAYDIUM).unwrap());
+    let raydium = account_keys.len() as u8 - 1;
+    let swap_accounts: Vec<u8> = (first as u8..raydium).collect();
+    let swap_instruction = CompiledInstruction::new_from_raw_parts(raydium, vec![9], swap_accounts);
+
+    let (instructions, inner_instructions) = match multisig {
+        Some((program, multisig)) => {
+            account_keys.push(multisig);
+            account_keys.push(program);
+            let program = account_keys.len() as u8 - 1;
+            let execute = CompiledInstruction::new_from_raw_parts(program, vec![1], vec![program - 1]);
+            let inner = InnerInstructions { index: 0, instructions: vec![swap_instruction] };
+            (vec![execute], vec![inner])
+        }
+        None => (vec![swap_instruction], vec![]),
+    };
+    let message = Message::new_with_compiled_instructions(
+        signers.len() as u8,
+        0,
+        2,
+        account_keys.clone(),
+        Hash::default(),
+        instructions,
+    );
+
+    let mut pre_balances = vec![1_000_000_000; account_keys.len()];
+    pre_balances[0] = 2_000_000_000;
+    let mut post_balances = pre_balances.clone();
+    post_balances[0] -= 5_000;
+
+    let (user_usdc, user_bonk, pool_usdc, pool_bonk) = (first, first + 1, first + 2, first + 3);
+    TransactionWithMeta {
+        signature: Signature::default().to_string(),
+        slot: 1,
+        block_time: Some(1_700_000_000),
+        transaction_index: None,
+        transaction: Transaction {
+            signatures: vec![Signature::default(); signers.len()],
+            message,
+        }.into(),
+        meta: TransactionMeta {
+            fee: 5_000,
+            pre_balances,
+            post_balances,
+            pre_token_balances: vec![
+                token_balance(user_usdc, USDC, owner, 10_000_000, 6),
+                token_balance(user_bonk, BONK, owner, 0, 6),
+                token_balance(pool_usdc, USDC, &authority, 500_000_000, 6),
+                token_balance(pool_bonk, BONK, &authority, 900_000_000, 6),
+            ],
+            post_token_balances: vec![
+                token_balance(user_usdc, USDC, owner, 0, 6),
+                token_balance(user_bonk, BONK, owner, 20_000_000, 6),
+                token_balance(pool_usdc, USDC, &authority, 510_000_000, 6),
+                token_balance(pool_bonk, BONK, &authority, 880_000_000, 6),
+            ],
+            inner_instructions,
+            ..Default::default()
+        },
+    }
+}
+
+#[test]
+fn test_fee_payer_trading_for_itself() {
+    let wallet = Pubkey::new_unique();
+
+    let attribution = attribute(&swap(&[wallet], &wallet, None)).unwrap();
+
+    assert_eq!(attribution.fee_payer, wallet);
+    assert_eq!(attribution.owner, wallet);
+}
+
+#[test]
+fn test_relayed_swap_is_attributed_to_the_signing_owner() {
+    let relayer = Pubkey::new_unique();
+    let user = Pubkey::new_unique();
+
+    let trade = SolanaClient::offline(Default::default())
+        .extract_trade_info(&swap(&[relayer, user], &user, None))
+        .unwrap();
+
+    assert_eq!(trade.wallet_address, user.to_string());
+    assert_eq!(trade.fee_payer, relayer.to_string());
+    assert_eq!(trade.token_in_mint, USDC);
+    assert_eq!(trade.token_in_amount, 10.0);
+    assert_eq!(trade.token_out_mint, BONK);
+    assert_eq!(trade.token_out_amount, 20.0);
+}
+
+#[test]
+fn test_rent_of_an_account_the_relayer_opens_is_not_the_traders() {
+    let relayer = Pubkey::new_unique();
+    let user = Pubkey::new_unique();
+    let mut transaction = swap(&[relayer, user], &user, None);
+
+    // The relayer creates the user's BONK account, which the swap pays into
+    let (user_bonk, rent) = (3, 2_039_280u64);
+    let VersionedMessage::Legacy(message) = &mut transaction.transaction.message else { unreachable!() };
+    message.account_keys.push(system_program::id());
+    message.header.num_readonly_unsigned_accounts += 1;
+    let system = message.account_keys.len() as u8 - 1;
+    let create_account = [&0u32.to_le_bytes()[..], &rent.to_le_bytes(), &165u64.to_le_bytes(), &[0; 32]].concat();
+    message.instructions.insert(0, CompiledInstruction::new_from_raw_parts(system, create_account, vec![0, user_bonk as u8]));
+    let meta = &mut transaction.meta;
+    meta.pre_balances.push(1);
+    meta.post_balances.push(1);
+    meta.pre_balances[user_bonk] = 0;
+    meta.post_balances[user_bonk] = rent;
+    meta.post_balances[0] -= rent;
+    meta.pre_token_balances.retain(|balance| balance.account_index != user_bonk);
+
+    assert_eq!(attribute(&transaction).unwrap().changes, vec![
+        BalanceChange { mint: BONK.to_string(), amount: 20.0 },
+        BalanceChange { mint: USDC.to_string(), amount: -10.0 },
+    ]);
+    assert!(owner_balance_changes(&transaction, &relayer).is_empty());
+}
+
+#[test]
+fn test_squads_vaults_are_attributed_as_owner() {
+    let member = Pubkey::new_unique();
+    let multisig = Pubkey::new_unique();
+
+    for (program, vault) in [
+        (SQUADS_V4_PROGRAM, squads_v4_vault(&multisig, 0)),
+        (SQUADS_V3_PROGRAM, squads_v3_vault(&multisig, 1)),
+    ] {
+        let trade = SolanaClient::offline(Default::default())
+            .extract_trade_info(&swap(&[member], &vault, Some((program, multisig))))
+            .unwrap();
+
+        assert_eq!(trade.wallet_address, vault.to_string());
+        assert_eq!(trade.fee_payer, member.to_string());
+    }
+}
+
+#[test]
+fn test_squads_vault_index_is_read_from_create_instructions() {
+    let member = Pubkey::new_unique();
+    let multisig = Pubkey::new_unique();
+    let vault = squads_v4_vault(&multisig, 3);
+
+    // A `vault_transaction_create` for vault 3, bundled with its execution
+    let mut transaction = swap(&[member], &vault, Some((SQUADS_V4_PROGRAM, multisig)));
+    let VersionedMessage::Legacy(message) = &mut transaction.transaction.message else { unreachable!() };
+    message.instructions[0].data = vec![48, 250, 78, 168, 208, 226, 218, 211, 3, 0];
+
+    assert_eq!(attribute(&transaction).unwrap().owner, vault);
+
+    // Without the index, only the default vault is considered
+    assert!(attribute(&swap(&[member], &vault, Some((SQUADS_V4_PROGRAM, multisig)))).is_none());
+}
+
+#[test]
+fn test_relayer_fees_are_not_charged_to_the_user() {
+    let relayer = Pubkey::new_unique();
+    let user = Pubkey::new_unique();
+
+    let trade = SolanaClient::offline(Default::default())
+        .extract_trade_info(&swap(&[relayer, user], &user, None))
+        .unwrap();
+
+    assert_eq!((trade.base_fee, trade.priority_fee, trade.tip), (0, 0, 0));
+
+    // Nor are fees recorded against the payer of someone else's trade
+    let recorded = TradeInfo {
+        base_fee: 5_000,
+        profit_loss: 1.0,
+        ..trade
+    };
+    let metrics = MetricsCalculator::new().calculate_metrics(&[recorded]).unwrap();
+    assert_eq!((metrics.total_costs, metrics.net_profit_loss), (0.0, 1.0));
+}
+
+#[test]
+fn test_member_fees_are_not_charged_to_the_vault() {
+    let member = Pubkey::new_unique();
+    let multisig = Pubkey::new_unique();
+    let vault = squads_v4_vault(&multisig, 0);
+
+    let trade = SolanaClient::offline(Default::default())
+        .extract_trade_info(&swap(&[member], &vault, Some((SQUADS_V4_PROGRAM, multisig))))
+        .unwrap();
+
+    assert_eq!(trade.wallet_address, vault.to_string());
+    assert_eq!((trade.base_fee, trade.priority_fee, trade.tip), (0, 0, 0));
+    assert_eq!(trade.costs(), 0.0);
+}
+
+#[test]
+fn test_non_signing_owners_outside_a_multisig_are_not_attributed() {
+    // Neither the fee payer nor the pool authority is a plausible trader
+    let payer = Pubkey::new_unique();
+    let stranger = Pubkey::new_unique();
+
+    assert!(attribute(&swap(&[payer], &stranger, None)).is_none());
+
+    // Nor is an owner that is not a vault of the executing multisig
+    let multisig = Some((SQUADS_V4_PROGRAM, Pubkey::new_unique()));
+    assert!(attribute(&swap(&[payer], &stranger, multisig)).is_none());
+}
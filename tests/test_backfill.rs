@@ -0,0 +1,55 @@
+use solana_client::rpc_response::RpcConfirmedTransactionStatusWithSignature;
+use solana_wallet_analyzer::ingestion::backfill::{BackfillBound, BackfillRange};
+
+/// A newest-first page covering `slots`, one second per slot.
+fn page(slots: impl DoubleEndedIterator<Item = u64>) -> Vec<RpcConfirmedTransactionStatusWithSignature> {
+    slots
+        .rev()
+        .map(|slot| RpcConfirmedTransactionStatusWithSignature {
+            signature: format!("sig{}", slot),
+            slot,
+            err: None,
+            memo: None,
+            block_time: Some(1_700_000_000 + slot as i64),
+            confirmation_status: None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_select_page_keeps_signatures_inside_range() {
+    let range = BackfillRange {
+        start: BackfillBound::Slot(100),
+        end: Some(BackfillBound::Timestamp(1_700_000_150)),
+    };
+
+    // Entirely after the end: nothing selected, keep walking back
+    let selected = range.select_page(page(160..=169), 10);
+    assert!(selected.signatures.is_empty());
+    assert!(!selected.done);
+    assert_eq!(selected.cursor.unwrap().slot, 160);
+
+    // Straddles the end
+    let selected = range.select_page(page(145..=154), 10);
+    let slots: Vec<u64> = selected.signatures.iter().map(|s| s.slot).collect();
+    assert_eq!(slots, (145..=150).collect::<Vec<_>>());
+    assert!(!selected.done);
+
+    // Straddles the start: oldest first, and the walk is finished
+    let selected = range.select_page(page(95..=104), 10);
+    let slots: Vec<u64> = selected.signatures.iter().map(|s| s.slot).collect();
+    assert_eq!(slots, (100..=104).collect::<Vec<_>>());
+    assert!(selected.done);
+}
+
+#[test]
+fn test_progress_and_id() {
+    let range = BackfillRange {
+        start: BackfillBound::Slot(100),
+        end: None,
+    };
+    let history = page(100..=300);
+
+    assert_eq!(range.progress(&history[0], &history[100]), Some(0.5));
+    assert_eq!(range.id(), "slot:100-tip");
+}
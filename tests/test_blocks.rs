@@ -0,0 +1,89 @@
+mod common;
+
+use common::{encoded_transaction, raydium_swap, rpc_result, MockRpc};
+use hyper::{Body, Response};
+use serde_json::{json, Value};
+use solana_sdk::{hash::Hash, pubkey::Pubkey, signature::Signature};
+use solana_wallet_analyzer::ingestion::{
+    client::{ClientConfig, SolanaClient},
+    decoders::DecoderRegistry,
+};
+use std::sync::Arc;
+use tokio::time::Duration;
+
+const SKIPPED_SLOT: u64 = 102;
+
+fn block(slot: u64, transactions: Vec<Value>) -> Value {
+    json!({
+        "previousBlockhash": Hash::default().to_string(),
+        "blockhash": Hash::default().to_string(),
+        "parentSlot": slot - 1,
+        "blockTime": 1_700_000_000 + slot as i64,
+        "blockHeight": slot - 10,
+        "transactions": transactions,
+    })
+}
+
+/// A transfer that touches no DEX program, as a block entry.
+fn transfer() -> Value {
+    let encoded = encoded_transaction(&Signature::new_unique(), 0);
+    json!({ "transaction": encoded["transaction"], "meta": encoded["meta"] })
+}
+
+fn respond(wallet: &Pubkey, request: &Value) -> Response<Body> {
+    let params = &request["params"];
+    match request["method"].as_str().unwrap() {
+        "getBlocksWithLimit" => {
+            assert_eq!(params[0], 100);
+            assert_eq!(params[1], 3);
+            rpc_result(request, json!([100, 101, SKIPPED_SLOT]))
+        }
+        "getBlock" => {
+            assert_eq!(params[1]["transactionDetails"], "full");
+            assert_eq!(params[1]["maxSupportedTransactionVersion"], 0);
+            let buy = || raydium_swap(wallet, (2_000_000_000, 1_497_955_720), (None, 100_000_000));
+            match params[0].as_u64().unwrap() {
+                100 => rpc_result(request, block(100, vec![transfer(), buy()])),
+                101 => rpc_result(request, block(101, vec![buy(), transfer(), buy()])),
+                _ => {
+                    let body = json!({
+                        "jsonrpc": "2.0",
+                        "error": { "code": -32007, "message": "Slot 102 was skipped" },
+                        "id": request["id"],
+                    });
+                    Response::new(Body::from(body.to_string()))
+                }
+            }
+        }
+        method => panic!("unexpected method {}", method),
+    }
+}
+
+#[tokio::test]
+async fn test_blocks_are_scanned_for_dex_transactions() {
+    let wallet = Arc::new(Pubkey::new_unique());
+    let handler_wallet = Arc::clone(&wallet);
+    let rpc = MockRpc::start(Duration::ZERO, move |request| respond(&handler_wallet, request)).await;
+    let client = SolanaClient::with_config(&rpc.url, DecoderRegistry::default(), ClientConfig {
+        requests_per_second: 0.0,
+        ..ClientConfig::default()
+    }).unwrap();
+
+    let slots = client.get_block_slots(100, 3).await.unwrap();
+    let transactions = client.fetch_blocks(&slots).await.unwrap();
+
+    // Transfers are filtered out and the skipped slot contributes nothing
+    let positions: Vec<_> = transactions.iter()
+        .map(|tx| (tx.slot, tx.transaction_index, tx.block_time))
+        .collect();
+    assert_eq!(positions, vec![
+        (100, Some(1), Some(1_700_000_100)),
+        (101, Some(0), Some(1_700_000_101)),
+        (101, Some(2), Some(1_700_000_101)),
+    ]);
+
+    let trade = client.extract_trade_info(&transactions[1]).unwrap();
+    assert_eq!(trade.wallet_address, wallet.to_string());
+    assert_eq!(trade.slot, 101);
+    assert_eq!(trade.timestamp, 1_700_000_101);
+}
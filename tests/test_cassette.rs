@@ -0,0 +1,123 @@
+mod common;
+
+use common::{encoded_transaction, http_error, rpc_result, MockRpc};
+use hyper::{Body, Response, StatusCode};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use solana_wallet_analyzer::ingestion::{
+    cassette::{CassetteMode, Interaction, RecordedError, RecordedResponse},
+    client::{ClientConfig, SolanaClient},
+    decoders::DecoderRegistry,
+    pool::RpcEndpoint,
+    retry::RetryPolicy,
+    transaction::{TransactionMeta, TransactionWithMeta},
+};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::time::Duration;
+
+fn cassette_path() -> PathBuf {
+    std::env::temp_dir().join(format!("cassette-{}.jsonl", Signature::new_unique()))
+}
+
+fn client(url: &str, cassette: CassetteMode) -> SolanaClient {
+    SolanaClient::with_endpoints(vec![RpcEndpoint::new(url)], DecoderRegistry::default(), ClientConfig {
+        max_concurrent_requests: 1,
+        requests_per_second: 0.0,
+        burst: 1,
+        retry: RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(10),
+        },
+        cassette: Some(cassette),
+        ..ClientConfig::default()
+    }).unwrap()
+}
+
+fn contents(transactions: &[TransactionWithMeta]) -> Vec<(String, TransactionMeta)> {
+    transactions.iter().map(|tx| (tx.signature.clone(), tx.meta.clone())).collect()
+}
+
+#[tokio::test]
+async fn test_replay_serves_recorded_traffic_in_order() {
+    // The first request is refused once, so the cassette holds a retry
+    let seen = AtomicUsize::new(0);
+    let rpc = MockRpc::start(Duration::ZERO, move |request| {
+        if seen.fetch_add(1, Ordering::SeqCst) == 0 {
+            return http_error(StatusCode::SERVICE_UNAVAILABLE);
+        }
+        let signature = Signature::from_str(request["params"][0].as_str().unwrap()).unwrap();
+        rpc_result(request, encoded_transaction(&signature, 7))
+    }).await;
+    let path = cassette_path();
+    let signatures: Vec<_> = (0..3).map(|_| Signature::new_unique().to_string()).collect();
+
+    let recorded = client(&rpc.url, CassetteMode::Record(path.clone()))
+        .fetch_transactions(&signatures).await.unwrap();
+    assert_eq!(recorded.len(), 3);
+    assert_eq!(rpc.request_count(), 4);
+
+    let interactions: Vec<Interaction> = std::fs::read_to_string(&path).unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .filter(|interaction: &Interaction| interaction.method == "getTransaction")
+        .collect();
+    assert_eq!(interactions.len(), 4);
+    assert_eq!(
+        interactions[0].response,
+        RecordedResponse::Error(RecordedError::HttpStatus { status: 503, retry_after_ms: None }),
+    );
+
+    // Replay needs no server: the same failure and retry happen from the tape
+    let replayed = client("replay:", CassetteMode::Replay(path.clone()))
+        .fetch_transactions(&signatures).await.unwrap();
+    assert_eq!(contents(&replayed), contents(&recorded));
+    assert_eq!(rpc.request_count(), 4);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn test_replay_retries_recorded_transport_errors() {
+    // The first response body is garbled, which fails reading it
+    let seen = AtomicUsize::new(0);
+    let rpc = MockRpc::start(Duration::ZERO, move |request| {
+        if seen.fetch_add(1, Ordering::SeqCst) == 0 {
+            return Response::new(Body::from("{\"jsonrpc\": \"2.0\", \"res"));
+        }
+        let signature = Signature::from_str(request["params"][0].as_str().unwrap()).unwrap();
+        rpc_result(request, encoded_transaction(&signature, 7))
+    }).await;
+    let path = cassette_path();
+    let signatures = vec![Signature::new_unique().to_string()];
+
+    let recorded = client(&rpc.url, CassetteMode::Record(path.clone()))
+        .fetch_transactions(&signatures).await.unwrap();
+    assert_eq!(rpc.request_count(), 2);
+
+    let interaction = std::fs::read_to_string(&path).unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<Interaction>(line).unwrap())
+        .find(|interaction| interaction.method == "getTransaction")
+        .unwrap();
+    assert!(matches!(interaction.response, RecordedResponse::Error(RecordedError::Network(_))));
+
+    // Replayed, the failure is retried just as it was while recording
+    let replayed = client("replay:", CassetteMode::Replay(path.clone()))
+        .fetch_transactions(&signatures).await.unwrap();
+    assert_eq!(contents(&replayed), contents(&recorded));
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn test_replay_fails_requests_that_were_not_recorded() {
+    let path = cassette_path();
+    std::fs::write(&path, "").unwrap();
+    let client = client("replay:", CassetteMode::Replay(path.clone()));
+
+    assert!(client.get_signatures_before(&Pubkey::new_unique(), None, 10).await.is_err());
+
+    std::fs::remove_file(&path).unwrap();
+}
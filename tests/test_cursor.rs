@@ -0,0 +1,113 @@
+use solana_client::rpc_response::RpcConfirmedTransactionStatusWithSignature;
+use solana_sdk::signature::Signature;
+use std::cell::Cell;
+use solana_wallet_analyzer::{
+    ingestion::cursor::{signatures_since, MAX_SIGNATURES_PER_PAGE},
+    types::SignatureCursor,
+};
+
+/// Signatures for slots 1..=count, served newest first like the RPC does.
+fn history(count: u64) -> Vec<RpcConfirmedTransactionStatusWithSignature> {
+    (1..=count)
+        .rev()
+        .map(|slot| {
+            let mut bytes = [0u8; 64];
+            bytes[..8].copy_from_slice(&slot.to_le_bytes());
+            RpcConfirmedTransactionStatusWithSignature {
+                signature: Signature::from(bytes).to_string(),
+                slot,
+                err: None,
+                memo: None,
+                block_time: None,
+                confirmation_status: None,
+            }
+        })
+        .collect()
+}
+
+type Page = solana_wallet_analyzer::error::Result<Vec<RpcConfirmedTransactionStatusWithSignature>>;
+
+/// Serves `history` the way `getSignaturesForAddress` pages with `before`/`until`.
+fn page<'a>(
+    history: &'a [RpcConfirmedTransactionStatusWithSignature],
+    until: Option<&'a SignatureCursor>,
+) -> impl FnMut(Option<Signature>, usize) -> std::future::Ready<Page> + 'a {
+    move |before, limit| {
+        std::future::ready(Ok(history
+            .iter()
+            .skip_while(|s| before.is_some_and(|b| s.signature != b.to_string()))
+            .skip(if before.is_some() { 1 } else { 0 })
+            .take_while(|s| until.is_none_or(|u| s.signature != u.signature))
+            .take(limit)
+            .cloned()
+            .collect()))
+    }
+}
+
+/// Wraps `page`, adding up the page sizes asked for.
+fn counted<'a>(
+    mut page: impl FnMut(Option<Signature>, usize) -> std::future::Ready<Page> + 'a,
+    fetched: &'a Cell<usize>,
+) -> impl FnMut(Option<Signature>, usize) -> std::future::Ready<Page> + 'a {
+    move |before, limit| {
+        fetched.set(fetched.get() + limit);
+        page(before, limit)
+    }
+}
+
+#[tokio::test]
+async fn test_first_run_takes_latest_signatures() {
+    let history = history(50);
+    let batch = signatures_since(None, 10, page(&history, None)).await.unwrap();
+
+    let slots: Vec<u64> = batch.signatures.iter().map(|s| s.slot).collect();
+    assert_eq!(slots, (41..=50).collect::<Vec<_>>());
+    assert_eq!(batch.cursor, Some(SignatureCursor::from(&history[0])));
+}
+
+#[tokio::test]
+async fn test_cursor_walks_forward_without_gaps() {
+    let history = history(2 * MAX_SIGNATURES_PER_PAGE as u64 + 500);
+    let mut cursor = SignatureCursor::from(&history[history.len() - 100]);
+    let mut seen = Vec::new();
+
+    loop {
+        let batch = signatures_since(Some(&cursor), 700, page(&history, Some(&cursor))).await.unwrap();
+        // Oldest first within a batch
+        assert!(batch.signatures.windows(2).all(|pair| pair[0].slot < pair[1].slot));
+        seen.extend(batch.signatures.iter().map(|s| s.slot));
+        cursor = batch.cursor.unwrap();
+        if batch.signatures.is_empty() {
+            break;
+        }
+    }
+
+    seen.sort_unstable();
+    let expected: Vec<u64> = (101..=history.len() as u64).collect();
+    assert_eq!(seen, expected);
+    assert_eq!(cursor, SignatureCursor::from(&history[0]));
+}
+
+#[tokio::test]
+async fn test_long_walks_resume_where_they_stopped() {
+    let history = history(2 * MAX_SIGNATURES_PER_PAGE as u64 + 500);
+    let cursor = SignatureCursor::from(&history[history.len() - 1]);
+    let fetched = Cell::new(0);
+
+    let batch = signatures_since(Some(&cursor), 300, counted(page(&history, Some(&cursor)), &fetched)).await.unwrap();
+
+    // Only what was asked for is fetched, newest first from the tip
+    assert_eq!(fetched.get(), 300);
+    assert_eq!(batch.signatures.first().unwrap().slot, history.len() as u64 - 299);
+    let next = batch.cursor.unwrap();
+    assert_eq!((next.signature.as_str(), next.slot), (cursor.signature.as_str(), cursor.slot));
+    let backlog = next.backlog.clone().unwrap();
+    assert_eq!(backlog.newest, history[0].signature);
+    assert_eq!(backlog.before, history[299].signature);
+
+    // The next call continues below the batch rather than from the tip
+    fetched.set(0);
+    let batch = signatures_since(Some(&next), 300, counted(page(&history, Some(&cursor)), &fetched)).await.unwrap();
+    assert_eq!(fetched.get(), 300);
+    assert_eq!(batch.signatures.last().unwrap().slot, history.len() as u64 - 300);
+}
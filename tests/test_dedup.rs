@@ -0,0 +1,111 @@
+mod common;
+
+use common::MockRedis;
+use solana_sdk::signature::Signature;
+use solana_wallet_analyzer::{
+    ingestion::{dedup::unseen, transaction::TransactionWithMeta},
+    storage::redis::{RedisStorage, CLAIM_TTL},
+    types::{CapitalFlow, FlowKind, TradeInfo},
+};
+
+fn transaction(signature: &str) -> TransactionWithMeta {
+    TransactionWithMeta {
+        signature: signature.to_string(),
+        slot: 0,
+        block_time: None,
+        transaction_index: None,
+        transaction: Default::default(),
+        meta: Default::default(),
+    }
+}
+
+#[test]
+fn test_seen_and_repeated_signatures_are_skipped() {
+    let [a, b, c] = [(); 3].map(|_| Signature::new_unique().to_string());
+    let batch = vec![transaction(&a), transaction(&b), transaction(&c), transaction(&c)];
+
+    let (fresh, duplicates) = unseen(&batch, &[false, true, false, false]);
+
+    let signatures: Vec<_> = fresh.iter().map(|tx| tx.signature.as_str()).collect();
+    assert_eq!(signatures, vec![a.as_str(), c.as_str()]);
+    assert_eq!(duplicates, 2);
+}
+
+#[test]
+fn test_nothing_seen_keeps_the_whole_batch() {
+    let batch = vec![transaction("first"), transaction("second")];
+
+    let (fresh, duplicates) = unseen(&batch, &[]);
+
+    assert_eq!(fresh.len(), 2);
+    assert_eq!(duplicates, 0);
+}
+
+#[tokio::test]
+async fn test_signatures_are_claimed_once() {
+    let redis = MockRedis::start();
+    let storage = RedisStorage::new(&redis.url).unwrap().with_dedup_ttl(3600);
+    let [a, b] = [(); 2].map(|_| Signature::new_unique().to_string());
+
+    // A repeat within the batch, or a second task's claim, loses
+    assert_eq!(storage.claim_signatures(&[a.clone(), b.clone(), a.clone()]).await.unwrap(), vec![true, true, false]);
+    assert_eq!(storage.claim_signatures(std::slice::from_ref(&a)).await.unwrap(), vec![false]);
+    assert_eq!(redis.ttl(&format!("seen:{}", a)), Some(Some(CLAIM_TTL)));
+
+    // Processed signatures are remembered for the dedup TTL
+    storage.mark_processed(&[&a]).await.unwrap();
+    assert_eq!(redis.ttl(&format!("seen:{}", a)), Some(Some(3600)));
+
+    // A released claim can be taken again
+    storage.release_signatures(&[&b]).await.unwrap();
+    assert_eq!(storage.claim_signatures(&[a, b]).await.unwrap(), vec![false, true]);
+}
+
+#[tokio::test]
+async fn test_restored_trades_and_flows_replace_earlier_copies() {
+    let redis = MockRedis::start();
+    let storage = RedisStorage::new(&redis.url).unwrap();
+    let signature = Signature::new_unique().to_string();
+    let trade = TradeInfo {
+        wallet_address: "wallet".to_string(),
+        transaction_hash: signature.clone(),
+        timestamp: 1_700_000_000,
+        slot: 10,
+        provisional: true,
+        ..Default::default()
+    };
+    let flow = CapitalFlow {
+        wallet_address: "wallet".to_string(),
+        counterparty: String::new(),
+        kind: FlowKind::SolTransfer,
+        mint: "So11111111111111111111111111111111111111112".to_string(),
+        amount: -1.5,
+        timestamp: 1_700_000_000,
+        slot: 10,
+        transaction_index: None,
+        transaction_hash: signature.clone(),
+        provisional: true,
+    };
+
+    // Reprocessing serializes differently, e.g. once the block is finalized
+    storage.store_trades(std::slice::from_ref(&trade)).await.unwrap();
+    storage.store_flows(std::slice::from_ref(&flow)).await.unwrap();
+    let finalized_trade = TradeInfo { provisional: false, ..trade.clone() };
+    let finalized_flow = CapitalFlow { provisional: false, ..flow.clone() };
+    storage.store_trades(std::slice::from_ref(&finalized_trade)).await.unwrap();
+    storage.store_flows(std::slice::from_ref(&finalized_flow)).await.unwrap();
+
+    let trades = storage.get_wallet_trades("wallet").await.unwrap();
+    assert_eq!(trades.len(), 1);
+    assert!(!trades[0].provisional);
+    assert_eq!(storage.get_wallet_flows("wallet").await.unwrap(), vec![finalized_flow]);
+    assert!(storage.get_provisional_trades(u64::MAX).await.unwrap().is_empty());
+    assert!(storage.get_provisional_flows(u64::MAX).await.unwrap().is_empty());
+
+    // A rolled back trade is removed however often it was stored
+    storage.store_trades(&[trade.clone(), trade]).await.unwrap();
+    let provisional = storage.get_provisional_trades(u64::MAX).await.unwrap();
+    assert_eq!(provisional.len(), 1);
+    storage.revert_trades(&provisional).await.unwrap();
+    assert!(storage.get_wallet_trades("wallet").await.unwrap().is_empty());
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use common::{encoded_transaction, raydium_swap};
+use serde_json::{json, Value};
+use solana_sdk::{hash::Hash, pubkey::Pubkey, signature::Signature};
+use solana_wallet_analyzer::{
+    analysis::metrics::MetricsCalculator,
+    ingestion::{client::SolanaClient, decoders::DecoderRegistry, dump::DumpReader},
+};
+use std::io::Cursor;
+
+fn dump(wallet: &Pubkey) -> String {
+    // Buy 1,000 BONK for 0.5 SOL, then sell them for 0.6 SOL
+    let buy = raydium_swap(wallet, (2_000_000_000, 1_497_955_720), (None, 100_000_000));
+    let sell = raydium_swap(wallet, (1_497_955_720, 2_097_950_720), (Some(100_000_000), 0));
+    let transfer = encoded_transaction(&Signature::new_unique(), 101);
+
+    let mut get_transaction = buy;
+    get_transaction["slot"] = json!(100);
+    get_transaction["blockTime"] = json!(1_700_000_000);
+
+    let lines = [
+        json!({ "jsonrpc": "2.0", "result": get_transaction, "id": 1 }),
+        Value::Null,
+        json!({
+            "previousBlockhash": Hash::default().to_string(),
+            "blockhash": Hash::default().to_string(),
+            "parentSlot": 100,
+            "blockTime": 1_700_000_400,
+            "blockHeight": 90,
+            "transactions": [sell, { "transaction": transfer["transaction"], "meta": transfer["meta"] }],
+        }),
+        json!({ "unexpected": true }),
+        json!({ "jsonrpc": "2.0", "result": null, "id": 2 }),
+    ];
+    lines.iter()
+        .map(|line| if line.is_null() { String::new() } else { line.to_string() })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn test_dump_reader_reads_transactions_and_blocks() {
+    let wallet = Pubkey::new_unique();
+    let results: Vec<_> = DumpReader::new(Cursor::new(dump(&wallet))).collect();
+
+    // One transaction from getTransaction, two from getBlock, and the bad line
+    assert_eq!(results.len(), 4);
+    assert!(results[..3].iter().all(|result| result.is_ok()));
+    assert!(results[3].as_ref().unwrap_err().to_string().contains("line 4"));
+
+    // Block transactions carry the block's time and their position in it
+    let positions: Vec<_> = results[..3].iter()
+        .map(|result| result.as_ref().unwrap())
+        .map(|tx| (tx.slot, tx.block_time, tx.transaction_index))
+        .collect();
+    assert_eq!(positions, vec![
+        (100, Some(1_700_000_000), None),
+        (101, Some(1_700_000_400), Some(0)),
+        (101, Some(1_700_000_400), Some(1)),
+    ]);
+}
+
+#[test]
+fn test_dump_runs_through_trade_extraction_and_metrics() {
+    let wallet = Pubkey::new_unique();
+    let client = SolanaClient::offline(DecoderRegistry::default());
+
+    let mut trades: Vec<_> = DumpReader::new(Cursor::new(dump(&wallet)))
+        .filter_map(Result::ok)
+        .filter_map(|tx| client.extract_trade_info(&tx))
+        .collect();
+    assert_eq!(trades.len(), 2);
+    assert!(trades.iter().all(|trade| trade.wallet_address == wallet.to_string()));
+    assert_eq!(trades[0].timestamp, 1_700_000_000);
+    assert_eq!(trades[1].timestamp, 1_700_000_400);
+    assert!(trades.iter().all(|trade| !trade.provisional));
+
+    let calculator = MetricsCalculator::new();
+    calculator.apply_realized_pnl(&mut trades);
+    let metrics = calculator.calculate_metrics(&trades).unwrap();
+
+    assert_eq!(metrics.trade_count, 2);
+    assert!((metrics.total_profit_loss - 0.1).abs() < 1e-9);
+}
+
+#[test]
+fn test_offline_client_rejects_rpc_calls() {
+    let client = SolanaClient::offline(DecoderRegistry::default());
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let result = runtime.block_on(client.get_signatures_before(&Pubkey::new_unique(), None, 10));
+
+    assert!(result.is_err());
+}
@@ -0,0 +1,162 @@
+mod common;
+
+use common::{token_balance, BONK, RAYDIUM};
+use solana_sdk::{
+    compute_budget::{self, ComputeBudgetInstruction},
+    hash::Hash,
+    instruction::CompiledInstruction,
+    message::{Message, VersionedMessage},
+    pubkey::Pubkey,
+    signature::Signature,
+    system_instruction::SystemInstruction,
+    system_program,
+    transaction::Transaction,
+};
+use solana_wallet_analyzer::{
+    analysis::metrics::MetricsCalculator,
+    ingestion::{
+        balances::WRAPPED_SOL_MINT,
+        client::SolanaClient,
+        decoders::DecoderRegistry,
+        fees::{TransactionCosts, JITO_TIP_ACCOUNTS},
+        transaction::{TransactionMeta, TransactionWithMeta},
+    },
+    types::TradeInfo,
+};
+use std::str::FromStr;
+
+const TIP: u64 = 100_000;
+
+/// Buys 1,000 BONK for 0.5 SOL through Raydium with a 300k CU limit at
+/// 10,000 micro-lamports per CU, tipping Jito 100,000 lamports.
+fn tipped_buy(wallet: &Pubkey) -> TransactionWithMeta {
+    let account_keys = vec![
+        *wallet,
+        Pubkey::new_unique(),
+        JITO_TIP_ACCOUNTS[0],
+        Pubkey::new_unique(),
+        Pubkey::from_str(RAYDIUM).unwrap(),
+        compute_budget::id(),
+        system_program::id(),
+    ];
+    let instructions = vec![
+        CompiledInstruction::new_from_raw_parts(5, ComputeBudgetInstruction::set_compute_unit_limit(300_000).data, vec![]),
+        CompiledInstruction::new_from_raw_parts(5, ComputeBudgetInstruction::set_compute_unit_price(10_000).data, vec![]),
+        CompiledInstruction::new_from_raw_parts(4, vec![9], vec![0, 1, 3]),
+        CompiledInstruction::new(6, &SystemInstruction::Transfer { lamports: TIP }, vec![0, 2]),
+    ];
+    let message = Message::new_with_compiled_instructions(1, 0, 3, account_keys, Hash::default(), instructions);
+    let fee = 5_000 + 3_000;
+
+    TransactionWithMeta {
+        signature: Signature::default().to_string(),
+        slot: 1,
+        block_time: Some(1_700_000_000),
+        transaction_index: Some(0),
+        transaction: Transaction {
+            signatures: vec![Signature::default()],
+            message,
+        }.into(),
+        meta: TransactionMeta {
+            fee,
+            pre_balances: vec![2_000_000_000, 0, 1_000_000, 0, 1, 1, 1],
+            post_balances: vec![2_000_000_000 - 500_000_000 - 2_039_280 - fee - TIP, 2_039_280, 1_000_000 + TIP, 0, 1, 1, 1],
+            post_token_balances: vec![token_balance(1, BONK, wallet, 100_000_000, 5)],
+            ..Default::default()
+        },
+    }
+}
+
+#[test]
+fn test_costs_are_split_into_base_priority_and_tip() {
+    let wallet = Pubkey::new_unique();
+    let costs = TransactionCosts::paid_by(&tipped_buy(&wallet), &wallet);
+
+    assert_eq!(costs, TransactionCosts { base_fee: 5_000, priority_fee: 3_000, tip: TIP });
+    assert_eq!(costs.total(), 108_000);
+}
+
+#[test]
+fn test_default_compute_unit_limit_applies_per_instruction() {
+    let wallet = Pubkey::new_unique();
+    let mut tx = tipped_buy(&wallet);
+    let VersionedMessage::Legacy(message) = &mut tx.transaction.message else { unreachable!() };
+    message.instructions.remove(0);
+
+    // Two non-budget instructions at 200k CU each, 10,000 micro-lamports per CU
+    assert_eq!(TransactionCosts::paid_by(&tx, &wallet).priority_fee, 4_000);
+}
+
+#[test]
+fn test_only_the_wallets_own_fees_and_tips_are_its_costs() {
+    let wallet = Pubkey::new_unique();
+    let partner = Pubkey::new_unique();
+    let mut tx = tipped_buy(&wallet);
+    let VersionedMessage::Legacy(message) = &mut tx.transaction.message else { unreachable!() };
+    message.account_keys.push(partner);
+    message.instructions.push(CompiledInstruction::new(6, &SystemInstruction::Transfer { lamports: 7 * TIP }, vec![7, 2]));
+    tx.meta.pre_balances.push(1_000_000_000);
+    tx.meta.post_balances.push(1_000_000_000 - 7 * TIP);
+    tx.meta.post_balances[2] += 7 * TIP;
+
+    assert_eq!(TransactionCosts::paid_by(&tx, &wallet).tip, TIP);
+    // Another payer's tip is its own, and it pays no fees
+    assert_eq!(TransactionCosts::paid_by(&tx, &partner), TransactionCosts { tip: 7 * TIP, ..Default::default() });
+}
+
+#[test]
+fn test_trade_carries_costs_and_excludes_tip_from_amounts() {
+    let wallet = Pubkey::new_unique();
+    let trade = SolanaClient::offline(DecoderRegistry::default())
+        .extract_trade_info(&tipped_buy(&wallet))
+        .unwrap();
+
+    assert_eq!(trade.token_in_mint, WRAPPED_SOL_MINT);
+    assert_eq!(trade.token_in_amount, 0.5);
+    assert_eq!((trade.base_fee, trade.priority_fee, trade.tip), (5_000, 3_000, TIP));
+    assert!(!trade.failed);
+}
+
+#[test]
+fn test_failed_swap_is_recorded_for_its_costs_only() {
+    let wallet = Pubkey::new_unique();
+    let client = SolanaClient::offline(DecoderRegistry::default());
+    let mut tx = tipped_buy(&wallet);
+    tx.meta.err = Some("Error processing Instruction 2: custom program error: 0x1e".to_string());
+
+    assert!(client.extract_trade_info(&tx).is_none());
+
+    let record = client.extract_failed_swap(&tx).unwrap();
+    assert!(record.failed);
+    assert_eq!(record.wallet_address, wallet.to_string());
+    assert_eq!(record.venue, "raydium-amm-v4");
+    assert_eq!(record.token_in_mint, "");
+    assert_eq!(record.base_fee + record.priority_fee, 8_000);
+}
+
+#[test]
+fn test_metrics_report_net_profit_after_costs() {
+    let trade = |profit_loss: f64, base_fee: u64, failed: bool| TradeInfo {
+        wallet_address: "wallet".to_string(),
+        amount: 1.0,
+        profit_loss,
+        base_fee,
+        priority_fee: 1_000_000,
+        failed,
+        ..Default::default()
+    };
+    let trades = vec![
+        trade(0.5, 5_000_000, false),
+        trade(-0.1, 5_000_000, false),
+        trade(0.0, 4_000_000, true),
+    ];
+
+    let metrics = MetricsCalculator::new().calculate_metrics(&trades).unwrap();
+
+    assert_eq!(metrics.trade_count, 2);
+    assert_eq!(metrics.failed_transaction_count, 1);
+    assert_eq!(metrics.win_rate, 50.0);
+    assert!((metrics.total_profit_loss - 0.4).abs() < 1e-9);
+    assert!((metrics.total_costs - 0.017).abs() < 1e-9);
+    assert!((metrics.net_profit_loss - 0.383).abs() < 1e-9);
+}
@@ -0,0 +1,56 @@
+mod common;
+
+use common::{encoded_transaction, rpc_result, MockRpc};
+use serde_json::Value;
+use solana_sdk::signature::Signature;
+use solana_wallet_analyzer::ingestion::{
+    client::{ClientConfig, SolanaClient},
+    decoders::DecoderRegistry,
+    rate_limit::RateLimiter,
+};
+use std::str::FromStr;
+use std::sync::atomic::Ordering;
+use tokio::time::{Duration, Instant};
+
+fn get_transaction(request: &Value) -> hyper::Response<hyper::Body> {
+    assert_eq!(request["method"], "getTransaction");
+    let signature = Signature::from_str(request["params"][0].as_str().unwrap()).unwrap();
+    rpc_result(request, encoded_transaction(&signature, 1))
+}
+
+#[tokio::test]
+async fn test_fetch_transactions_runs_in_parallel_and_preserves_order() {
+    let rpc = MockRpc::start(Duration::from_millis(50), get_transaction).await;
+    let client = SolanaClient::with_config(&rpc.url, DecoderRegistry::default(), ClientConfig {
+        max_concurrent_requests: 4,
+        requests_per_second: 0.0,
+        burst: 4,
+        ..ClientConfig::default()
+    }).unwrap();
+
+    let signatures: Vec<_> = (0..20).map(|_| Signature::new_unique().to_string()).collect();
+    let transactions = client.fetch_transactions(&signatures).await.unwrap();
+
+    let fetched: Vec<_> = transactions.iter().map(|tx| tx.signature.clone()).collect();
+    assert_eq!(fetched, signatures);
+    assert_eq!(rpc.request_count(), 20);
+    assert_eq!(rpc.max_in_flight.load(Ordering::SeqCst), 4);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_rate_limiter_allows_burst_then_refills() {
+    let limiter = RateLimiter::new(10.0, 5);
+    let start = Instant::now();
+
+    for _ in 0..5 {
+        limiter.acquire().await;
+    }
+    assert_eq!(start.elapsed(), Duration::ZERO);
+
+    for _ in 0..20 {
+        limiter.acquire().await;
+    }
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_secs(2), "{:?}", elapsed);
+    assert!(elapsed < Duration::from_millis(2100), "{:?}", elapsed);
+}
@@ -0,0 +1,200 @@
+mod common;
+
+use common::{token_balance, BONK, RAYDIUM};
+use solana_sdk::{
+    hash::Hash,
+    instruction::CompiledInstruction,
+    message::{Message, VersionedMessage},
+    pubkey::Pubkey,
+    signature::Signature,
+    system_instruction::SystemInstruction,
+    system_program,
+    transaction::Transaction,
+};
+use solana_wallet_analyzer::{
+    analysis::metrics::MetricsCalculator,
+    ingestion::{
+        balances::WRAPPED_SOL_MINT,
+        client::{SolanaClient, TOKEN_PROGRAM_ID},
+        decoders::DecoderRegistry,
+        flows::{capital_flows, EXCHANGE_WALLETS},
+        transaction::{TransactionMeta, TransactionWithMeta},
+    },
+    types::{CapitalFlow, FlowKind, TradeInfo},
+};
+use std::str::FromStr;
+
+fn transaction(message: Message, meta: TransactionMeta) -> TransactionWithMeta {
+    TransactionWithMeta {
+        signature: Signature::new_unique().to_string(),
+        slot: 1,
+        block_time: Some(1_700_000_000),
+        transaction_index: Some(0),
+        transaction: Transaction {
+            signatures: vec![Signature::default()],
+            message,
+        }.into(),
+        meta,
+    }
+}
+
+/// An exchange hot wallet sends 5 SOL to `wallet`.
+fn exchange_withdrawal(wallet: &Pubkey) -> TransactionWithMeta {
+    let exchange = EXCHANGE_WALLETS[0].0;
+    let message = Message::new_with_compiled_instructions(
+        1,
+        0,
+        1,
+        vec![exchange, *wallet, system_program::id()],
+        Hash::default(),
+        vec![CompiledInstruction::new(2, &SystemInstruction::Transfer { lamports: 5_000_000_000 }, vec![0, 1])],
+    );
+    transaction(message, TransactionMeta {
+        fee: 5_000,
+        pre_balances: vec![1_000_000_000_000, 0, 1],
+        post_balances: vec![1_000_000_000_000 - 5_000_000_000 - 5_000, 5_000_000_000, 1],
+        ..Default::default()
+    })
+}
+
+/// `sender` transfers 100 BONK to `recipient`'s existing token account.
+fn token_transfer(sender: &Pubkey, recipient: &Pubkey) -> TransactionWithMeta {
+    let message = Message::new_with_compiled_instructions(
+        1,
+        0,
+        1,
+        vec![*sender, Pubkey::new_unique(), Pubkey::new_unique(), TOKEN_PROGRAM_ID],
+        Hash::default(),
+        vec![CompiledInstruction::new_from_raw_parts(3, vec![3], vec![1, 2, 0])],
+    );
+    transaction(message, TransactionMeta {
+        fee: 5_000,
+        pre_balances: vec![1_000_000_000, 2_039_280, 2_039_280, 1],
+        post_balances: vec![1_000_000_000 - 5_000, 2_039_280, 2_039_280, 1],
+        pre_token_balances: vec![token_balance(1, BONK, sender, 50_000_000, 5), token_balance(2, BONK, recipient, 0, 5)],
+        post_token_balances: vec![token_balance(1, BONK, sender, 40_000_000, 5), token_balance(2, BONK, recipient, 10_000_000, 5)],
+        ..Default::default()
+    })
+}
+
+#[test]
+fn test_exchange_withdrawal_is_an_inflow() {
+    let wallet = Pubkey::new_unique();
+    let flows = capital_flows(&exchange_withdrawal(&wallet));
+
+    // The exchange's own side is not recorded
+    assert_eq!(flows.len(), 1);
+    assert_eq!(flows[0].wallet_address, wallet.to_string());
+    assert_eq!(flows[0].kind, FlowKind::ExchangeWithdrawal);
+    assert_eq!(flows[0].counterparty, EXCHANGE_WALLETS[0].0.to_string());
+    assert_eq!(flows[0].mint, WRAPPED_SOL_MINT);
+    assert_eq!(flows[0].amount, 5.0);
+}
+
+#[test]
+fn test_token_transfer_is_recorded_on_both_sides() {
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    let flows = capital_flows(&token_transfer(&sender, &recipient));
+
+    let summary: Vec<_> = flows.iter()
+        .map(|flow| (flow.wallet_address.clone(), flow.kind, flow.amount, flow.counterparty.clone()))
+        .collect();
+    assert_eq!(summary, vec![
+        (sender.to_string(), FlowKind::TokenTransfer, -100.0, recipient.to_string()),
+        (recipient.to_string(), FlowKind::TokenTransfer, 100.0, sender.to_string()),
+    ]);
+}
+
+/// `sender` transfers 100 BONK to `recipient`, first creating and paying
+/// for the recipient's token account.
+fn token_transfer_opening_account(sender: &Pubkey, recipient: &Pubkey) -> TransactionWithMeta {
+    let create = SystemInstruction::CreateAccount { lamports: 2_039_280, space: 165, owner: TOKEN_PROGRAM_ID };
+    let message = Message::new_with_compiled_instructions(
+        1,
+        0,
+        2,
+        vec![*sender, Pubkey::new_unique(), Pubkey::new_unique(), system_program::id(), TOKEN_PROGRAM_ID],
+        Hash::default(),
+        vec![
+            CompiledInstruction::new(3, &create, vec![0, 2]),
+            CompiledInstruction::new_from_raw_parts(4, vec![3], vec![1, 2, 0]),
+        ],
+    );
+    transaction(message, TransactionMeta {
+        fee: 5_000,
+        pre_balances: vec![1_000_000_000, 2_039_280, 0, 1, 1],
+        post_balances: vec![1_000_000_000 - 5_000 - 2_039_280, 2_039_280, 2_039_280, 1, 1],
+        pre_token_balances: vec![token_balance(1, BONK, sender, 50_000_000, 5)],
+        post_token_balances: vec![token_balance(1, BONK, sender, 40_000_000, 5), token_balance(2, BONK, recipient, 10_000_000, 5)],
+        ..Default::default()
+    })
+}
+
+#[test]
+fn test_rent_of_opened_accounts_is_not_a_flow() {
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    let flows = capital_flows(&token_transfer_opening_account(&sender, &recipient));
+
+    // Neither the sender's rent payment nor the recipient's new account is a SOL transfer
+    let summary: Vec<_> = flows.iter()
+        .map(|flow| (flow.wallet_address.clone(), flow.mint.as_str(), flow.amount))
+        .collect();
+    assert_eq!(summary, vec![
+        (sender.to_string(), BONK, -100.0),
+        (recipient.to_string(), BONK, 100.0),
+    ]);
+}
+
+#[test]
+fn test_transactions_on_known_venues_are_not_flows() {
+    let client = SolanaClient::offline(DecoderRegistry::default());
+    let sender = Pubkey::new_unique();
+    let mut tx = token_transfer(&sender, &Pubkey::new_unique());
+    assert_eq!(client.extract_capital_flows(&tx).len(), 2);
+
+    let VersionedMessage::Legacy(message) = &mut tx.transaction.message else { unreachable!() };
+    message.account_keys.push(Pubkey::from_str(RAYDIUM).unwrap());
+    message.header.num_readonly_unsigned_accounts += 1;
+    message.instructions.push(CompiledInstruction::new_from_raw_parts(4, vec![9], vec![0]));
+    tx.meta.pre_balances.push(1);
+    tx.meta.post_balances.push(1);
+
+    assert!(client.extract_capital_flows(&tx).is_empty());
+}
+
+#[test]
+fn test_flows_set_roi_without_touching_profit() {
+    let calculator = MetricsCalculator::new();
+    let trades = vec![TradeInfo {
+        wallet_address: "wallet".to_string(),
+        profit_loss: 2.0,
+        ..Default::default()
+    }];
+    let flow = |amount: f64, kind: FlowKind| CapitalFlow {
+        wallet_address: "wallet".to_string(),
+        counterparty: String::new(),
+        kind,
+        mint: WRAPPED_SOL_MINT.to_string(),
+        amount,
+        timestamp: 0,
+        slot: 0,
+        transaction_index: None,
+        transaction_hash: String::new(),
+        provisional: false,
+    };
+    let flows = vec![
+        flow(10.0, FlowKind::ExchangeWithdrawal),
+        flow(-3.0, FlowKind::SolTransfer),
+        CapitalFlow { mint: BONK.to_string(), ..flow(1_000.0, FlowKind::TokenTransfer) },
+    ];
+
+    let mut metrics = calculator.calculate_metrics(&trades).unwrap();
+    assert_eq!(metrics.roi, None);
+    calculator.apply_capital_flows(&mut metrics, &flows);
+
+    assert_eq!(metrics.total_profit_loss, 2.0);
+    assert_eq!((metrics.capital_in, metrics.capital_out), (10.0, 3.0));
+    assert_eq!(metrics.roi, Some(20.0));
+}
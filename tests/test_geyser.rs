@@ -0,0 +1,245 @@
+mod common;
+
+use common::{BONK, RAYDIUM};
+use futures::{Stream, StreamExt};
+use solana_sdk::{
+    instruction::{CompiledInstruction, InstructionError},
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::TransactionError,
+};
+use solana_wallet_analyzer::ingestion::{
+    balances::WRAPPED_SOL_MINT,
+    client::SolanaClient,
+    geyser::{
+        proto::geyser::{
+            geyser_server::{Geyser, GeyserServer},
+            subscribe_update::UpdateOneof,
+            SubscribeRequest, SubscribeUpdate, SubscribeUpdateBlockMeta, SubscribeUpdatePing,
+            SubscribeUpdateTransaction, SubscribeUpdateTransactionInfo,
+        },
+        proto::solana::storage::confirmed_block,
+        GeyserStream,
+    },
+    transaction::TransactionWithMeta,
+};
+use std::pin::Pin;
+use std::str::FromStr;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio::time::{timeout, Duration};
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::{Request, Response, Status, Streaming};
+
+/// Geyser update for a wallet buying 1,000 BONK for 0.5 SOL through Raydium.
+fn buy_update(wallet: &Pubkey, signature: Signature) -> SubscribeUpdateTransactionInfo {
+    let account_keys = [*wallet, Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::from_str(RAYDIUM).unwrap()];
+
+    SubscribeUpdateTransactionInfo {
+        signature: signature.as_ref().to_vec(),
+        is_vote: false,
+        transaction: Some(confirmed_block::Transaction {
+            signatures: vec![signature.as_ref().to_vec()],
+            message: Some(confirmed_block::Message {
+                header: Some(confirmed_block::MessageHeader {
+                    num_required_signatures: 1,
+                    num_readonly_signed_accounts: 0,
+                    num_readonly_unsigned_accounts: 1,
+                }),
+                account_keys: account_keys.iter().map(|k| k.to_bytes().to_vec()).collect(),
+                recent_blockhash: vec![0; 32],
+                instructions: vec![confirmed_block::CompiledInstruction {
+                    program_id_index: 3,
+                    accounts: vec![0, 1, 2],
+                    data: vec![9],
+                }],
+                versioned: false,
+                address_table_lookups: vec![],
+            }),
+        }),
+        meta: Some(confirmed_block::TransactionStatusMeta {
+            fee: 5_000,
+            pre_balances: vec![2_000_000_000, 0, 0, 1],
+            post_balances: vec![1_497_955_720, 2_039_280, 0, 1],
+            post_token_balances: vec![confirmed_block::TokenBalance {
+                account_index: 1,
+                mint: BONK.to_string(),
+                ui_token_amount: Some(confirmed_block::UiTokenAmount {
+                    ui_amount: 1_000.0,
+                    decimals: 5,
+                    amount: "100000000".to_string(),
+                    ui_amount_string: "1000".to_string(),
+                }),
+                owner: wallet.to_string(),
+                program_id: String::new(),
+            }],
+            ..Default::default()
+        }),
+        index: 0,
+    }
+}
+
+fn update(update: UpdateOneof) -> SubscribeUpdate {
+    SubscribeUpdate {
+        filters: vec!["dex".to_string()],
+        update_oneof: Some(update),
+    }
+}
+
+/// Serves a fixed sequence of updates to every subscriber and reports the
+/// requests it receives.
+struct MockGeyser {
+    updates: Vec<SubscribeUpdate>,
+    requests: mpsc::UnboundedSender<(Option<String>, SubscribeRequest)>,
+}
+
+#[tonic::async_trait]
+impl Geyser for MockGeyser {
+    type SubscribeStream = Pin<Box<dyn Stream<Item = Result<SubscribeUpdate, Status>> + Send>>;
+
+    async fn subscribe(
+        &self,
+        request: Request<Streaming<SubscribeRequest>>,
+    ) -> Result<Response<Self::SubscribeStream>, Status> {
+        let token = request.metadata().get("x-token")
+            .map(|token| token.to_str().unwrap().to_string());
+        let mut requests = request.into_inner();
+        let reporter = self.requests.clone();
+        tokio::spawn(async move {
+            while let Ok(Some(request)) = requests.message().await {
+                let _ = reporter.send((token.clone(), request));
+            }
+        });
+
+        // Keep the stream open after the fixture so the client does not reconnect
+        let updates = futures::stream::iter(self.updates.clone().into_iter().map(Ok))
+            .chain(futures::stream::pending());
+        Ok(Response::new(Box::pin(updates)))
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_geyser_stream_delivers_decodable_transactions() {
+    let wallet = Pubkey::new_unique();
+    let signature = Signature::new_unique();
+    let (requests, mut received_requests) = mpsc::unbounded_channel();
+    let mock = MockGeyser {
+        updates: vec![
+            update(UpdateOneof::Ping(SubscribeUpdatePing {})),
+            update(UpdateOneof::Transaction(SubscribeUpdateTransaction {
+                transaction: Some(buy_update(&wallet, signature)),
+                slot: 7,
+            })),
+            // Block time of the slot only follows its transactions
+            update(UpdateOneof::BlockMeta(SubscribeUpdateBlockMeta {
+                slot: 7,
+                blockhash: String::new(),
+                block_time: Some(confirmed_block::UnixTimestamp { timestamp: 1_700_000_000 }),
+            })),
+        ],
+        requests,
+    };
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(
+        tonic::transport::Server::builder()
+            .add_service(GeyserServer::new(mock))
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+
+    let client = SolanaClient::new("http://127.0.0.1:1").unwrap();
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let handle = GeyserStream::new(&endpoint, Some("secret".to_string()), client.dex_program_ids())
+        .spawn(sender);
+
+    let transaction = timeout(Duration::from_secs(10), receiver.recv()).await
+        .expect("timed out waiting for a transaction")
+        .unwrap();
+    assert_eq!(transaction.signature, signature.to_string());
+    assert_eq!(transaction.slot, 7);
+    assert_eq!(transaction.block_time, Some(1_700_000_000));
+
+    let trade = client.extract_trade_info(&transaction).unwrap();
+    assert_eq!(trade.timestamp, 1_700_000_000);
+    assert_eq!(trade.wallet_address, wallet.to_string());
+    assert_eq!(trade.token_in_mint, WRAPPED_SOL_MINT);
+    assert_eq!(trade.token_in_amount, 0.5);
+    assert_eq!(trade.token_out_mint, BONK);
+    assert_eq!(trade.token_out_amount, 1_000.0);
+
+    // Subscription filtered by the DEX programs, followed by a ping reply
+    let (token, subscribe) = received_requests.recv().await.unwrap();
+    assert_eq!(token.as_deref(), Some("secret"));
+    let filter = &subscribe.transactions["dex"];
+    assert_eq!(filter.failed, None);
+    assert!(filter.account_include.contains(&RAYDIUM.to_string()));
+    assert_eq!(subscribe.blocks_meta.len(), 1);
+    let (_, ping) = timeout(Duration::from_secs(10), received_requests.recv()).await.unwrap().unwrap();
+    assert!(ping.ping.is_some());
+
+    handle.abort();
+}
+
+#[test]
+fn test_geyser_update_conversion() {
+    let wallet = Pubkey::new_unique();
+    let mut info = buy_update(&wallet, Signature::new_unique());
+    let meta = info.meta.as_mut().unwrap();
+    meta.err = Some(confirmed_block::TransactionError {
+        err: bincode::serialize(&TransactionError::InstructionError(0, InstructionError::Custom(1))).unwrap(),
+    });
+    meta.inner_instructions = vec![confirmed_block::InnerInstructions {
+        index: 0,
+        instructions: vec![confirmed_block::InnerInstruction {
+            program_id_index: 2,
+            accounts: vec![1],
+            data: vec![3, 4],
+            stack_height: Some(2),
+        }],
+    }];
+
+    info.index = 12;
+
+    let transaction = TransactionWithMeta::try_from(info.clone()).unwrap();
+    assert_eq!(transaction.account_keys()[0], wallet);
+    assert_eq!(transaction.transaction_index, Some(12));
+    assert_eq!(transaction.meta.err.as_deref(), Some("Error processing Instruction 0: custom program error: 0x1"));
+    assert_eq!(transaction.meta.post_token_balances[0].owner, Some(wallet.to_string()));
+    assert_eq!(
+        transaction.instructions().collect::<Vec<_>>(),
+        vec![
+            &CompiledInstruction::new_from_raw_parts(3, vec![9], vec![0, 1, 2]),
+            &CompiledInstruction::new_from_raw_parts(2, vec![3, 4], vec![1]),
+        ],
+    );
+
+}
+
+#[test]
+fn test_geyser_v0_update_uses_loaded_addresses() {
+    let wallet = Pubkey::new_unique();
+    let loaded = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let mut info = buy_update(&wallet, Signature::new_unique());
+
+    let message = info.transaction.as_mut().unwrap().message.as_mut().unwrap();
+    message.versioned = true;
+    message.address_table_lookups = vec![confirmed_block::MessageAddressTableLookup {
+        account_key: Pubkey::new_unique().to_bytes().to_vec(),
+        writable_indexes: vec![3],
+        readonly_indexes: vec![7],
+    }];
+    let meta = info.meta.as_mut().unwrap();
+    meta.loaded_writable_addresses = vec![loaded[0].to_bytes().to_vec()];
+    meta.loaded_readonly_addresses = vec![loaded[1].to_bytes().to_vec()];
+
+    let transaction = TransactionWithMeta::try_from(info).unwrap();
+
+    // Loaded addresses follow the static keys, writable first
+    assert!(!transaction.has_unresolved_lookups());
+    assert_eq!(transaction.account_keys().len(), 6);
+    assert_eq!(transaction.account_keys().get(4), Some(&loaded[0]));
+    assert_eq!(transaction.account_keys().get(5), Some(&loaded[1]));
+    let trade = SolanaClient::offline(Default::default()).extract_trade_info(&transaction).unwrap();
+    assert_eq!(trade.venue, "raydium-amm-v4");
+}
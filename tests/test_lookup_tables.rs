@@ -0,0 +1,175 @@
+mod common;
+
+use common::{bonk_balance, rpc_result, MockRpc, RAYDIUM};
+use serde_json::{json, Value};
+use solana_sdk::{
+    address_lookup_table::state::{AddressLookupTable, LookupTableMeta},
+    bs58,
+    hash::Hash,
+    instruction::CompiledInstruction,
+    message::{v0, MessageHeader, VersionedMessage},
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::VersionedTransaction,
+};
+use solana_wallet_analyzer::ingestion::{
+    client::{ClientConfig, SolanaClient},
+    decoders::DecoderRegistry,
+    lookup_tables::parse_lookup_table,
+};
+use std::borrow::Cow;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::time::Duration;
+
+fn lookup_table_data(addresses: &[Pubkey]) -> Vec<u8> {
+    AddressLookupTable {
+        meta: LookupTableMeta::default(),
+        addresses: Cow::Borrowed(addresses),
+    }.serialize_for_tests().unwrap()
+}
+
+/// `getTransaction` result of a v0 buy routed through an unknown program
+/// that calls Raydium, with the pool and the Raydium program loaded from
+/// `table`, as returned by a node that omits `loadedAddresses`.
+fn v0_buy(signature: &Signature, wallet: &Pubkey, table: &Pubkey) -> Value {
+    let message = v0::Message {
+        header: MessageHeader {
+            num_required_signatures: 1,
+            num_readonly_signed_accounts: 0,
+            num_readonly_unsigned_accounts: 1,
+        },
+        account_keys: vec![*wallet, Pubkey::new_unique(), Pubkey::new_unique()],
+        recent_blockhash: Hash::default(),
+        instructions: vec![CompiledInstruction::new_from_raw_parts(2, vec![1], vec![0, 1, 3, 4])],
+        address_table_lookups: vec![v0::MessageAddressTableLookup {
+            account_key: *table,
+            writable_indexes: vec![0],
+            readonly_indexes: vec![1],
+        }],
+    };
+    let transaction = VersionedTransaction {
+        signatures: vec![*signature],
+        message: VersionedMessage::V0(message),
+    };
+
+    json!({
+        "slot": 1,
+        "blockTime": null,
+        "version": 0,
+        "transaction": [bs58::encode(bincode::serialize(&transaction).unwrap()).into_string(), "base58"],
+        "meta": {
+            "err": null,
+            "status": { "Ok": null },
+            "fee": 5_000,
+            "preBalances": [2_000_000_000u64, 0, 1, 0, 1],
+            "postBalances": [1_497_955_720u64, 2_039_280, 1, 0, 1],
+            "innerInstructions": [{
+                "index": 0,
+                "instructions": [{ "programIdIndex": 4, "accounts": [0, 1, 3], "data": bs58::encode([9]).into_string() }],
+            }],
+            "preTokenBalances": [],
+            "postTokenBalances": [bonk_balance(wallet, 100_000_000)],
+        },
+    })
+}
+
+struct Chain {
+    wallet: Pubkey,
+    table: Pubkey,
+    table_data: Option<Vec<u8>>,
+    table_fetches: Arc<AtomicUsize>,
+}
+
+impl Chain {
+    fn respond(&self, request: &Value) -> hyper::Response<hyper::Body> {
+        match request["method"].as_str().unwrap() {
+            "getTransaction" => {
+                let signature = Signature::from_str(request["params"][0].as_str().unwrap()).unwrap();
+                rpc_result(request, v0_buy(&signature, &self.wallet, &self.table))
+            }
+            "getMultipleAccounts" => {
+                self.table_fetches.fetch_add(1, Ordering::SeqCst);
+                assert_eq!(request["params"][0], json!([self.table.to_string()]));
+                let account = self.table_data.as_ref().map(|data| json!({
+                    "lamports": 1_000_000,
+                    "data": [bs58::encode(data).into_string(), "base58"],
+                    "owner": "AddressLookupTab1e1111111111111111111111111",
+                    "executable": false,
+                    "rentEpoch": 0,
+                    "space": data.len(),
+                }));
+                rpc_result(request, json!({ "context": { "slot": 1 }, "value": [account] }))
+            }
+            method => panic!("unexpected method {}", method),
+        }
+    }
+}
+
+async fn start(table_data: impl FnOnce(&Pubkey) -> Option<Vec<u8>>) -> (MockRpc, Pubkey, Arc<AtomicUsize>) {
+    let wallet = Pubkey::new_unique();
+    let table = Pubkey::new_unique();
+    let table_fetches = Arc::new(AtomicUsize::new(0));
+    let chain = Chain {
+        wallet,
+        table,
+        table_data: table_data(&wallet),
+        table_fetches: Arc::clone(&table_fetches),
+    };
+    let rpc = MockRpc::start(Duration::ZERO, move |request| chain.respond(request)).await;
+    (rpc, wallet, table_fetches)
+}
+
+fn client(url: &str) -> SolanaClient {
+    SolanaClient::with_config(url, DecoderRegistry::default(), ClientConfig {
+        requests_per_second: 0.0,
+        ..ClientConfig::default()
+    }).unwrap()
+}
+
+fn signatures(count: usize) -> Vec<String> {
+    (0..count).map(|_| Signature::new_unique().to_string()).collect()
+}
+
+#[tokio::test]
+async fn test_lookup_tables_are_fetched_once_and_resolved() {
+    let (rpc, wallet, table_fetches) = start(|_| {
+        Some(lookup_table_data(&[Pubkey::new_unique(), Pubkey::from_str(RAYDIUM).unwrap()]))
+    }).await;
+    let client = client(&rpc.url);
+
+    let transactions = client.fetch_transactions(&signatures(2)).await.unwrap();
+    assert_eq!(transactions.len(), 2);
+    assert_eq!(table_fetches.load(Ordering::SeqCst), 1);
+
+    for tx in &transactions {
+        assert!(!tx.has_unresolved_lookups());
+        let trade = client.extract_trade_info(tx).unwrap();
+        assert_eq!(trade.wallet_address, wallet.to_string());
+        assert_eq!(trade.venue, "raydium-amm-v4");
+    }
+
+    // Later batches are resolved from the cache
+    let transactions = client.fetch_transactions(&signatures(1)).await.unwrap();
+    assert_eq!(transactions.len(), 1);
+    assert_eq!(table_fetches.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_transactions_with_missing_tables_are_dropped() {
+    let (rpc, _, table_fetches) = start(|_| None).await;
+
+    let transactions = client(&rpc.url).fetch_transactions(&signatures(1)).await.unwrap();
+
+    assert!(transactions.is_empty());
+    assert_eq!(table_fetches.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_parse_lookup_table() {
+    let addresses = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+
+    assert_eq!(parse_lookup_table(&lookup_table_data(&addresses)).unwrap(), addresses);
+    assert!(parse_lookup_table(&[1, 2, 3]).is_err());
+}
@@ -1,6 +1,7 @@
 use solana_wallet_analyzer::{
     types::TradeInfo,
     analysis::metrics::MetricsCalculator,
+    ingestion::balances::WRAPPED_SOL_MINT,
 };
 
 #[test]
@@ -12,6 +13,7 @@ fn test_metrics_calculation() {
             amount: 100.0,
             profit_loss: 10.0,
             transaction_hash: "hash1".to_string(),
+            ..Default::default()
         },
         TradeInfo {
             wallet_address: "test_wallet".to_string(),
@@ -19,6 +21,7 @@ fn test_metrics_calculation() {
             amount: 200.0,
             profit_loss: -5.0,
             transaction_hash: "hash2".to_string(),
+            ..Default::default()
         },
     ];
 
@@ -30,4 +33,57 @@ fn test_metrics_calculation() {
     assert_eq!(metrics.win_rate, 50.0);
     assert_eq!(metrics.avg_trade_size, 150.0);
     assert_eq!(metrics.trade_count, 2);
-}
\ No newline at end of file
+}
+
+fn swap(token_in: (&str, f64), token_out: (&str, f64)) -> TradeInfo {
+    TradeInfo {
+        wallet_address: "test_wallet".to_string(),
+        token_in_mint: token_in.0.to_string(),
+        token_in_amount: token_in.1,
+        token_out_mint: token_out.0.to_string(),
+        token_out_amount: token_out.1,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_realized_pnl_uses_average_cost() {
+    let mut trades = vec![
+        swap((WRAPPED_SOL_MINT, 1.0), ("BONK", 100.0)),
+        swap((WRAPPED_SOL_MINT, 3.0), ("BONK", 100.0)),
+        swap(("BONK", 100.0), (WRAPPED_SOL_MINT, 2.5)),
+        swap(("WIF", 10.0), (WRAPPED_SOL_MINT, 1.0)),
+    ];
+
+    let calculator = MetricsCalculator::new();
+    calculator.apply_realized_pnl(&mut trades);
+
+    assert_eq!(trades[0].profit_loss, 0.0);
+    assert_eq!(trades[1].profit_loss, 0.0);
+    // 100 of 200 BONK bought for 4 SOL in total, sold for 2.5 SOL
+    assert_eq!(trades[2].profit_loss, 0.5);
+    // No known cost basis for WIF
+    assert_eq!(trades[3].profit_loss, 0.0);
+}
+
+#[test]
+fn test_same_second_trades_order_by_slot_and_position() {
+    let at = |slot: u64, index: u32, trade: TradeInfo| TradeInfo {
+        timestamp: 1_700_000_000,
+        slot,
+        transaction_index: Some(index),
+        ..trade
+    };
+    let mut trades = vec![
+        at(11, 0, swap(("BONK", 100.0), (WRAPPED_SOL_MINT, 1.5))),
+        at(10, 4, swap((WRAPPED_SOL_MINT, 0.5), ("BONK", 50.0))),
+        at(10, 2, swap((WRAPPED_SOL_MINT, 0.5), ("BONK", 50.0))),
+    ];
+    trades.sort_by_key(TradeInfo::chronological_key);
+    assert_eq!(trades.iter().map(|t| (t.slot, t.transaction_index)).collect::<Vec<_>>(),
+        vec![(10, Some(2)), (10, Some(4)), (11, Some(0))]);
+
+    // Both buys precede the sell, so it realizes against their cost
+    MetricsCalculator::new().apply_realized_pnl(&mut trades);
+    assert!((trades[2].profit_loss - 0.5).abs() < 1e-9);
+}
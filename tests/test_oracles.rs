@@ -0,0 +1,247 @@
+mod common;
+
+use common::{rpc_result, MockRpc, BONK};
+use serde_json::{json, Value};
+use solana_sdk::{bs58, pubkey::Pubkey};
+use solana_wallet_analyzer::{
+    analysis::pricing::{PriceHistory, PriceOracle, PriceObservation, PriceSource, USDC_MINT},
+    ingestion::{
+        balances::WRAPPED_SOL_MINT,
+        client::{ClientConfig, SolanaClient},
+        decoders::DecoderRegistry,
+        oracles::{
+            anchor_discriminator, parse_price_account, read_price_dump, OracleFeed,
+            PYTH_PROGRAM_ID, PYTH_RECEIVER_PROGRAM_ID, SWITCHBOARD_V2_PROGRAM_ID,
+        },
+    },
+    types::{OraclePrice, OracleSource},
+};
+use std::io::Cursor;
+use std::str::FromStr;
+use tokio::time::Duration;
+
+fn write(data: &mut [u8], offset: usize, bytes: &[u8]) {
+    data[offset..offset + bytes.len()].copy_from_slice(bytes);
+}
+
+/// A Pyth push oracle price account with the given aggregate.
+fn pyth_price(price: i64, conf: u64, exponent: i32, publish_time: i64, status: u32) -> Vec<u8> {
+    let mut data = vec![0; 3312];
+    write(&mut data, 0, &0xa1b2c3d4u32.to_le_bytes());
+    write(&mut data, 4, &2u32.to_le_bytes());
+    write(&mut data, 8, &3u32.to_le_bytes());
+    write(&mut data, 20, &exponent.to_le_bytes());
+    write(&mut data, 96, &publish_time.to_le_bytes());
+    write(&mut data, 208, &price.to_le_bytes());
+    write(&mut data, 216, &conf.to_le_bytes());
+    write(&mut data, 224, &status.to_le_bytes());
+    data
+}
+
+/// A Pyth pull oracle `PriceUpdateV2` account.
+fn pyth_price_update(price: i64, conf: u64, exponent: i32, publish_time: i64, fully_verified: bool) -> Vec<u8> {
+    let mut data = anchor_discriminator("PriceUpdateV2").to_vec();
+    data.extend_from_slice(Pubkey::new_unique().as_ref());
+    match fully_verified {
+        true => data.push(1),
+        false => data.extend_from_slice(&[0, 5]),
+    }
+    data.extend_from_slice(&[7; 32]);
+    data.extend_from_slice(&price.to_le_bytes());
+    data.extend_from_slice(&conf.to_le_bytes());
+    data.extend_from_slice(&exponent.to_le_bytes());
+    data.extend_from_slice(&publish_time.to_le_bytes());
+    data.extend_from_slice(&[0; 8 + 8 + 8 + 8]);
+    data
+}
+
+/// A Switchboard v2 aggregator whose latest confirmed round has the given
+/// result and standard deviation, as `(mantissa, scale)`.
+fn switchboard_aggregator(result: (i128, u32), std_deviation: (i128, u32), round_open_timestamp: i64, num_success: u32) -> Vec<u8> {
+    let mut data = vec![0; 3851];
+    write(&mut data, 0, &anchor_discriminator("AggregatorAccountData"));
+    write(&mut data, 341, &num_success.to_le_bytes());
+    write(&mut data, 358, &round_open_timestamp.to_le_bytes());
+    write(&mut data, 366, &result.0.to_le_bytes());
+    write(&mut data, 382, &result.1.to_le_bytes());
+    write(&mut data, 386, &std_deviation.0.to_le_bytes());
+    write(&mut data, 402, &std_deviation.1.to_le_bytes());
+    data
+}
+
+#[test]
+fn test_pyth_price_accounts() {
+    let mint = Pubkey::from_str(WRAPPED_SOL_MINT).unwrap();
+
+    let price = parse_price_account(&mint, &PYTH_PROGRAM_ID, &pyth_price(15_000_000_000, 7_500_000, -8, 1_700_000_000, 1)).unwrap();
+    assert_eq!(price, OraclePrice {
+        mint: WRAPPED_SOL_MINT.to_string(),
+        source: OracleSource::Pyth,
+        price: 150.0,
+        confidence: 0.075,
+        publish_time: 1_700_000_000,
+    });
+
+    // Halted
+    assert!(parse_price_account(&mint, &PYTH_PROGRAM_ID, &pyth_price(15_000_000_000, 0, -8, 0, 2)).is_err());
+    assert!(parse_price_account(&mint, &PYTH_PROGRAM_ID, &[0; 240]).is_err());
+    assert!(parse_price_account(&mint, &PYTH_PROGRAM_ID, &pyth_price(1, 0, 0, 0, 1)[..100]).is_err());
+}
+
+#[test]
+fn test_pyth_price_updates() {
+    let mint = Pubkey::from_str(BONK).unwrap();
+
+    let price = parse_price_account(&mint, &PYTH_RECEIVER_PROGRAM_ID, &pyth_price_update(2_500, 5, -8, 1_700_000_100, true)).unwrap();
+    assert_eq!((price.price, price.confidence, price.publish_time), (2.5e-5, 5e-8, 1_700_000_100));
+    assert_eq!(price.mint, BONK);
+
+    assert!(parse_price_account(&mint, &PYTH_RECEIVER_PROGRAM_ID, &pyth_price_update(2_500, 5, -8, 0, false)).is_err());
+    assert!(parse_price_account(&mint, &PYTH_RECEIVER_PROGRAM_ID, &pyth_price(1, 0, 0, 0, 1)).is_err());
+}
+
+#[test]
+fn test_switchboard_aggregators() {
+    let mint = Pubkey::from_str(WRAPPED_SOL_MINT).unwrap();
+
+    let price = parse_price_account(&mint, &SWITCHBOARD_V2_PROGRAM_ID, &switchboard_aggregator((1505, 1), (25, 2), 1_700_000_200, 3)).unwrap();
+    assert_eq!(price.source, OracleSource::Switchboard);
+    assert_eq!((price.price, price.confidence, price.publish_time), (150.5, 0.25, 1_700_000_200));
+
+    // No round confirmed yet
+    assert!(parse_price_account(&mint, &SWITCHBOARD_V2_PROGRAM_ID, &switchboard_aggregator((1505, 1), (0, 0), 0, 0)).is_err());
+    assert!(parse_price_account(&mint, &Pubkey::new_unique(), &switchboard_aggregator((1505, 1), (0, 0), 0, 3)).is_err());
+}
+
+#[test]
+fn test_oracle_feed_arguments() {
+    let account = Pubkey::new_unique();
+    let feed: OracleFeed = format!("{}={}", WRAPPED_SOL_MINT, account).parse().unwrap();
+    assert_eq!(feed.mint.to_string(), WRAPPED_SOL_MINT);
+    assert_eq!(feed.account, account);
+
+    assert!(WRAPPED_SOL_MINT.parse::<OracleFeed>().is_err());
+    assert!(format!("{}=nope", WRAPPED_SOL_MINT).parse::<OracleFeed>().is_err());
+}
+
+fn account(owner: &Pubkey, data: &[u8]) -> Value {
+    json!({
+        "data": [bs58::encode(data).into_string(), "base58"],
+        "executable": false,
+        "lamports": 1_461_600,
+        "owner": owner.to_string(),
+        "rentEpoch": 0,
+        "space": data.len(),
+    })
+}
+
+#[test]
+fn test_price_dumps_read_feed_accounts() {
+    let sol = OracleFeed {
+        mint: Pubkey::from_str(WRAPPED_SOL_MINT).unwrap(),
+        account: Pubkey::new_unique(),
+    };
+    let lines = [
+        json!({ "pubkey": sol.account.to_string(), "account": account(&PYTH_PROGRAM_ID, &pyth_price(15_000_000_000, 0, -8, 1_700_000_000, 1)) }).to_string(),
+        String::new(),
+        // Not a configured feed
+        json!({ "pubkey": Pubkey::new_unique().to_string(), "account": account(&PYTH_PROGRAM_ID, &[]) }).to_string(),
+        "{ not json".to_string(),
+        json!({ "pubkey": sol.account.to_string(), "account": account(&PYTH_PROGRAM_ID, &pyth_price(1, 0, 0, 0, 0)) }).to_string(),
+    ];
+
+    let prices = read_price_dump(Cursor::new(lines.join("\n")), &[sol]);
+
+    assert_eq!(prices.len(), 3);
+    assert_eq!(prices[0].as_ref().unwrap().price, 150.0);
+    assert!(prices[1].as_ref().unwrap_err().to_string().contains("line 4"));
+    assert!(prices[2].as_ref().unwrap_err().to_string().contains("line 5"));
+}
+
+#[tokio::test]
+async fn test_fetch_oracle_prices() {
+    let feed = OracleFeed {
+        mint: Pubkey::from_str(WRAPPED_SOL_MINT).unwrap(),
+        account: Pubkey::new_unique(),
+    };
+    let missing = OracleFeed {
+        mint: Pubkey::from_str(BONK).unwrap(),
+        account: Pubkey::new_unique(),
+    };
+    let rpc = MockRpc::start(Duration::ZERO, move |request: &Value| {
+        assert_eq!(request["method"], "getMultipleAccounts");
+        assert_eq!(request["params"][0], json!([feed.account.to_string(), missing.account.to_string()]));
+        rpc_result(request, json!({
+            "context": { "slot": 1 },
+            "value": [account(&SWITCHBOARD_V2_PROGRAM_ID, &switchboard_aggregator((150, 0), (1, 1), 1_700_000_000, 1)), null],
+        }))
+    }).await;
+    let client = SolanaClient::with_config(&rpc.url, DecoderRegistry::default(), ClientConfig {
+        requests_per_second: 0.0,
+        ..ClientConfig::default()
+    }).unwrap();
+
+    let prices = client.fetch_oracle_prices(&[feed, missing]).await.unwrap();
+
+    assert_eq!(prices.len(), 1);
+    assert_eq!((prices[0].mint.as_str(), prices[0].price), (WRAPPED_SOL_MINT, 150.0));
+}
+
+fn quote(mint: &str, price: f64, confidence: f64, publish_time: i64) -> OraclePrice {
+    OraclePrice {
+        mint: mint.to_string(),
+        source: OracleSource::Pyth,
+        price,
+        confidence,
+        publish_time,
+    }
+}
+
+#[test]
+fn test_confident_oracle_prices_are_preferred() {
+    let mut prices = PriceHistory::new(600);
+    prices.insert(PriceOracle::default().candles(vec![
+        // Swaps imply 0.0002 SOL per BONK and 100 USD per SOL
+        PriceObservation { mint: BONK.to_string(), transaction_hash: String::new(), timestamp: 950, price: 0.0002, volume: 1.0 },
+        PriceObservation { mint: USDC_MINT.to_string(), transaction_hash: String::new(), timestamp: 950, price: 0.01, volume: 1.0 },
+    ]));
+    prices.insert_oracle_prices([
+        quote(WRAPPED_SOL_MINT, 150.0, 0.1, 900),
+        quote(BONK, 0.000_03, 0.000_000_1, 900),
+        // Too uncertain to trust
+        quote(BONK, 0.000_06, 0.000_01, 2000),
+    ]);
+
+    assert_eq!(prices.sol_usd(1000), Some(150.0));
+    assert_eq!(prices.price(BONK, 1000), Some(0.000_03 / 150.0));
+    // The confident oracle prices are too old by then, and the newer BONK one
+    // too uncertain, so swaps price both
+    assert_eq!(prices.sol_usd(1520), Some(100.0));
+    assert_eq!(prices.price(BONK, 1520), Some(0.0002));
+}
+
+#[test]
+fn test_oracle_prices_published_later_are_not_used() {
+    let mut prices = PriceHistory::new(600);
+    prices.insert_oracle_prices([
+        quote(BONK, 0.000_03, 0.0, 900),
+        quote(BONK, 0.000_04, 0.0, 1010),
+    ]);
+
+    assert_eq!(prices.oracle_usd(BONK, 899), None);
+    assert_eq!(prices.oracle_usd(BONK, 1000), Some(0.000_03));
+    assert_eq!(prices.oracle_usd(BONK, 1010), Some(0.000_04));
+}
+
+#[test]
+fn test_oracle_prices_need_sol_usd() {
+    let mut prices = PriceHistory::new(600);
+    prices.insert_oracle_prices([quote(BONK, 0.000_03, 0.0, 1000)]);
+
+    assert_eq!(prices.oracle_usd(BONK, 1000), Some(0.000_03));
+    assert_eq!(prices.price(BONK, 1000), None);
+
+    let mut strict = PriceHistory::new(600).with_max_confidence(0.0);
+    strict.insert_oracle_prices([quote(WRAPPED_SOL_MINT, 150.0, 0.1, 1000)]);
+    assert_eq!(strict.sol_usd(1000), None);
+}
@@ -0,0 +1,89 @@
+mod common;
+
+use common::{encoded_transaction, http_error, rpc_result, MockRpc};
+use hyper::StatusCode;
+use serde_json::Value;
+use solana_sdk::signature::Signature;
+use solana_wallet_analyzer::ingestion::{
+    client::{ClientConfig, SolanaClient},
+    decoders::DecoderRegistry,
+    pool::RpcEndpoint,
+};
+use std::str::FromStr;
+use tokio::time::Duration;
+
+fn get_transaction(request: &Value) -> hyper::Response<hyper::Body> {
+    let signature = Signature::from_str(request["params"][0].as_str().unwrap()).unwrap();
+    rpc_result(request, encoded_transaction(&signature, 1))
+}
+
+/// One request at a time, so endpoint selection is deterministic.
+fn sequential() -> ClientConfig {
+    ClientConfig {
+        max_concurrent_requests: 1,
+        requests_per_second: 0.0,
+        burst: 1,
+        ..ClientConfig::default()
+    }
+}
+
+fn signatures(count: usize) -> Vec<String> {
+    (0..count).map(|_| Signature::new_unique().to_string()).collect()
+}
+
+#[test]
+fn test_parse_weighted_endpoint() {
+    assert_eq!(
+        RpcEndpoint::from_str("https://rpc.example.com/?api-key=secret#3").unwrap(),
+        RpcEndpoint { url: "https://rpc.example.com/?api-key=secret".to_string(), weight: 3 },
+    );
+    assert_eq!(RpcEndpoint::from_str("http://127.0.0.1:8899").unwrap().weight, 1);
+    assert!(RpcEndpoint::from_str("http://127.0.0.1:8899#0").is_err());
+    assert_eq!(RpcEndpoint::new("https://rpc.example.com/?api-key=secret").label(), "https://rpc.example.com/");
+}
+
+#[tokio::test]
+async fn test_requests_are_split_by_weight() {
+    let heavy = MockRpc::start(Duration::ZERO, get_transaction).await;
+    let light = MockRpc::start(Duration::ZERO, get_transaction).await;
+    let client = SolanaClient::with_endpoints(
+        vec![
+            RpcEndpoint { url: heavy.url.clone(), weight: 3 },
+            RpcEndpoint { url: light.url.clone(), weight: 1 },
+        ],
+        DecoderRegistry::default(),
+        sequential(),
+    ).unwrap();
+
+    let transactions = client.fetch_transactions(&signatures(8)).await.unwrap();
+
+    assert_eq!(transactions.len(), 8);
+    assert_eq!(heavy.request_count(), 6);
+    assert_eq!(light.request_count(), 2);
+}
+
+#[tokio::test]
+async fn test_failing_endpoint_fails_over_and_is_ejected() {
+    let failing = MockRpc::start(Duration::ZERO, |_| http_error(StatusCode::SERVICE_UNAVAILABLE)).await;
+    let healthy = MockRpc::start(Duration::ZERO, get_transaction).await;
+    let client = SolanaClient::with_endpoints(
+        vec![RpcEndpoint::new(&failing.url), RpcEndpoint::new(&healthy.url)],
+        DecoderRegistry::default(),
+        sequential(),
+    ).unwrap();
+
+    let signatures = signatures(10);
+    let transactions = client.fetch_transactions(&signatures).await.unwrap();
+
+    // Nothing is lost, and the failing endpoint stops receiving traffic once ejected
+    assert_eq!(transactions.len(), 10);
+    assert_eq!(failing.request_count(), 3);
+    assert_eq!(healthy.request_count(), 10);
+
+    let stats = client.endpoint_stats();
+    assert_eq!(stats[0].failures, 3);
+    assert!(!stats[0].healthy);
+    assert_eq!(stats[1].failures, 0);
+    assert_eq!(stats[1].requests, 10);
+    assert!(stats[1].healthy);
+}
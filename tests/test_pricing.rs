@@ -0,0 +1,226 @@
+mod common;
+
+use common::{MockRedis, BONK};
+use solana_wallet_analyzer::{
+    analysis::{
+        metrics::MetricsCalculator,
+        pricing::{observations, PriceHistory, PriceObservation, PriceOracle, PriceSource, USDC_MINT},
+    },
+    ingestion::balances::WRAPPED_SOL_MINT,
+    storage::redis::RedisStorage,
+    types::{Candle, TradeInfo},
+};
+
+const WIF: &str = "EKpQGSJtjMFqKZ9KQanSqYXRcF8fBopzLHYxdM65zcjm";
+
+fn swap(timestamp: i64, token_in: (&str, f64), token_out: (&str, f64)) -> TradeInfo {
+    let amount = if token_in.0 == WRAPPED_SOL_MINT {
+        token_in.1
+    } else if token_out.0 == WRAPPED_SOL_MINT {
+        token_out.1
+    } else {
+        0.0
+    };
+    TradeInfo {
+        wallet_address: "test_wallet".to_string(),
+        timestamp,
+        amount,
+        token_in_mint: token_in.0.to_string(),
+        token_in_amount: token_in.1,
+        token_out_mint: token_out.0.to_string(),
+        token_out_amount: token_out.1,
+        ..Default::default()
+    }
+}
+
+fn observation(timestamp: i64, price: f64, volume: f64) -> PriceObservation {
+    PriceObservation {
+        mint: BONK.to_string(),
+        transaction_hash: String::new(),
+        timestamp,
+        price,
+        volume,
+    }
+}
+
+#[test]
+fn test_swaps_against_sol_imply_prices() {
+    let failed = TradeInfo {
+        failed: true,
+        ..swap(4, (WRAPPED_SOL_MINT, 1.0), (BONK, 50.0))
+    };
+    let trades = vec![
+        swap(1, (WRAPPED_SOL_MINT, 2.0), (BONK, 1000.0)),
+        swap(2, (BONK, 500.0), (WRAPPED_SOL_MINT, 0.9)),
+        swap(3, (BONK, 500.0), (WIF, 10.0)),
+        failed,
+    ];
+
+    assert_eq!(observations(&trades), vec![
+        observation(1, 0.002, 2.0),
+        observation(2, 0.0018, 0.9),
+    ]);
+}
+
+#[test]
+fn test_outliers_are_filtered_by_volume_weighted_median() {
+    let oracle = PriceOracle::default();
+    let kept = oracle.filter_outliers(vec![
+        observation(61, 1.0, 10.0),
+        observation(62, 1.05, 5.0),
+        // A thin trade against a drained pool
+        observation(63, 3.0, 0.5),
+        // Dust
+        observation(64, 1.0, 0.001),
+        // A different interval with its own median
+        observation(121, 3.0, 0.5),
+    ]);
+
+    let kept: Vec<_> = kept.iter().map(|o| (o.timestamp, o.price)).collect();
+    assert_eq!(kept, vec![(61, 1.0), (62, 1.05), (121, 3.0)]);
+}
+
+#[test]
+fn test_candles_aggregate_open_high_low_close() {
+    let oracle = PriceOracle::default();
+    let candles = oracle.candles(vec![
+        observation(70, 1.1, 1.0),
+        observation(61, 1.0, 2.0),
+        observation(100, 0.95, 1.0),
+        observation(119, 1.05, 1.0),
+        observation(130, 1.2, 1.0),
+    ]);
+
+    assert_eq!(candles.len(), 2);
+    assert_eq!(candles[0], Candle {
+        mint: BONK.to_string(),
+        start: 60,
+        open: 1.0,
+        high: 1.1,
+        low: 0.95,
+        close: 1.05,
+        volume: 5.0,
+        trade_count: 4,
+        first_trade: 61,
+        last_trade: 119,
+    });
+    assert_eq!((candles[1].start, candles[1].open, candles[1].close), (120, 1.2, 1.2));
+}
+
+#[test]
+fn test_candles_merge_out_of_order() {
+    let oracle = PriceOracle::default();
+    let mut later = oracle.candles(vec![observation(90, 2.0, 1.0), observation(100, 2.1, 1.0)]).remove(0);
+    let earlier = oracle.candles(vec![observation(65, 1.9, 1.0), observation(80, 2.2, 1.0)]).remove(0);
+
+    later.merge(&earlier);
+
+    assert_eq!((later.open, later.high, later.low, later.close), (1.9, 2.2, 1.9, 2.1));
+    assert_eq!((later.first_trade, later.last_trade, later.trade_count), (65, 100, 4));
+}
+
+#[tokio::test]
+async fn test_stored_candles_count_each_swap_once() {
+    let redis = MockRedis::start();
+    let storage = RedisStorage::new(&redis.url).unwrap();
+    let oracle = PriceOracle::default();
+    let priced = |transaction_hash: &str, timestamp: i64, price: f64| PriceObservation {
+        transaction_hash: transaction_hash.to_string(),
+        ..observation(timestamp, price, 1.0)
+    };
+    let candle = || async { storage.get_candle_at(BONK, 100).await.unwrap() };
+
+    storage.store_price_observations(&[priced("a", 61, 1.0), priced("b", 70, 1.1)], &oracle).await.unwrap();
+    // Reprocessing a swap replaces its observation
+    storage.store_price_observations(&[priced("b", 70, 1.1), priced("c", 80, 1.05)], &oracle).await.unwrap();
+    let stored = candle().await.unwrap();
+    assert_eq!((stored.volume, stored.trade_count, stored.open, stored.close), (3.0, 3, 1.0, 1.05));
+
+    // Rolled back swaps are taken out again
+    storage.remove_price_observations(&[priced("c", 80, 1.05)], &oracle).await.unwrap();
+    let stored = candle().await.unwrap();
+    assert_eq!((stored.volume, stored.trade_count, stored.close), (2.0, 2, 1.1));
+
+    storage.remove_price_observations(&[priced("a", 61, 1.0), priced("b", 70, 1.1)], &oracle).await.unwrap();
+    assert_eq!(candle().await, None);
+}
+
+#[test]
+fn test_price_at_time_uses_latest_candle_until_stale() {
+    let oracle = PriceOracle::default();
+    let mut prices = PriceHistory::new(600);
+    prices.insert(oracle.candles(vec![
+        observation(61, 1.0, 1.0),
+        observation(185, 1.5, 1.0),
+    ]));
+
+    assert_eq!(prices.price(BONK, 30), None);
+    assert_eq!(prices.price(BONK, 61), Some(1.0));
+    assert_eq!(prices.price(BONK, 179), Some(1.0));
+    assert_eq!(prices.price(BONK, 200), Some(1.5));
+    assert_eq!(prices.price(BONK, 785), Some(1.5));
+    assert_eq!(prices.price(BONK, 786), None);
+    assert_eq!(prices.price(WIF, 100), None);
+    assert_eq!(prices.price(WRAPPED_SOL_MINT, 100), Some(1.0));
+}
+
+#[test]
+fn test_sol_usd_from_usdc_swaps() {
+    let oracle = PriceOracle::default();
+    let mut prices = PriceHistory::new(600);
+    prices.insert(oracle.candles(observations(&[
+        swap(100, (WRAPPED_SOL_MINT, 2.0), (USDC_MINT, 300.0)),
+    ])));
+
+    assert_eq!(prices.sol_usd(100), Some(150.0));
+}
+
+#[test]
+fn test_token_to_token_swaps_are_valued_and_realized() {
+    let oracle = PriceOracle::default();
+    let mut prices = PriceHistory::new(600);
+    prices.insert(oracle.candles(observations(&[
+        swap(100, (WRAPPED_SOL_MINT, 3.0), (USDC_MINT, 300.0)),
+        swap(160, (WRAPPED_SOL_MINT, 1.0), (WIF, 10.0)),
+    ])));
+
+    let mut trades = vec![
+        swap(100, (WRAPPED_SOL_MINT, 1.0), (BONK, 1000.0)),
+        // BONK is unpriced, so the swap is valued at WIF's price: 20 WIF at 0.1 SOL
+        swap(170, (BONK, 1000.0), (WIF, 20.0)),
+        swap(170, (WIF, 20.0), (WRAPPED_SOL_MINT, 2.5)),
+    ];
+
+    let calculator = MetricsCalculator::new();
+    calculator.value_trades(&mut trades, &prices);
+    calculator.apply_realized_pnl(&mut trades);
+
+    assert_eq!(trades[1].amount, 2.0);
+    assert!(trades.iter().all(|trade| trade.sol_usd_price == Some(100.0)));
+    // BONK bought for 1 SOL and swapped into 2 SOL of WIF, which sold for 2.5 SOL
+    assert_eq!(trades[1].profit_loss, 1.0);
+    assert_eq!(trades[2].profit_loss, 0.5);
+
+    let metrics = calculator.calculate_metrics(&trades).unwrap();
+    assert_eq!(metrics.net_profit_loss_usd, Some(150.0));
+}
+
+#[test]
+fn test_usd_profit_loss_needs_every_trade_priced() {
+    let trades = vec![
+        TradeInfo {
+            profit_loss: 1.0,
+            sol_usd_price: Some(100.0),
+            ..Default::default()
+        },
+        TradeInfo {
+            profit_loss: 1.0,
+            ..Default::default()
+        },
+    ];
+
+    let metrics = MetricsCalculator::new().calculate_metrics(&trades).unwrap();
+
+    assert_eq!(metrics.net_profit_loss, 2.0);
+    assert_eq!(metrics.net_profit_loss_usd, None);
+}
@@ -13,6 +13,7 @@ fn test_wallet_ranking() {
             avg_trade_size: 500.0,
             trade_count: 10,
             last_updated: 1000,
+            ..Default::default()
         },
         WalletMetrics {
             address: "wallet2".to_string(),
@@ -21,6 +22,7 @@ fn test_wallet_ranking() {
             avg_trade_size: 1000.0,
             trade_count: 5,
             last_updated: 1000,
+            ..Default::default()
         },
     ];
 
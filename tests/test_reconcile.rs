@@ -0,0 +1,122 @@
+mod common;
+
+use common::{encoded_transaction, rpc_result, MockRpc};
+use serde_json::{json, Value};
+use solana_sdk::{commitment_config::CommitmentConfig, signature::Signature, transaction::TransactionError};
+use solana_transaction_status::{TransactionConfirmationStatus, TransactionStatus};
+use solana_wallet_analyzer::{
+    ingestion::{
+        client::{ClientConfig, SolanaClient},
+        decoders::DecoderRegistry,
+        reconcile::{verdict, Verdict},
+    },
+    types::TradeInfo,
+};
+use std::str::FromStr;
+use tokio::time::Duration;
+
+fn trade(slot: u64) -> TradeInfo {
+    TradeInfo {
+        slot,
+        provisional: true,
+        ..Default::default()
+    }
+}
+
+fn status(slot: u64, confirmation_status: TransactionConfirmationStatus) -> TransactionStatus {
+    let finalized = confirmation_status == TransactionConfirmationStatus::Finalized;
+    TransactionStatus {
+        slot,
+        confirmations: if finalized { None } else { Some(10) },
+        status: Ok(()),
+        err: None,
+        confirmation_status: Some(confirmation_status),
+    }
+}
+
+#[test]
+fn test_verdicts() {
+    let finalized = status(100, TransactionConfirmationStatus::Finalized);
+
+    // Slots past the finalized one are left alone
+    assert_eq!(verdict(&trade(101), 100, None), Verdict::Pending);
+
+    assert_eq!(verdict(&trade(100), 100, Some(&finalized)), Verdict::Finalized);
+    assert_eq!(verdict(&trade(100), 100, None), Verdict::Reverted);
+    assert_eq!(verdict(&trade(99), 100, Some(&finalized)), Verdict::Moved(100));
+    assert_eq!(
+        verdict(&trade(100), 100, Some(&status(100, TransactionConfirmationStatus::Confirmed))),
+        Verdict::Pending,
+    );
+
+    let failed = TransactionStatus {
+        err: Some(TransactionError::AccountInUse),
+        status: Err(TransactionError::AccountInUse),
+        ..finalized
+    };
+    assert_eq!(verdict(&trade(100), 100, Some(&failed)), Verdict::Reverted);
+}
+
+fn client(url: &str, commitment: CommitmentConfig) -> SolanaClient {
+    SolanaClient::with_config(url, DecoderRegistry::default(), ClientConfig {
+        requests_per_second: 0.0,
+        commitment,
+        ..ClientConfig::default()
+    }).unwrap()
+}
+
+#[tokio::test]
+async fn test_signature_statuses_search_history() {
+    let known = Signature::new_unique();
+    let rpc = MockRpc::start(Duration::ZERO, move |request: &Value| {
+        assert_eq!(request["method"], "getSignatureStatuses");
+        assert_eq!(request["params"][1]["searchTransactionHistory"], true);
+        let statuses: Vec<Value> = request["params"][0].as_array().unwrap().iter()
+            .map(|signature| match Signature::from_str(signature.as_str().unwrap()).unwrap() == known {
+                true => json!({
+                    "slot": 42,
+                    "confirmations": null,
+                    "status": { "Ok": null },
+                    "err": null,
+                    "confirmationStatus": "finalized",
+                }),
+                false => Value::Null,
+            })
+            .collect();
+        rpc_result(request, json!({ "context": { "slot": 50 }, "value": statuses }))
+    }).await;
+
+    let statuses = client(&rpc.url, CommitmentConfig::confirmed())
+        .get_signature_statuses(&[known.to_string(), Signature::new_unique().to_string()])
+        .await
+        .unwrap();
+
+    assert_eq!(statuses.len(), 2);
+    assert_eq!(statuses[0].as_ref().unwrap().slot, 42);
+    assert!(statuses[1].is_none());
+}
+
+#[tokio::test]
+async fn test_requests_use_configured_commitment() {
+    let rpc = MockRpc::start(Duration::ZERO, |request: &Value| {
+        assert_eq!(request["params"][1]["commitment"], "finalized");
+        let signature = Signature::from_str(request["params"][0].as_str().unwrap()).unwrap();
+        rpc_result(request, encoded_transaction(&signature, 1))
+    }).await;
+    let client = client(&rpc.url, CommitmentConfig::finalized());
+
+    let transactions = client.fetch_transactions(&[Signature::new_unique().to_string()]).await.unwrap();
+
+    assert_eq!(transactions.len(), 1);
+    assert!(!client.is_provisional());
+}
+
+#[test]
+fn test_trades_below_finalized_are_provisional() {
+    let confirmed = client("http://127.0.0.1:8899", CommitmentConfig::confirmed());
+    let finalized = client("http://127.0.0.1:8899", CommitmentConfig::finalized());
+
+    assert!(confirmed.is_provisional());
+    assert!(!finalized.is_provisional());
+    assert!(!SolanaClient::offline(DecoderRegistry::default()).is_provisional());
+}
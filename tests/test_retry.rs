@@ -0,0 +1,166 @@
+mod common;
+
+use common::{encoded_transaction, http_error, rpc_result, MockRpc};
+use hyper::{Body, Response, StatusCode};
+use serde_json::{json, Value};
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use solana_client::rpc_request::{RpcError, RpcResponseErrorData};
+use solana_sdk::signature::Signature;
+use solana_wallet_analyzer::{
+    error::AnalyzerError,
+    ingestion::{
+        client::{ClientConfig, SolanaClient},
+        decoders::DecoderRegistry,
+        retry::RetryPolicy,
+        sender::{parse_retry_after, HttpStatusError},
+    },
+};
+use std::io;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::time::{Duration, Instant};
+
+/// Fails the first `failures` requests with `error`, then serves the transaction.
+fn failing_then_ok<F>(failures: usize, error: F) -> impl Fn(&Value) -> Response<Body>
+where
+    F: Fn(&Value) -> Response<Body>,
+{
+    let seen = AtomicUsize::new(0);
+    move |request| {
+        if seen.fetch_add(1, Ordering::SeqCst) < failures {
+            return error(request);
+        }
+        let signature = Signature::from_str(request["params"][0].as_str().unwrap()).unwrap();
+        rpc_result(request, encoded_transaction(&signature, 1))
+    }
+}
+
+fn client(url: &str, max_attempts: u32) -> SolanaClient {
+    client_with_max_delay(url, max_attempts, Duration::from_millis(10))
+}
+
+fn client_with_max_delay(url: &str, max_attempts: u32, max_delay: Duration) -> SolanaClient {
+    SolanaClient::with_config(url, DecoderRegistry::default(), ClientConfig {
+        max_concurrent_requests: 1,
+        requests_per_second: 0.0,
+        burst: 1,
+        retry: RetryPolicy {
+            max_attempts,
+            base_delay: Duration::from_millis(1),
+            max_delay,
+        },
+        ..ClientConfig::default()
+    }).unwrap()
+}
+
+fn client_error(status: u16, retry_after: Option<Duration>) -> ClientError {
+    ClientErrorKind::Io(io::Error::other(HttpStatusError { status, retry_after })).into()
+}
+
+fn rpc_error(code: i64) -> ClientError {
+    RpcError::RpcResponseError { code, message: "error".into(), data: RpcResponseErrorData::Empty }.into()
+}
+
+#[tokio::test]
+async fn test_rate_limited_request_waits_for_retry_after() {
+    let rpc = MockRpc::start(Duration::ZERO, failing_then_ok(1, |_| {
+        Response::builder()
+            .status(StatusCode::TOO_MANY_REQUESTS)
+            .header("Retry-After", "1")
+            .body(Body::empty())
+            .unwrap()
+    })).await;
+    let client = client_with_max_delay(&rpc.url, 3, Duration::from_secs(5));
+
+    let started = Instant::now();
+    let transactions = client.fetch_transactions(&[Signature::new_unique().to_string()]).await.unwrap();
+
+    assert_eq!(transactions.len(), 1);
+    assert_eq!(rpc.request_count(), 2);
+    assert!(started.elapsed() >= Duration::from_secs(1));
+}
+
+#[tokio::test]
+async fn test_server_errors_are_retried() {
+    let rpc = MockRpc::start(Duration::ZERO, failing_then_ok(2, |_| http_error(StatusCode::SERVICE_UNAVAILABLE))).await;
+    let client = client(&rpc.url, 3);
+
+    let transactions = client.fetch_transactions(&[Signature::new_unique().to_string()]).await.unwrap();
+
+    assert_eq!(transactions.len(), 1);
+    assert_eq!(rpc.request_count(), 3);
+}
+
+#[tokio::test]
+async fn test_permanent_errors_are_not_retried() {
+    let rpc = MockRpc::start(Duration::ZERO, |request| {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "error": { "code": -32602, "message": "Invalid params" },
+            "id": request["id"],
+        });
+        Response::new(Body::from(body.to_string()))
+    }).await;
+    let client = client(&rpc.url, 3);
+
+    // The transaction is skipped rather than failing the batch
+    let transactions = client.fetch_transactions(&[Signature::new_unique().to_string()]).await.unwrap();
+
+    assert!(transactions.is_empty());
+    assert_eq!(rpc.request_count(), 1);
+}
+
+#[tokio::test]
+async fn test_exhausted_retries_fail_the_batch() {
+    let rpc = MockRpc::start(Duration::ZERO, |_| http_error(StatusCode::BAD_GATEWAY)).await;
+    let client = client(&rpc.url, 3);
+
+    let error = client.fetch_transactions(&[Signature::new_unique().to_string()]).await.unwrap_err();
+
+    assert!(matches!(error, AnalyzerError::TransientError(_)));
+    assert_eq!(rpc.request_count(), 3);
+}
+
+#[test]
+fn test_client_errors_are_classified() {
+    let error = AnalyzerError::from_client_error(&client_error(429, Some(Duration::from_secs(7))));
+    assert!(matches!(error, AnalyzerError::RateLimitError(..)));
+    assert_eq!(error.retry_after(), Some(Duration::from_secs(7)));
+
+    assert!(matches!(AnalyzerError::from_client_error(&client_error(503, None)), AnalyzerError::TransientError(_)));
+    assert!(matches!(AnalyzerError::from_client_error(&client_error(401, None)), AnalyzerError::SolanaClientError(_)));
+    assert!(matches!(AnalyzerError::from_client_error(&rpc_error(-32005)), AnalyzerError::TransientError(_)));
+    assert!(matches!(AnalyzerError::from_client_error(&rpc_error(-32602)), AnalyzerError::SolanaClientError(_)));
+
+    let network = ClientErrorKind::Io(io::Error::new(io::ErrorKind::ConnectionReset, "reset")).into();
+    assert!(AnalyzerError::from_client_error(&network).is_retryable());
+    assert!(!AnalyzerError::ParseError("bad".into()).is_retryable());
+}
+
+#[test]
+fn test_parse_retry_after() {
+    assert_eq!(parse_retry_after("3"), Some(Duration::from_secs(3)));
+    assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), Some(Duration::ZERO));
+    assert_eq!(parse_retry_after("soon"), None);
+}
+
+#[test]
+fn test_backoff_doubles_up_to_max_delay() {
+    let policy = RetryPolicy {
+        max_attempts: 10,
+        base_delay: Duration::from_millis(100),
+        max_delay: Duration::from_secs(1),
+    };
+    assert_eq!(policy.backoff_ceiling(1), Duration::from_millis(100));
+    assert_eq!(policy.backoff_ceiling(3), Duration::from_millis(400));
+    assert_eq!(policy.backoff_ceiling(8), Duration::from_secs(1));
+
+    let error = AnalyzerError::TransientError("busy".into());
+    assert!(policy.delay(3, &error) <= Duration::from_millis(400));
+
+    // A server asking for a longer wait is held to max_delay too
+    let rate_limited = AnalyzerError::from_client_error(&client_error(429, Some(Duration::from_secs(3600))));
+    assert_eq!(policy.delay(1, &rate_limited), Duration::from_secs(1));
+    let rate_limited = AnalyzerError::from_client_error(&client_error(429, Some(Duration::from_millis(500))));
+    assert_eq!(policy.delay(1, &rate_limited), Duration::from_millis(500));
+}
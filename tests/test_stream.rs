@@ -0,0 +1,207 @@
+mod common;
+
+use common::{http_error, rpc_result, MockRpc};
+use hyper::StatusCode;
+use futures::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use solana_wallet_analyzer::ingestion::{
+    client::{ClientConfig, SolanaClient},
+    decoders::DecoderRegistry,
+    retry::RetryPolicy,
+    stream::{recv_batch, ws_url_from_rpc, LogStream},
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio::time::{timeout, Duration};
+use tokio_tungstenite::{accept_async, tungstenite::Message};
+
+fn notification(subscription: u64, slot: u64, signature: &str, err: Value) -> Message {
+    Message::Text(json!({
+        "jsonrpc": "2.0",
+        "method": "logsNotification",
+        "params": {
+            "result": {
+                "context": { "slot": slot },
+                "value": { "signature": signature, "err": err, "logs": [] }
+            },
+            "subscription": subscription
+        }
+    }).to_string())
+}
+
+/// Stands in for a validator's PubSub endpoint: every connection answers one
+/// `logsSubscribe`, emits its notifications and then drops the socket.
+async fn serve_pubsub(listener: TcpListener, connections: Vec<Vec<(u64, &'static str, Value)>>) {
+    for notifications in connections {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut ws = accept_async(socket).await.unwrap();
+
+        let request = loop {
+            if let Message::Text(text) = ws.next().await.unwrap().unwrap() {
+                break serde_json::from_str::<Value>(&text).unwrap();
+            }
+        };
+        assert_eq!(request["method"], "logsSubscribe");
+        let subscription = 42;
+        ws.send(Message::Text(json!({
+            "jsonrpc": "2.0",
+            "result": subscription,
+            "id": request["id"]
+        }).to_string())).await.unwrap();
+
+        for (slot, signature, err) in notifications {
+            ws.send(notification(subscription, slot, signature, err)).await.unwrap();
+        }
+        ws.close(None).await.unwrap();
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_log_stream_resubscribes_after_disconnect() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let ws_url = format!("ws://{}", listener.local_addr().unwrap());
+    tokio::spawn(serve_pubsub(listener, vec![
+        vec![
+            (5, "failed", json!({ "InstructionError": [0, "InvalidArgument"] })),
+            (5, "first", Value::Null),
+        ],
+        vec![(6, "second", Value::Null)],
+    ]));
+
+    // Gap fill goes to an unreachable RPC node and is expected to only warn
+    let client = Arc::new(SolanaClient::new("http://127.0.0.1:1").unwrap());
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let handle = LogStream::new(&ws_url, vec![Pubkey::new_unique()], client).spawn(sender);
+
+    let mut received = Vec::new();
+    for _ in 0..3 {
+        let signature = timeout(Duration::from_secs(10), receiver.recv()).await
+            .expect("timed out waiting for a signature")
+            .unwrap();
+        received.push(signature);
+    }
+    // Failed transactions are forwarded too, for their fees
+    assert_eq!(received, vec!["failed", "first", "second"]);
+
+    handle.abort();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_log_stream_recovers_signatures_missed_while_reconnecting() {
+    let signatures: Vec<&'static str> = (0..4)
+        .map(|_| &*Box::leak(Signature::new_unique().to_string().into_boxed_str()))
+        .collect();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let ws_url = format!("ws://{}", listener.local_addr().unwrap());
+    tokio::spawn(serve_pubsub(listener, vec![
+        vec![(5, signatures[0], Value::Null)],
+        vec![(8, signatures[3], Value::Null)],
+    ]));
+
+    let gap = signatures.clone();
+    let rpc = MockRpc::start(Duration::ZERO, move |request: &Value| {
+        assert_eq!(request["method"], "getSignaturesForAddress");
+        // Only the signatures between the two subscriptions are asked for
+        assert_eq!(request["params"][1]["before"], gap[3]);
+        assert_eq!(request["params"][1]["until"], gap[0]);
+        rpc_result(request, json!([
+            { "signature": gap[2], "slot": 7, "err": null, "memo": null, "blockTime": null },
+            { "signature": gap[1], "slot": 6, "err": null, "memo": null, "blockTime": null },
+        ]))
+    }).await;
+    let client = Arc::new(SolanaClient::with_config(&rpc.url, DecoderRegistry::default(), ClientConfig {
+        requests_per_second: 0.0,
+        ..ClientConfig::default()
+    }).unwrap());
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let handle = LogStream::new(&ws_url, vec![Pubkey::new_unique()], client).spawn(sender);
+
+    let mut received = Vec::new();
+    for _ in 0..4 {
+        let signature = timeout(Duration::from_secs(10), receiver.recv()).await
+            .expect("timed out waiting for a signature")
+            .unwrap();
+        received.push(signature);
+    }
+    assert_eq!(received, signatures);
+    assert_eq!(rpc.request_count(), 1);
+
+    handle.abort();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_log_stream_retries_a_failed_gap_fill_with_the_next_signature() {
+    let signatures: Vec<&'static str> = (0..4)
+        .map(|_| &*Box::leak(Signature::new_unique().to_string().into_boxed_str()))
+        .collect();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let ws_url = format!("ws://{}", listener.local_addr().unwrap());
+    tokio::spawn(serve_pubsub(listener, vec![
+        vec![(5, signatures[0], Value::Null)],
+        vec![(7, signatures[2], Value::Null), (8, signatures[3], Value::Null)],
+    ]));
+
+    let gap = signatures.clone();
+    let attempts = AtomicUsize::new(0);
+    let rpc = MockRpc::start(Duration::ZERO, move |request: &Value| {
+        if attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+            return http_error(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+        // Still from the last signature seen before the reconnect
+        assert_eq!(request["params"][1]["before"], gap[3]);
+        assert_eq!(request["params"][1]["until"], gap[0]);
+        rpc_result(request, json!([
+            { "signature": gap[2], "slot": 7, "err": null, "memo": null, "blockTime": null },
+            { "signature": gap[1], "slot": 6, "err": null, "memo": null, "blockTime": null },
+        ]))
+    }).await;
+    let client = Arc::new(SolanaClient::with_config(&rpc.url, DecoderRegistry::default(), ClientConfig {
+        requests_per_second: 0.0,
+        retry: RetryPolicy { max_attempts: 1, ..RetryPolicy::default() },
+        ..ClientConfig::default()
+    }).unwrap());
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let handle = LogStream::new(&ws_url, vec![Pubkey::new_unique()], client).spawn(sender);
+
+    let mut received = Vec::new();
+    for _ in 0..5 {
+        let signature = timeout(Duration::from_secs(10), receiver.recv()).await
+            .expect("timed out waiting for a signature")
+            .unwrap();
+        received.push(signature);
+    }
+    // The signature streamed while the gap was open is recovered again
+    assert_eq!(received, vec![signatures[0], signatures[2], signatures[1], signatures[2], signatures[3]]);
+    assert_eq!(rpc.request_count(), 2);
+
+    handle.abort();
+}
+
+#[tokio::test]
+async fn test_recv_batch_deduplicates_within_window() {
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    for signature in ["a", "b", "a", "c"] {
+        sender.send(signature.to_string()).unwrap();
+    }
+
+    let batch = recv_batch(&mut receiver, 10, Duration::from_millis(50)).await.unwrap();
+    assert_eq!(batch, vec!["a", "b", "c"]);
+
+    sender.send("d".to_string()).unwrap();
+    sender.send("e".to_string()).unwrap();
+    let batch = recv_batch(&mut receiver, 1, Duration::from_millis(50)).await.unwrap();
+    assert_eq!(batch, vec!["d"]);
+
+    drop(sender);
+    assert_eq!(recv_batch(&mut receiver, 10, Duration::from_millis(50)).await.unwrap(), vec!["e"]);
+    assert!(recv_batch(&mut receiver, 10, Duration::from_millis(50)).await.is_none());
+}
+
+#[test]
+fn test_ws_url_from_rpc() {
+    assert_eq!(ws_url_from_rpc("https://api.mainnet-beta.solana.com"), "wss://api.mainnet-beta.solana.com");
+    assert_eq!(ws_url_from_rpc("http://127.0.0.1:8899"), "ws://127.0.0.1:8899");
+}
@@ -0,0 +1,98 @@
+mod common;
+
+use common::{raydium_transaction, token_balance};
+use serde_json::json;
+use solana_sdk::{
+    hash::Hash,
+    message::Message,
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::Transaction,
+};
+use solana_transaction_status::{Encodable, EncodedConfirmedTransactionWithStatusMeta, UiTransactionEncoding};
+use solana_wallet_analyzer::ingestion::{
+    balances::{owner_balance_changes, BalanceChange, WRAPPED_SOL_MINT},
+    client::{SolanaClient, TOKEN_2022_PROGRAM_ID},
+    decoders::DecoderRegistry,
+    transaction::{TransactionMeta, TransactionWithMeta},
+};
+
+/// A Token-2022 mint with 6 decimals.
+const TOKEN_2022_MINT: &str = "2b1kV6DkPAnxd5ixfnxCpjxmKwqjjaYmCZfHsFu24GXo";
+/// Rent of a Token-2022 account carrying the `TransferFeeAmount` extension.
+const EXTENDED_ACCOUNT_RENT: u64 = 2_074_080;
+
+#[test]
+fn test_buy_receives_amount_net_of_transfer_fee() {
+    let wallet = Pubkey::new_unique();
+    // 100 tokens leave the pool; a 1% transfer fee is withheld in the new account
+    let tx = raydium_transaction(&wallet, TransactionMeta {
+        fee: 5_000,
+        pre_balances: vec![2_000_000_000, 0, 0, 1],
+        post_balances: vec![2_000_000_000 - 500_000_000 - EXTENDED_ACCOUNT_RENT - 5_000, EXTENDED_ACCOUNT_RENT, 0, 1],
+        post_token_balances: vec![token_balance(1, TOKEN_2022_MINT, &wallet, 99_000_000, 6)],
+        ..Default::default()
+    });
+
+    let trade = SolanaClient::offline(DecoderRegistry::default()).extract_trade_info(&tx).unwrap();
+
+    assert_eq!(trade.token_in_mint, WRAPPED_SOL_MINT);
+    assert_eq!(trade.token_in_amount, 0.5);
+    assert_eq!(trade.token_out_mint, TOKEN_2022_MINT);
+    assert_eq!(trade.token_out_amount, 99.0);
+}
+
+#[test]
+fn test_closing_an_extended_account_refunds_its_own_rent() {
+    let wallet = Pubkey::new_unique();
+    let tx = raydium_transaction(&wallet, TransactionMeta {
+        fee: 5_000,
+        pre_balances: vec![1_000_000_000, EXTENDED_ACCOUNT_RENT, 0, 1],
+        post_balances: vec![1_000_000_000 + 700_000_000 + EXTENDED_ACCOUNT_RENT - 5_000, 0, 0, 1],
+        pre_token_balances: vec![token_balance(1, TOKEN_2022_MINT, &wallet, 99_000_000, 6)],
+        ..Default::default()
+    });
+
+    let mut changes = owner_balance_changes(&tx, &wallet);
+    changes.sort_by(|a, b| a.mint.cmp(&b.mint));
+
+    assert_eq!(changes, vec![
+        BalanceChange { mint: TOKEN_2022_MINT.to_string(), amount: -99.0 },
+        BalanceChange { mint: WRAPPED_SOL_MINT.to_string(), amount: 0.7 },
+    ]);
+}
+
+#[test]
+fn test_interest_bearing_balances_use_raw_amounts() {
+    let wallet = Pubkey::new_unique();
+    let message = Message::new_with_compiled_instructions(
+        1, 0, 1, vec![wallet, Pubkey::new_unique()], Hash::default(), vec![],
+    );
+    let transaction = Transaction { signatures: vec![Signature::new_unique()], message };
+    // With accrued interest the UI amount is above amount / 10^decimals
+    let balance = json!({
+        "accountIndex": 1,
+        "mint": TOKEN_2022_MINT,
+        "owner": wallet.to_string(),
+        "programId": TOKEN_2022_PROGRAM_ID.to_string(),
+        "uiTokenAmount": { "amount": "100000000", "decimals": 6, "uiAmount": 105.2, "uiAmountString": "105.2" },
+    });
+    let encoded: EncodedConfirmedTransactionWithStatusMeta = serde_json::from_value(json!({
+        "slot": 1,
+        "blockTime": null,
+        "transaction": transaction.encode(UiTransactionEncoding::Base64),
+        "meta": {
+            "err": null,
+            "status": { "Ok": null },
+            "fee": 5_000,
+            "preBalances": [1_000_000, 1],
+            "postBalances": [995_000, 1],
+            "preTokenBalances": [balance],
+            "postTokenBalances": [balance],
+        },
+    })).unwrap();
+
+    let tx = TransactionWithMeta::from_encoded(encoded).unwrap();
+
+    assert_eq!(tx.meta.post_token_balances[0].amount, 100_000_000);
+}
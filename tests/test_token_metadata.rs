@@ -0,0 +1,174 @@
+mod common;
+
+use common::{rpc_result, MockRpc, BONK};
+use serde_json::{json, Value};
+use solana_sdk::{bs58, program_option::COption, program_pack::Pack, pubkey::Pubkey};
+use solana_wallet_analyzer::{
+    analysis::metrics::MetricsCalculator,
+    ingestion::{
+        balances::WRAPPED_SOL_MINT,
+        client::{ClientConfig, SolanaClient, TOKEN_PROGRAM_ID},
+        decoders::DecoderRegistry,
+        token_metadata::{metadata_address, parse_metaplex_metadata, parse_mint},
+    },
+    types::{TokenMetadata, TradeInfo},
+};
+use spl_token_2022::{
+    extension::{mint_close_authority::MintCloseAuthority, ExtensionType, StateWithExtensionsMut},
+    state::Mint,
+};
+use spl_token_metadata_interface::state::TokenMetadata as TokenMetadataExtension;
+use std::str::FromStr;
+use tokio::time::Duration;
+
+fn mint(decimals: u8, supply: u64) -> Mint {
+    Mint {
+        mint_authority: COption::None,
+        supply,
+        decimals,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    }
+}
+
+fn legacy_mint(decimals: u8, supply: u64) -> Vec<u8> {
+    let mut data = vec![0; Mint::LEN];
+    mint(decimals, supply).pack_into_slice(&mut data);
+    data
+}
+
+/// A Token-2022 mint with a close authority and an embedded metadata extension.
+fn token_2022_mint(address: &Pubkey) -> Vec<u8> {
+    let metadata = TokenMetadataExtension {
+        mint: *address,
+        name: "Paypal USD".to_string(),
+        symbol: "PYUSD".to_string(),
+        ..Default::default()
+    };
+    let len = ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::MintCloseAuthority]).unwrap()
+        + metadata.tlv_size_of().unwrap();
+    let mut data = vec![0; len];
+    let mut state = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut data).unwrap();
+    state.init_extension::<MintCloseAuthority>(true).unwrap();
+    state.init_variable_len_extension(&metadata, false).unwrap();
+    state.base = mint(6, 1_000_000);
+    state.pack_base();
+    state.init_account_type().unwrap();
+    data
+}
+
+/// A Metaplex metadata account, with the name and symbol padded as on chain.
+fn metaplex_metadata(mint: &Pubkey, name: &str, symbol: &str) -> Vec<u8> {
+    let mut data = vec![4];
+    data.extend_from_slice(Pubkey::new_unique().as_ref());
+    data.extend_from_slice(mint.as_ref());
+    for (value, padded_len) in [(name, 32), (symbol, 10), ("https://example.com", 200)] {
+        let mut bytes = value.as_bytes().to_vec();
+        bytes.resize(padded_len, 0);
+        data.extend_from_slice(&(padded_len as u32).to_le_bytes());
+        data.extend_from_slice(&bytes);
+    }
+    data
+}
+
+#[test]
+fn test_parse_legacy_mint() {
+    let bonk = Pubkey::from_str(BONK).unwrap();
+    let metadata = parse_mint(&bonk, &TOKEN_PROGRAM_ID, &legacy_mint(5, 42)).unwrap();
+
+    assert_eq!(metadata, TokenMetadata {
+        mint: BONK.to_string(),
+        decimals: 5,
+        supply: 42,
+        ..Default::default()
+    });
+    assert_eq!(metadata.label(), "DezXAZ8z");
+    assert_eq!(metadata.ui_amount(150_000), 1.5);
+}
+
+#[test]
+fn test_parse_token_2022_mint_with_extensions() {
+    let address = Pubkey::new_unique();
+    let metadata = parse_mint(&address, &spl_token_2022::id(), &token_2022_mint(&address)).unwrap();
+
+    assert!(metadata.token_2022);
+    assert_eq!(metadata.decimals, 6);
+    assert_eq!(metadata.extensions, vec!["MintCloseAuthority", "TokenMetadata"]);
+    assert_eq!((metadata.name.as_str(), metadata.symbol.as_str()), ("Paypal USD", "PYUSD"));
+}
+
+#[test]
+fn test_parse_mint_rejects_other_owners() {
+    let address = Pubkey::new_unique();
+    assert!(parse_mint(&address, &Pubkey::new_unique(), &legacy_mint(5, 42)).is_err());
+}
+
+#[test]
+fn test_parse_metaplex_metadata_strips_padding() {
+    let data = metaplex_metadata(&Pubkey::new_unique(), "Bonk", "Bonk");
+    assert_eq!(parse_metaplex_metadata(&data).unwrap(), ("Bonk".to_string(), "Bonk".to_string()));
+
+    assert!(parse_metaplex_metadata(&data[..80]).is_err());
+}
+
+fn account(owner: &Pubkey, data: &[u8]) -> Value {
+    json!({
+        "data": [bs58::encode(data).into_string(), "base58"],
+        "executable": false,
+        "lamports": 1_461_600,
+        "owner": owner.to_string(),
+        "rentEpoch": 0,
+        "space": data.len(),
+    })
+}
+
+#[tokio::test]
+async fn test_fetch_token_metadata_combines_mint_and_metaplex_accounts() {
+    let bonk = Pubkey::from_str(BONK).unwrap();
+    let missing = Pubkey::new_unique();
+    let rpc = MockRpc::start(Duration::ZERO, move |request: &Value| {
+        assert_eq!(request["method"], "getMultipleAccounts");
+        let keys: Vec<_> = request["params"][0].as_array().unwrap().iter()
+            .map(|key| Pubkey::from_str(key.as_str().unwrap()).unwrap())
+            .collect();
+        assert_eq!(keys, vec![bonk, missing, metadata_address(&bonk), metadata_address(&missing)]);
+        let metaplex = Pubkey::from_str("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s").unwrap();
+        rpc_result(request, json!({
+            "context": { "slot": 1 },
+            "value": [
+                account(&TOKEN_PROGRAM_ID, &legacy_mint(5, 42)),
+                null,
+                account(&metaplex, &metaplex_metadata(&bonk, "Bonk", "Bonk")),
+                null,
+            ],
+        }))
+    }).await;
+    let client = SolanaClient::with_config(&rpc.url, DecoderRegistry::default(), ClientConfig {
+        requests_per_second: 0.0,
+        ..ClientConfig::default()
+    }).unwrap();
+
+    let tokens = client.fetch_token_metadata(&[bonk, missing]).await.unwrap();
+
+    assert_eq!(tokens.len(), 1);
+    assert_eq!(tokens[0].mint, BONK);
+    assert_eq!(tokens[0].decimals, 5);
+    assert_eq!(tokens[0].label(), "Bonk");
+}
+
+#[test]
+fn test_metrics_name_the_most_traded_token() {
+    let trade = |mint: &str, symbol: &str| TradeInfo {
+        wallet_address: "wallet".to_string(),
+        token_in_mint: WRAPPED_SOL_MINT.to_string(),
+        token_in_symbol: "SOL".to_string(),
+        token_out_mint: mint.to_string(),
+        token_out_symbol: symbol.to_string(),
+        ..Default::default()
+    };
+    let trades = vec![trade(BONK, "Bonk"), trade("unlabeled", ""), trade(BONK, "")];
+
+    let metrics = MetricsCalculator::new().calculate_metrics(&trades).unwrap();
+
+    assert_eq!(metrics.most_traded_token, "Bonk");
+}
@@ -0,0 +1,265 @@
+mod common;
+
+use common::{raydium_transaction, token_balance, BONK, RAYDIUM, USDC};
+use solana_sdk::{
+    hash::Hash,
+    instruction::CompiledInstruction,
+    message::{v0, Message, MessageHeader, VersionedMessage},
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::VersionedTransaction,
+};
+use solana_wallet_analyzer::ingestion::{
+    balances::WRAPPED_SOL_MINT,
+    client::SolanaClient,
+    decoders::{DecoderRegistry, SwapDecoder},
+    transaction::{InnerInstructions, TransactionMeta, TransactionWithMeta},
+};
+use solana_sdk::message::v0::{LoadedAddresses, MessageAddressTableLookup};
+use std::str::FromStr;
+
+const JUPITER: &str = "JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4";
+const WHIRLPOOL: &str = "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc";
+
+/// Wallet buys 1,000 BONK for 0.5 SOL through Raydium, opening a new BONK account.
+fn buy_transaction(wallet: &Pubkey) -> TransactionWithMeta {
+    TransactionWithMeta {
+        slot: 250_000_000,
+        transaction_index: Some(7),
+        ..raydium_transaction(wallet, TransactionMeta {
+            fee: 5_000,
+            pre_balances: vec![2_000_000_000, 0, 0, 1],
+            post_balances: vec![1_497_955_720, 2_039_280, 0, 1],
+            post_token_balances: vec![token_balance(1, BONK, wallet, 100_000_000, 5)],
+            ..Default::default()
+        })
+    }
+}
+
+fn legacy_message(tx: &mut TransactionWithMeta) -> &mut Message {
+    match &mut tx.transaction.message {
+        VersionedMessage::Legacy(message) => message,
+        VersionedMessage::V0(_) => panic!("not a legacy transaction"),
+    }
+}
+
+#[test]
+fn test_extract_trade_from_balance_changes() {
+    let client = SolanaClient::new("http://127.0.0.1:8899").unwrap();
+    let wallet = Pubkey::new_unique();
+
+    let trade = client.extract_trade_info(&buy_transaction(&wallet)).unwrap();
+
+    assert_eq!(trade.wallet_address, wallet.to_string());
+    assert_eq!(trade.token_in_mint, WRAPPED_SOL_MINT);
+    assert_eq!(trade.token_in_amount, 0.5);
+    assert_eq!(trade.token_out_mint, BONK);
+    assert_eq!(trade.token_out_amount, 1_000.0);
+    assert_eq!(trade.amount, 0.5);
+    assert_eq!(trade.venue, "raydium-amm-v4");
+    assert_eq!(trade.decoder_version, "1");
+    assert_eq!(trade.timestamp, 1_700_000_000);
+    assert_eq!(trade.slot, 250_000_000);
+    assert_eq!(trade.transaction_index, Some(7));
+    assert!(trade.provisional);
+}
+
+#[test]
+fn test_missing_block_time_falls_back_to_wall_clock() {
+    let client = SolanaClient::new("http://127.0.0.1:8899").unwrap();
+    let mut tx = buy_transaction(&Pubkey::new_unique());
+    tx.block_time = None;
+
+    let before = chrono::Utc::now().timestamp();
+    let trade = client.extract_trade_info(&tx).unwrap();
+
+    assert!(trade.timestamp >= before);
+    assert_eq!(trade.slot, 250_000_000);
+}
+
+#[test]
+fn test_non_dex_transaction_is_ignored() {
+    let client = SolanaClient::new("http://127.0.0.1:8899").unwrap();
+    let wallet = Pubkey::new_unique();
+
+    let mut tx = buy_transaction(&wallet);
+    legacy_message(&mut tx).account_keys[3] = Pubkey::new_unique();
+
+    assert!(client.extract_trade_info(&tx).is_none());
+}
+
+#[test]
+fn test_non_swap_instruction_is_ignored() {
+    let client = SolanaClient::new("http://127.0.0.1:8899").unwrap();
+    let wallet = Pubkey::new_unique();
+
+    // Raydium AMM v4 Deposit
+    let mut tx = buy_transaction(&wallet);
+    legacy_message(&mut tx).instructions[0].data = vec![3];
+
+    assert!(client.extract_trade_info(&tx).is_none());
+}
+
+struct TestVenueDecoder(Pubkey);
+
+impl SwapDecoder for TestVenueDecoder {
+    fn venue(&self) -> &'static str {
+        "test-venue"
+    }
+
+    fn program_id(&self) -> Pubkey {
+        self.0
+    }
+
+    fn version(&self) -> &'static str {
+        "7"
+    }
+
+    fn is_swap_instruction(&self, data: &[u8]) -> bool {
+        data == [42]
+    }
+}
+
+#[test]
+fn test_registered_decoder_handles_its_program() {
+    let venue = Pubkey::new_unique();
+    let mut registry = DecoderRegistry::new();
+    registry.register(Box::new(TestVenueDecoder(venue)));
+    let client = SolanaClient::with_decoders("http://127.0.0.1:8899", registry).unwrap();
+    let wallet = Pubkey::new_unique();
+
+    let mut tx = buy_transaction(&wallet);
+    assert!(client.extract_trade_info(&tx).is_none());
+
+    legacy_message(&mut tx).account_keys[3] = venue;
+    legacy_message(&mut tx).instructions[0].data = vec![42];
+    let trade = client.extract_trade_info(&tx).unwrap();
+
+    assert_eq!(trade.venue, "test-venue");
+    assert_eq!(trade.decoder_version, "7");
+}
+
+fn jupiter_swap_event(amm: &str, input: (&str, u64), output: (&str, u64)) -> Vec<u8> {
+    let mut data = vec![228, 69, 165, 46, 81, 203, 154, 29, 64, 198, 205, 232, 38, 8, 113, 226];
+    data.extend_from_slice(Pubkey::from_str(amm).unwrap().as_ref());
+    data.extend_from_slice(Pubkey::from_str(input.0).unwrap().as_ref());
+    data.extend_from_slice(&input.1.to_le_bytes());
+    data.extend_from_slice(Pubkey::from_str(output.0).unwrap().as_ref());
+    data.extend_from_slice(&output.1.to_le_bytes());
+    data
+}
+
+#[test]
+fn test_jupiter_route_collapses_into_one_trade() {
+    let client = SolanaClient::new("http://127.0.0.1:8899").unwrap();
+    let wallet = Pubkey::new_unique();
+
+    // SOL -> USDC on Raydium, then USDC -> BONK on Orca, all inside one Jupiter route
+    let mut tx = buy_transaction(&wallet);
+    let keys = &mut legacy_message(&mut tx).account_keys;
+    keys[3] = Pubkey::from_str(JUPITER).unwrap();
+    keys.push(Pubkey::from_str(RAYDIUM).unwrap());
+    keys.push(Pubkey::from_str(WHIRLPOOL).unwrap());
+    legacy_message(&mut tx).instructions[0].data = vec![229, 23, 203, 151, 122, 227, 173, 42];
+    tx.meta.inner_instructions = vec![InnerInstructions {
+        index: 0,
+        instructions: vec![
+            CompiledInstruction::new_from_raw_parts(4, vec![9], vec![]),
+            CompiledInstruction::new_from_raw_parts(
+                3,
+                jupiter_swap_event(RAYDIUM, (WRAPPED_SOL_MINT, 500_000_000), (USDC, 75_000_000)),
+                vec![],
+            ),
+            CompiledInstruction::new_from_raw_parts(5, vec![248, 198, 158, 145, 225, 117, 135, 200], vec![]),
+            CompiledInstruction::new_from_raw_parts(
+                3,
+                jupiter_swap_event(WHIRLPOOL, (USDC, 75_000_000), (BONK, 100_000_000)),
+                vec![],
+            ),
+        ],
+    }];
+
+    let trade = client.extract_trade_info(&tx).unwrap();
+
+    assert_eq!(trade.venue, "jupiter-v6");
+    assert_eq!(trade.token_in_mint, WRAPPED_SOL_MINT);
+    assert_eq!(trade.token_in_amount, 0.5);
+    assert_eq!(trade.token_out_mint, BONK);
+    assert_eq!(trade.token_out_amount, 1_000.0);
+
+    assert_eq!(trade.route.len(), 2);
+    assert_eq!(trade.route[0].venue, "raydium-amm-v4");
+    assert_eq!(trade.route[0].output_mint, USDC);
+    assert_eq!(trade.route[0].output_amount, 75_000_000);
+    assert_eq!(trade.route[1].venue, "orca-whirlpool");
+    assert_eq!(trade.route[1].input_mint, USDC);
+    assert_eq!(trade.route[1].output_mint, BONK);
+}
+
+/// The Raydium buy as a v0 transaction sent through an unknown router
+/// program, which calls Raydium with the pool and the Raydium program loaded
+/// from a lookup table. The loaded addresses are left unresolved.
+fn v0_buy_transaction(wallet: &Pubkey) -> (TransactionWithMeta, LoadedAddresses) {
+    let legacy = buy_transaction(wallet);
+    let bonk_account = legacy.account_keys()[1];
+    let loaded = LoadedAddresses {
+        writable: vec![Pubkey::new_unique()],
+        readonly: vec![Pubkey::from_str(RAYDIUM).unwrap()],
+    };
+    let message = v0::Message {
+        header: MessageHeader {
+            num_required_signatures: 1,
+            num_readonly_signed_accounts: 0,
+            num_readonly_unsigned_accounts: 1,
+        },
+        account_keys: vec![*wallet, bonk_account, Pubkey::new_unique()],
+        recent_blockhash: Hash::default(),
+        instructions: vec![CompiledInstruction::new_from_raw_parts(2, vec![1], vec![0, 1, 3, 4])],
+        address_table_lookups: vec![MessageAddressTableLookup {
+            account_key: Pubkey::new_unique(),
+            writable_indexes: vec![0],
+            readonly_indexes: vec![1],
+        }],
+    };
+
+    let tx = TransactionWithMeta {
+        signature: legacy.signature.clone(),
+        slot: legacy.slot,
+        block_time: legacy.block_time,
+        transaction_index: legacy.transaction_index,
+        transaction: VersionedTransaction {
+            signatures: vec![Signature::default()],
+            message: VersionedMessage::V0(message),
+        },
+        meta: TransactionMeta {
+            pre_balances: vec![2_000_000_000, 0, 1, 0, 1],
+            post_balances: vec![1_497_955_720, 2_039_280, 1, 0, 1],
+            inner_instructions: vec![InnerInstructions {
+                index: 0,
+                instructions: vec![CompiledInstruction::new_from_raw_parts(4, vec![9], vec![0, 1, 3])],
+            }],
+            ..legacy.meta.clone()
+        },
+    };
+    (tx, loaded)
+}
+
+#[test]
+fn test_v0_transaction_decodes_on_loaded_accounts() {
+    let client = SolanaClient::new("http://127.0.0.1:8899").unwrap();
+    let wallet = Pubkey::new_unique();
+    let (mut tx, loaded) = v0_buy_transaction(&wallet);
+
+    // Without its loaded addresses the Raydium call points past the static keys
+    assert!(tx.has_unresolved_lookups());
+    assert!(client.extract_trade_info(&tx).is_none());
+
+    tx.meta.loaded_addresses = loaded;
+    assert_eq!(tx.account_keys().get(4), Some(&Pubkey::from_str(RAYDIUM).unwrap()));
+    let trade = client.extract_trade_info(&tx).unwrap();
+
+    assert_eq!(trade.wallet_address, wallet.to_string());
+    assert_eq!(trade.venue, "raydium-amm-v4");
+    assert_eq!(trade.token_in_amount, 0.5);
+    assert_eq!(trade.token_out_amount, 1_000.0);
+}
@@ -0,0 +1,117 @@
+mod common;
+
+use common::{encoded_transaction, rpc_result, MockRpc};
+use serde_json::{json, Value};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use solana_wallet_analyzer::{
+    ingestion::{
+        client::{ClientConfig, SolanaClient, WalletHistoryBatch},
+        decoders::DecoderRegistry,
+        watchlist::{load_watchlist, parse_watchlist},
+    },
+    types::WatchCursor,
+};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use tokio::time::Duration;
+
+fn signature(slot: u64) -> String {
+    let mut bytes = [0u8; 64];
+    bytes[..8].copy_from_slice(&slot.to_le_bytes());
+    Signature::from(bytes).to_string()
+}
+
+fn slot_of(signature: &str) -> u64 {
+    let bytes = Signature::from_str(signature).unwrap();
+    u64::from_le_bytes(bytes.as_ref()[..8].try_into().unwrap())
+}
+
+/// Serves the wallet's history, slots `1..=newest`, the way
+/// `getSignaturesForAddress` pages it, and each of its transactions.
+fn respond(wallet: &Pubkey, newest: u64, request: &Value) -> hyper::Response<hyper::Body> {
+    let params = &request["params"];
+    match request["method"].as_str().unwrap() {
+        "getSignaturesForAddress" => {
+            assert_eq!(params[0], wallet.to_string());
+            let before = params[1]["before"].as_str().map(slot_of).unwrap_or(newest + 1);
+            let until = params[1]["until"].as_str().map(slot_of).unwrap_or(0);
+            let limit = params[1]["limit"].as_u64().unwrap();
+            let page: Vec<Value> = (until + 1..before).rev().take(limit as usize)
+                .map(|slot| json!({ "signature": signature(slot), "slot": slot, "err": null, "memo": null, "blockTime": null }))
+                .collect();
+            rpc_result(request, json!(page))
+        }
+        "getTransaction" => {
+            let signature = params[0].as_str().unwrap();
+            rpc_result(request, encoded_transaction(&Signature::from_str(signature).unwrap(), slot_of(signature)))
+        }
+        method => panic!("unexpected method {}", method),
+    }
+}
+
+#[tokio::test]
+async fn test_wallet_history_is_synced_in_both_directions() {
+    let wallet = Pubkey::new_unique();
+    let newest = Arc::new(Mutex::new(3));
+    let handler_newest = Arc::clone(&newest);
+    let rpc = MockRpc::start(Duration::ZERO, move |request| {
+        respond(&wallet, *handler_newest.lock().unwrap(), request)
+    }).await;
+    let client = SolanaClient::with_config(&rpc.url, DecoderRegistry::default(), ClientConfig {
+        requests_per_second: 0.0,
+        ..ClientConfig::default()
+    }).unwrap();
+    let slots = |batch: &WalletHistoryBatch| {
+        batch.transactions.iter().map(|tx| tx.slot).collect::<Vec<_>>()
+    };
+
+    // The latest transactions come first, then the walk back reaches the start
+    let batch = client.get_wallet_transactions(&wallet, &WatchCursor::default(), 2).await.unwrap();
+    assert_eq!(slots(&batch), vec![2, 3]);
+    assert!(batch.more);
+    assert!(!batch.cursor.complete);
+
+    let batch = client.get_wallet_transactions(&wallet, &batch.cursor, 2).await.unwrap();
+    assert_eq!(slots(&batch), vec![1]);
+    assert!(!batch.more);
+    assert!(batch.cursor.complete);
+    assert_eq!(batch.cursor.oldest.as_ref().unwrap().slot, 1);
+
+    // New activity is picked up down to the newest signature, walking back
+    // from the tip a batch at a time
+    *newest.lock().unwrap() = 6;
+    let batch = client.get_wallet_transactions(&wallet, &batch.cursor, 2).await.unwrap();
+    assert_eq!(slots(&batch), vec![5, 6]);
+    assert!(batch.more);
+    assert_eq!(batch.cursor.newest.as_ref().unwrap().slot, 3);
+
+    let batch = client.get_wallet_transactions(&wallet, &batch.cursor, 2).await.unwrap();
+    assert_eq!(slots(&batch), vec![4]);
+    assert!(!batch.more);
+    assert_eq!(batch.cursor.newest.unwrap().slot, 6);
+}
+
+#[test]
+fn test_parse_watchlist() {
+    let first = Pubkey::new_unique();
+    let second = Pubkey::new_unique();
+    let contents = format!("# traders\n{}\n\n  {}  # bot\n", first, second);
+
+    assert_eq!(parse_watchlist(&contents).unwrap(), vec![first, second]);
+
+    let error = parse_watchlist(&format!("{}\nnot-an-address\n", first)).unwrap_err();
+    assert!(error.to_string().contains("Line 2"), "{}", error);
+}
+
+#[test]
+fn test_load_watchlist_merges_flags_and_file() {
+    let first = Pubkey::new_unique();
+    let second = Pubkey::new_unique();
+    let path = std::env::temp_dir().join(format!("watchlist-{}.txt", Pubkey::new_unique()));
+    std::fs::write(&path, format!("{}\n{}\n", second, first)).unwrap();
+
+    let watchlist = load_watchlist(&[first], Some(&path)).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(watchlist, vec![first, second]);
+}